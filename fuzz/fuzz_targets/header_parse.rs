@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libppm::header::{PamHeader, PnmHeader};
+
+fuzz_target!(|data: &[u8]| {
+    // A parsed header must encode to bytes that parse back to the same header
+    if let Ok((header, _)) = PnmHeader::parse(data) {
+        let (parsed, _) = PnmHeader::parse(&header.encode(&[])).unwrap();
+        assert_eq!(parsed, header);
+    }
+
+    if let Ok((header, _)) = PamHeader::parse(data) {
+        let (parsed, _) = PamHeader::parse(&header.encode(&[])).unwrap();
+        assert_eq!(parsed, header);
+    }
+});
@@ -0,0 +1,24 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libppm::pbm::{decode, Encoding, PBMImage};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(bitmap) = decode(data) else {
+        return;
+    };
+
+    // Anything that decodes must survive a round trip through the encoder
+    for encoding in [Encoding::Raw, Encoding::Plain] {
+        let mut bytes: Vec<u8> = Vec::new();
+        PBMImage::builder()
+            .image(&bitmap)
+            .filename("unused.pbm")
+            .encoding(encoding)
+            .build()
+            .unwrap()
+            .write_to(&mut bytes)
+            .unwrap();
+        assert_eq!(decode(&bytes).unwrap(), bitmap);
+    }
+});
@@ -0,0 +1,21 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libppm::ppm::{decode, PPMImage};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(image) = decode(data) else {
+        return;
+    };
+
+    // Anything that decodes must survive a round trip through the encoder
+    let mut bytes: Vec<u8> = Vec::new();
+    PPMImage::builder()
+        .image(&image)
+        .filename("unused.ppm")
+        .build()
+        .unwrap()
+        .write_to(&mut bytes)
+        .unwrap();
+    assert_eq!(decode(&bytes).unwrap(), image);
+});
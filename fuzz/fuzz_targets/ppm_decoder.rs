@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libppm::ppm::{DecodeMode, PPMDecoder};
+
+fuzz_target!(|data: &[u8]| {
+    for mode in [DecodeMode::Strict, DecodeMode::Lenient] {
+        // Lenient decoding pads short rasters up to the limits, so keep them small for speed
+        let _ = PPMDecoder::new()
+            .mode(mode)
+            .max_pixels(1 << 20)
+            .decode(data);
+    }
+});
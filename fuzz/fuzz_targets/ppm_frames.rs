@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libppm::ppm::PPMFrames;
+
+fuzz_target!(|data: &[u8]| {
+    for frame in PPMFrames::new(data) {
+        let _ = frame;
+    }
+});
@@ -0,0 +1,19 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use libppm::ppm::{decode, PPMView};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(view) = PPMView::new(data) else {
+        return;
+    };
+
+    for row in view.row_slices() {
+        let _ = row;
+    }
+
+    // Samples within the maxval must read back as decode reads them
+    if let Ok(image) = decode(data) {
+        assert_eq!(view.to_image(), image);
+    }
+});
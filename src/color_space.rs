@@ -0,0 +1,410 @@
+//! Conversions between sRGB colors & other color spaces
+//!
+//! Every color space converts to & from `Rgb32F`, the floating point sRGB pixel, & through it to &
+//! from `Color`. Alpha isn't part of any of these color spaces, so colors converted back to a
+//! `Color` are opaque.
+//!
+//! Conversions from a `Color` & back again give the same color. Conversions from an `Rgb32F` &
+//! back again are within 1e-4 of each channel, the rounding error of `f32` arithmetic.
+//!
+//! # Example
+//!
+//! ```
+//! use libppm::color_space::{Hsv, Lab};
+//! use libppm::colors::{Color, ORANGE, RED};
+//!
+//! let hsv = Hsv::from(ORANGE);
+//! assert_eq!(hsv.h.round(), 39.0);
+//! assert_eq!(Color::from(Hsv { h: 0.0, ..hsv }), RED);
+//!
+//! // Perceptual distance between two colors
+//! assert!(Lab::from(RED).delta_e(Lab::from(ORANGE)) > 50.0);
+//! ```
+
+use crate::colors::Color;
+use crate::graphics::pixel::{Pixel, Rgb32F};
+
+/// Hue, saturation & value
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct Hsv {
+    /// Hue in degrees, from 0.0 to 360.0
+    pub h: f32,
+    /// Saturation, from 0.0 to 1.0
+    pub s: f32,
+    /// Value, from 0.0 to 1.0
+    pub v: f32,
+}
+
+/// Hue, saturation & lightness
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct Hsl {
+    /// Hue in degrees, from 0.0 to 360.0
+    pub h: f32,
+    /// Saturation, from 0.0 to 1.0
+    pub s: f32,
+    /// Lightness, from 0.0 to 1.0
+    pub l: f32,
+}
+
+/// sRGB without its transfer function, where each channel is proportional to light intensity
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct LinearRgb {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+/// CIE 1931 XYZ, relative to the D65 white point, where Y ranges from 0.0 to 1.0
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct Xyz {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+/// CIELAB, relative to the D65 white point
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct Lab {
+    /// Lightness, from 0.0 to 100.0
+    pub l: f32,
+    /// Green (negative) to red (positive)
+    pub a: f32,
+    /// Blue (negative) to yellow (positive)
+    pub b: f32,
+}
+
+/// XYZ of the D65 white point
+const D65: Xyz = Xyz {
+    x: 0.95047,
+    y: 1.0,
+    z: 1.08883,
+};
+
+/// Linear sRGB to XYZ, from IEC 61966-2-1
+const RGB_TO_XYZ: [[f32; 3]; 3] = [
+    [0.4124564, 0.3575761, 0.1804375],
+    [0.2126729, 0.7151522, 0.072175],
+    [0.0193339, 0.119192, 0.9503041],
+];
+
+/// XYZ to linear sRGB, the inverse of RGB_TO_XYZ
+const XYZ_TO_RGB: [[f32; 3]; 3] = [
+    [3.2404542, -1.5371385, -0.4985314],
+    [-0.969266, 1.8760108, 0.041556],
+    [0.0556434, -0.2040259, 1.0572252],
+];
+
+fn multiply(matrix: [[f32; 3]; 3], vector: [f32; 3]) -> [f32; 3] {
+    matrix.map(|row| row.iter().zip(vector).map(|(m, v)| m * v).sum())
+}
+
+/// Removes the sRGB transfer function from a channel. Negative channels mirror positive ones.
+fn to_linear(channel: f32) -> f32 {
+    let magnitude = channel.abs();
+    let linear = if magnitude <= 0.04045 {
+        magnitude / 12.92
+    } else {
+        ((magnitude + 0.055) / 1.055).powf(2.4)
+    };
+    linear.copysign(channel)
+}
+
+/// Applies the sRGB transfer function to a linear channel. Negative channels mirror positive ones.
+fn from_linear(channel: f32) -> f32 {
+    let magnitude = channel.abs();
+    let encoded = if magnitude <= 0.0031308 {
+        magnitude * 12.92
+    } else {
+        1.055 * magnitude.powf(1.0 / 2.4) - 0.055
+    };
+    encoded.copysign(channel)
+}
+
+/// Hue in degrees from red, green & blue, along with their maximum & minimum.
+fn hue([r, g, b]: [f32; 3], max: f32, min: f32) -> f32 {
+    let chroma = max - min;
+    let hue = if chroma == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / chroma)
+    } else if max == g {
+        60.0 * ((b - r) / chroma + 2.0)
+    } else {
+        60.0 * ((r - g) / chroma + 4.0)
+    };
+    hue.rem_euclid(360.0)
+}
+
+/// Red, green & blue from a hue in degrees, a chroma & the smallest channel.
+fn from_hue(hue: f32, chroma: f32, min: f32) -> [f32; 3] {
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+
+    let [r, g, b] = match sector as u32 {
+        0 => [chroma, x, 0.0],
+        1 => [x, chroma, 0.0],
+        2 => [0.0, chroma, x],
+        3 => [0.0, x, chroma],
+        4 => [x, 0.0, chroma],
+        _ => [chroma, 0.0, x],
+    };
+
+    [r + min, g + min, b + min]
+}
+
+impl From<Rgb32F> for Hsv {
+    fn from(Rgb32F(rgb): Rgb32F) -> Self {
+        let max = rgb.iter().copied().fold(f32::MIN, f32::max);
+        let min = rgb.iter().copied().fold(f32::MAX, f32::min);
+
+        Hsv {
+            h: hue(rgb, max, min),
+            s: if max == 0.0 { 0.0 } else { (max - min) / max },
+            v: max,
+        }
+    }
+}
+
+impl From<Hsv> for Rgb32F {
+    fn from(Hsv { h, s, v }: Hsv) -> Self {
+        let chroma = v * s;
+        Rgb32F(from_hue(h, chroma, v - chroma))
+    }
+}
+
+impl From<Rgb32F> for Hsl {
+    fn from(Rgb32F(rgb): Rgb32F) -> Self {
+        let max = rgb.iter().copied().fold(f32::MIN, f32::max);
+        let min = rgb.iter().copied().fold(f32::MAX, f32::min);
+        let l = (max + min) / 2.0;
+
+        Hsl {
+            h: hue(rgb, max, min),
+            s: if max == min {
+                0.0
+            } else {
+                (max - min) / (1.0 - (2.0 * l - 1.0).abs())
+            },
+            l,
+        }
+    }
+}
+
+impl From<Hsl> for Rgb32F {
+    fn from(Hsl { h, s, l }: Hsl) -> Self {
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        Rgb32F(from_hue(h, chroma, l - chroma / 2.0))
+    }
+}
+
+impl From<Rgb32F> for LinearRgb {
+    fn from(Rgb32F(rgb): Rgb32F) -> Self {
+        let [r, g, b] = rgb.map(to_linear);
+        LinearRgb { r, g, b }
+    }
+}
+
+impl From<LinearRgb> for Rgb32F {
+    fn from(LinearRgb { r, g, b }: LinearRgb) -> Self {
+        Rgb32F([r, g, b].map(from_linear))
+    }
+}
+
+impl From<LinearRgb> for Xyz {
+    fn from(LinearRgb { r, g, b }: LinearRgb) -> Self {
+        let [x, y, z] = multiply(RGB_TO_XYZ, [r, g, b]);
+        Xyz { x, y, z }
+    }
+}
+
+impl From<Xyz> for LinearRgb {
+    fn from(Xyz { x, y, z }: Xyz) -> Self {
+        let [r, g, b] = multiply(XYZ_TO_RGB, [x, y, z]);
+        LinearRgb { r, g, b }
+    }
+}
+
+impl From<Rgb32F> for Xyz {
+    fn from(rgb: Rgb32F) -> Self {
+        Xyz::from(LinearRgb::from(rgb))
+    }
+}
+
+impl From<Xyz> for Rgb32F {
+    fn from(xyz: Xyz) -> Self {
+        Rgb32F::from(LinearRgb::from(xyz))
+    }
+}
+
+/// Threshold between the linear & cube root segments of the CIELAB transfer function.
+const DELTA: f32 = 6.0 / 29.0;
+
+impl From<Xyz> for Lab {
+    fn from(Xyz { x, y, z }: Xyz) -> Self {
+        let f = |t: f32| {
+            if t > DELTA * DELTA * DELTA {
+                t.cbrt()
+            } else {
+                t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+            }
+        };
+        let [fx, fy, fz] = [x / D65.x, y / D65.y, z / D65.z].map(f);
+
+        Lab {
+            l: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+        }
+    }
+}
+
+impl From<Lab> for Xyz {
+    fn from(Lab { l, a, b }: Lab) -> Self {
+        let f_inverse = |t: f32| {
+            if t > DELTA {
+                t * t * t
+            } else {
+                3.0 * DELTA * DELTA * (t - 4.0 / 29.0)
+            }
+        };
+        let fy = (l + 16.0) / 116.0;
+
+        Xyz {
+            x: D65.x * f_inverse(fy + a / 500.0),
+            y: D65.y * f_inverse(fy),
+            z: D65.z * f_inverse(fy - b / 200.0),
+        }
+    }
+}
+
+impl From<Rgb32F> for Lab {
+    fn from(rgb: Rgb32F) -> Self {
+        Lab::from(Xyz::from(rgb))
+    }
+}
+
+impl From<Lab> for Rgb32F {
+    fn from(lab: Lab) -> Self {
+        Rgb32F::from(Xyz::from(lab))
+    }
+}
+
+impl Lab {
+    /// Perceptual distance to another color, the CIE76 ΔE. A ΔE around 2.3 is just noticeable.
+    pub fn delta_e(self, other: Lab) -> f32 {
+        ((self.l - other.l).powi(2) + (self.a - other.a).powi(2) + (self.b - other.b).powi(2))
+            .sqrt()
+    }
+}
+
+impl From<Color> for Rgb32F {
+    fn from(color: Color) -> Self {
+        Rgb32F::from_rgba(color.to_rgba())
+    }
+}
+
+impl From<Rgb32F> for Color {
+    /// Rounds each channel to the nearest 8 bit value, clamping those out of range.
+    fn from(rgb: Rgb32F) -> Self {
+        let [r, g, b, _] = rgb.to_rgba();
+        Color::from_argb(u32::from_rgba([r, g, b, 1.0]))
+    }
+}
+
+/// Converts between Color & a color space, through Rgb32F.
+macro_rules! impl_color_conversions {
+    ($($space:ty),*) => {
+        $(
+            impl From<Color> for $space {
+                fn from(color: Color) -> Self {
+                    <$space>::from(Rgb32F::from(color))
+                }
+            }
+
+            impl From<$space> for Color {
+                fn from(value: $space) -> Self {
+                    Color::from(Rgb32F::from(value))
+                }
+            }
+        )*
+    };
+}
+
+impl_color_conversions!(Hsv, Hsl, LinearRgb, Xyz, Lab);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::{BLUE, NAMED_COLORS, REBECCAPURPLE, WHITE};
+
+    /// Round trips of floating point channels are within this of the original.
+    const TOLERANCE: f32 = 1e-4;
+
+    /// Colors spread across the whole sRGB cube, & every named color.
+    fn colors() -> Vec<Color> {
+        let levels = (0..=255).step_by(17);
+        let mut colors: Vec<Color> = levels
+            .clone()
+            .flat_map(|r| {
+                levels
+                    .clone()
+                    .flat_map(move |g| (0..=255).step_by(17).map(move |b| Color::rgb(r, g, b)))
+            })
+            .collect();
+        colors.extend(NAMED_COLORS.map(|(_, color)| color));
+        colors
+    }
+
+    fn assert_close(a: Rgb32F, b: Rgb32F) {
+        for (a, b) in a.0.iter().zip(b.0) {
+            assert!((a - b).abs() <= TOLERANCE, "{:?} != {:?}", a, b);
+        }
+    }
+
+    fn assert_round_trip<C: From<Rgb32F> + Into<Rgb32F> + From<Color> + Into<Color>>() {
+        for color in colors() {
+            assert_eq!(Into::<Color>::into(C::from(color)), color);
+
+            let rgb = Rgb32F::from(color);
+            assert_close(C::from(rgb).into(), rgb);
+        }
+    }
+
+    #[test]
+    fn test_round_trips() {
+        assert_round_trip::<Hsv>();
+        assert_round_trip::<Hsl>();
+        assert_round_trip::<LinearRgb>();
+        assert_round_trip::<Xyz>();
+        assert_round_trip::<Lab>();
+    }
+
+    #[test]
+    fn test_known_values() {
+        let close = |a: f32, b: f32| (a - b).abs() <= TOLERANCE;
+
+        let hsv = Hsv::from(REBECCAPURPLE);
+        assert!(close(hsv.h, 270.0) && close(hsv.s, 2.0 / 3.0) && close(hsv.v, 0.6));
+
+        let hsl = Hsl::from(REBECCAPURPLE);
+        assert!(close(hsl.h, 270.0) && close(hsl.s, 0.5) && close(hsl.l, 0.4));
+
+        let xyz = Xyz::from(WHITE);
+        assert!(close(xyz.x, D65.x) && close(xyz.y, D65.y) && close(xyz.z, D65.z));
+
+        // Reference values from the CIE formulas, to 2 decimal places
+        let lab = Lab::from(BLUE);
+        assert!((lab.l - 32.30).abs() < 0.01);
+        assert!((lab.a - 79.19).abs() < 0.01);
+        assert!((lab.b + 107.86).abs() < 0.01);
+
+        let white = Lab::from(WHITE);
+        assert!(close(white.l, 100.0) && close(white.a, 0.0) && close(white.b, 0.0));
+        assert_eq!(white.delta_e(white), 0.0);
+
+        assert!(close(
+            LinearRgb::from(Color::rgb(0x80, 0x80, 0x80)).r,
+            0.2158605
+        ));
+    }
+}
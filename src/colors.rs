@@ -1,17 +1,695 @@
-/// Basic Colors - Values match CSS Definitions
-pub const BLACK: u32 = 0x00_00_00_00;
-pub const WHITE: u32 = 0x00_FF_FF_FF;
-pub const RED: u32 = 0x00_FF_00_00;
-pub const LIME: u32 = 0x00_00_FF_00;
-pub const BLUE: u32 = 0x00_00_00_FF;
-pub const YELLOW: u32 = 0x00_FF_FF_00;
-pub const CYAN: u32 = 0x00_00_FF_FF;
-pub const MAGENTA: u32 = 0x00_FF_00_FF;
-pub const SILVER: u32 = 0x00_C0_C0_C0;
-pub const GRAY: u32 = 0x00_80_80_80;
-pub const MAROON: u32 = 0x00_80_00_00;
-pub const OLIVE: u32 = 0x00_80_80_00;
-pub const GREEN: u32 = 0x00_00_80_00;
-pub const PURPLE: u32 = 0x00_80_00_80;
-pub const TEAL: u32 = 0x00_00_80_80;
-pub const NAVY: u32 = 0x00_00_00_80;
+use crate::color_space::Hsl;
+use crate::graphics::pixel::Rgb32F;
+use std::error::Error;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// A color with 8 bits per channel & straight alpha, where an alpha of 255 is opaque.
+///
+/// Converting to & from `u32` uses the layout of ColorMode::Rgb images - 0x00_RR_GG_BB - so that
+/// colors can be compared with the pixels of those images. The top byte is ignored & every such
+/// color is opaque. Use `from_argb` & `to_argb` to keep alpha in the top byte instead.
+///
+/// # Example
+///
+/// ```
+/// use libppm::colors::{Color, RED};
+///
+/// assert_eq!(RED, Color::rgb(0xFF, 0x00, 0x00));
+/// assert_eq!(u32::from(RED), 0x00_FF_00_00);
+/// assert_eq!(Color::from(0x00_FF_00_00), RED);
+/// assert_eq!(RED.with_alpha(0x80).to_argb(), 0x80_FF_00_00);
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    /// Opaque color from its red, green & blue channels
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b, a: 0xFF }
+    }
+
+    /// Color from its red, green, blue & alpha channels
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Color { r, g, b, a }
+    }
+
+    /// Opaque color from 0x00_RR_GG_BB, ignoring the top byte
+    pub const fn from_u32(rgb: u32) -> Self {
+        let [_, r, g, b] = rgb.to_be_bytes();
+        Color::rgb(r, g, b)
+    }
+
+    /// Color from 0xAA_RR_GG_BB
+    pub const fn from_argb(argb: u32) -> Self {
+        let [a, r, g, b] = argb.to_be_bytes();
+        Color::rgba(r, g, b, a)
+    }
+
+    /// 0x00_RR_GG_BB, dropping alpha
+    pub const fn to_u32(self) -> u32 {
+        u32::from_be_bytes([0, self.r, self.g, self.b])
+    }
+
+    /// 0xAA_RR_GG_BB
+    pub const fn to_argb(self) -> u32 {
+        u32::from_be_bytes([self.a, self.r, self.g, self.b])
+    }
+
+    /// The same color with another alpha
+    pub const fn with_alpha(self, a: u8) -> Self {
+        Color { a, ..self }
+    }
+
+    /// Red, green, blue & alpha channels, each ranging from 0.0 to 1.0.
+    pub fn to_rgba(self) -> [f32; 4] {
+        [self.r, self.g, self.b, self.a].map(|channel| channel as f32 / 255.0)
+    }
+
+    /// Looks up one of the CSS named colors, ignoring case
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        NAMED_COLORS
+            .binary_search_by(|(named, _)| named.cmp(&name.as_str()))
+            .ok()
+            .map(|index| NAMED_COLORS[index].1)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ColorParseError {
+    Empty(String),
+    UnknownName(String),
+    InvalidHex(String),
+    UnknownFunction(String),
+    InvalidArguments(String),
+    InvalidComponent(String),
+}
+
+impl Display for ColorParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorParseError::Empty(msg) => {
+                write!(f, "{}", msg)
+            }
+            ColorParseError::UnknownName(msg) => {
+                write!(f, "{}", msg)
+            }
+            ColorParseError::InvalidHex(msg) => {
+                write!(f, "{}", msg)
+            }
+            ColorParseError::UnknownFunction(msg) => {
+                write!(f, "{}", msg)
+            }
+            ColorParseError::InvalidArguments(msg) => {
+                write!(f, "{}", msg)
+            }
+            ColorParseError::InvalidComponent(msg) => {
+                write!(f, "{}", msg)
+            }
+        }
+    }
+}
+
+impl Error for ColorParseError {}
+
+impl FromStr for Color {
+    type Err = ColorParseError;
+
+    /// Parses a color the way CSS does, from one of:
+    ///
+    /// - a named color, such as `rebeccapurple`
+    /// - `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`
+    /// - `rgb(r, g, b)` or `rgba(r, g, b, a)`, where each channel is 0 to 255 or a percentage &
+    ///   alpha is 0 to 1 or a percentage
+    /// - `hsl(h, s%, l%)` or `hsla(h, s%, l%, a)`, where the hue is in degrees
+    ///
+    /// Functions accept both the comma separated & space separated syntax, as in
+    /// `rgb(255 0 0 / 50%)`. Channels out of range are clamped, as CSS does.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use libppm::colors::{Color, REBECCAPURPLE};
+    ///
+    /// assert_eq!("RebeccaPurple".parse::<Color>(), Ok(REBECCAPURPLE));
+    /// assert_eq!("#639".parse::<Color>(), Ok(REBECCAPURPLE));
+    /// assert_eq!("rgb(102, 51, 153)".parse::<Color>(), Ok(REBECCAPURPLE));
+    /// assert_eq!("hsl(270 50% 40% / 0.5)".parse(), Ok(REBECCAPURPLE.with_alpha(0x80)));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return a ColorParseError describing why the string isn't a color.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if s.is_empty() {
+            return Err(ColorParseError::Empty(String::from(
+                "Expected a color, but found an empty string.",
+            )));
+        }
+
+        if let Some(digits) = s.strip_prefix('#') {
+            return parse_hex(digits);
+        }
+
+        if let Some((function, rest)) = s.split_once('(') {
+            let Some(arguments) = rest.strip_suffix(')') else {
+                return Err(ColorParseError::InvalidArguments(format!(
+                    "Expected '{}' to end with ')'.",
+                    s
+                )));
+            };
+            return parse_function(&function.trim().to_ascii_lowercase(), arguments);
+        }
+
+        Color::from_name(s).ok_or_else(|| {
+            ColorParseError::UnknownName(format!("'{}' isn't a CSS named color.", s))
+        })
+    }
+}
+
+/// Parses the digits of a hex color, after the '#'.
+fn parse_hex(digits: &str) -> Result<Color, ColorParseError> {
+    if !digits.chars().all(|digit| digit.is_ascii_hexdigit()) {
+        return Err(ColorParseError::InvalidHex(format!(
+            "'#{}' has characters that aren't hexadecimal digits.",
+            digits
+        )));
+    }
+
+    let channels: Vec<u8> = match digits.len() {
+        // Each digit is repeated, so #f80 is #ff8800
+        3 | 4 => digits
+            .chars()
+            .map(|digit| digit.to_digit(16).unwrap_or_default() as u8 * 0x11)
+            .collect(),
+        6 | 8 => (0..digits.len())
+            .step_by(2)
+            .map(|index| u8::from_str_radix(&digits[index..index + 2], 16).unwrap_or_default())
+            .collect(),
+        _ => {
+            return Err(ColorParseError::InvalidHex(format!(
+                "'#{}' must have 3, 4, 6 or 8 hexadecimal digits, but has {}.",
+                digits,
+                digits.len()
+            )));
+        }
+    };
+
+    Ok(Color::rgba(
+        channels[0],
+        channels[1],
+        channels[2],
+        channels.get(3).copied().unwrap_or(0xFF),
+    ))
+}
+
+/// Parses the arguments of rgb(), rgba(), hsl() or hsla().
+fn parse_function(function: &str, arguments: &str) -> Result<Color, ColorParseError> {
+    let arguments: Vec<&str> = if arguments.contains(',') {
+        arguments.split(',').map(str::trim).collect()
+    } else {
+        arguments
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .filter(|argument| !argument.is_empty())
+            .collect()
+    };
+
+    if !matches!(function, "rgb" | "rgba" | "hsl" | "hsla") {
+        return Err(ColorParseError::UnknownFunction(format!(
+            "'{}' isn't a color function. Expected rgb, rgba, hsl or hsla.",
+            function
+        )));
+    }
+
+    if arguments.len() != 3 && arguments.len() != 4 {
+        return Err(ColorParseError::InvalidArguments(format!(
+            "{}() takes 3 or 4 arguments, but {} were provided.",
+            function,
+            arguments.len()
+        )));
+    }
+
+    let alpha = match arguments.get(3) {
+        Some(alpha) => parse_component(alpha, 1.0)?,
+        None => 1.0,
+    };
+
+    let [r, g, b] = if function.starts_with("rgb") {
+        [
+            parse_component(arguments[0], 255.0)?,
+            parse_component(arguments[1], 255.0)?,
+            parse_component(arguments[2], 255.0)?,
+        ]
+    } else {
+        let hue = arguments[0].strip_suffix("deg").unwrap_or(arguments[0]);
+        let hue = parse_number(hue)?;
+        Rgb32F::from(Hsl {
+            h: hue,
+            s: parse_component(arguments[1], 100.0)?,
+            l: parse_component(arguments[2], 100.0)?,
+        })
+        .0
+    };
+
+    let [r, g, b, a] = [r, g, b, alpha].map(|channel| (channel * 255.0).round() as u8);
+    Ok(Color::rgba(r, g, b, a))
+}
+
+/// Parses a number or percentage, returning it as a fraction of `max` clamped from 0.0 to 1.0.
+fn parse_component(component: &str, max: f32) -> Result<f32, ColorParseError> {
+    let fraction = match component.strip_suffix('%') {
+        Some(percentage) => parse_number(percentage)? / 100.0,
+        None => parse_number(component)? / max,
+    };
+
+    Ok(fraction.clamp(0.0, 1.0))
+}
+
+fn parse_number(number: &str) -> Result<f32, ColorParseError> {
+    match number.trim().parse::<f32>() {
+        Ok(number) if number.is_finite() => Ok(number),
+        _ => Err(ColorParseError::InvalidComponent(format!(
+            "'{}' isn't a number.",
+            number
+        ))),
+    }
+}
+
+impl From<u32> for Color {
+    fn from(rgb: u32) -> Self {
+        Color::from_u32(rgb)
+    }
+}
+
+impl From<Color> for u32 {
+    fn from(color: Color) -> Self {
+        color.to_u32()
+    }
+}
+
+impl Display for Color {
+    /// Formats as #rrggbb, or #rrggbbaa when not opaque.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)?;
+        if self.a != 0xFF {
+            write!(f, "{:02x}", self.a)?;
+        }
+        Ok(())
+    }
+}
+
+/// Named Colors - Values match CSS Definitions, including both the gray & grey spellings
+pub const ALICEBLUE: Color = Color::rgb(0xF0, 0xF8, 0xFF);
+pub const ANTIQUEWHITE: Color = Color::rgb(0xFA, 0xEB, 0xD7);
+pub const AQUA: Color = Color::rgb(0x00, 0xFF, 0xFF);
+pub const AQUAMARINE: Color = Color::rgb(0x7F, 0xFF, 0xD4);
+pub const AZURE: Color = Color::rgb(0xF0, 0xFF, 0xFF);
+pub const BEIGE: Color = Color::rgb(0xF5, 0xF5, 0xDC);
+pub const BISQUE: Color = Color::rgb(0xFF, 0xE4, 0xC4);
+pub const BLACK: Color = Color::rgb(0x00, 0x00, 0x00);
+pub const BLANCHEDALMOND: Color = Color::rgb(0xFF, 0xEB, 0xCD);
+pub const BLUE: Color = Color::rgb(0x00, 0x00, 0xFF);
+pub const BLUEVIOLET: Color = Color::rgb(0x8A, 0x2B, 0xE2);
+pub const BROWN: Color = Color::rgb(0xA5, 0x2A, 0x2A);
+pub const BURLYWOOD: Color = Color::rgb(0xDE, 0xB8, 0x87);
+pub const CADETBLUE: Color = Color::rgb(0x5F, 0x9E, 0xA0);
+pub const CHARTREUSE: Color = Color::rgb(0x7F, 0xFF, 0x00);
+pub const CHOCOLATE: Color = Color::rgb(0xD2, 0x69, 0x1E);
+pub const CORAL: Color = Color::rgb(0xFF, 0x7F, 0x50);
+pub const CORNFLOWERBLUE: Color = Color::rgb(0x64, 0x95, 0xED);
+pub const CORNSILK: Color = Color::rgb(0xFF, 0xF8, 0xDC);
+pub const CRIMSON: Color = Color::rgb(0xDC, 0x14, 0x3C);
+pub const CYAN: Color = Color::rgb(0x00, 0xFF, 0xFF);
+pub const DARKBLUE: Color = Color::rgb(0x00, 0x00, 0x8B);
+pub const DARKCYAN: Color = Color::rgb(0x00, 0x8B, 0x8B);
+pub const DARKGOLDENROD: Color = Color::rgb(0xB8, 0x86, 0x0B);
+pub const DARKGRAY: Color = Color::rgb(0xA9, 0xA9, 0xA9);
+pub const DARKGREEN: Color = Color::rgb(0x00, 0x64, 0x00);
+pub const DARKGREY: Color = Color::rgb(0xA9, 0xA9, 0xA9);
+pub const DARKKHAKI: Color = Color::rgb(0xBD, 0xB7, 0x6B);
+pub const DARKMAGENTA: Color = Color::rgb(0x8B, 0x00, 0x8B);
+pub const DARKOLIVEGREEN: Color = Color::rgb(0x55, 0x6B, 0x2F);
+pub const DARKORANGE: Color = Color::rgb(0xFF, 0x8C, 0x00);
+pub const DARKORCHID: Color = Color::rgb(0x99, 0x32, 0xCC);
+pub const DARKRED: Color = Color::rgb(0x8B, 0x00, 0x00);
+pub const DARKSALMON: Color = Color::rgb(0xE9, 0x96, 0x7A);
+pub const DARKSEAGREEN: Color = Color::rgb(0x8F, 0xBC, 0x8F);
+pub const DARKSLATEBLUE: Color = Color::rgb(0x48, 0x3D, 0x8B);
+pub const DARKSLATEGRAY: Color = Color::rgb(0x2F, 0x4F, 0x4F);
+pub const DARKSLATEGREY: Color = Color::rgb(0x2F, 0x4F, 0x4F);
+pub const DARKTURQUOISE: Color = Color::rgb(0x00, 0xCE, 0xD1);
+pub const DARKVIOLET: Color = Color::rgb(0x94, 0x00, 0xD3);
+pub const DEEPPINK: Color = Color::rgb(0xFF, 0x14, 0x93);
+pub const DEEPSKYBLUE: Color = Color::rgb(0x00, 0xBF, 0xFF);
+pub const DIMGRAY: Color = Color::rgb(0x69, 0x69, 0x69);
+pub const DIMGREY: Color = Color::rgb(0x69, 0x69, 0x69);
+pub const DODGERBLUE: Color = Color::rgb(0x1E, 0x90, 0xFF);
+pub const FIREBRICK: Color = Color::rgb(0xB2, 0x22, 0x22);
+pub const FLORALWHITE: Color = Color::rgb(0xFF, 0xFA, 0xF0);
+pub const FORESTGREEN: Color = Color::rgb(0x22, 0x8B, 0x22);
+pub const FUCHSIA: Color = Color::rgb(0xFF, 0x00, 0xFF);
+pub const GAINSBORO: Color = Color::rgb(0xDC, 0xDC, 0xDC);
+pub const GHOSTWHITE: Color = Color::rgb(0xF8, 0xF8, 0xFF);
+pub const GOLD: Color = Color::rgb(0xFF, 0xD7, 0x00);
+pub const GOLDENROD: Color = Color::rgb(0xDA, 0xA5, 0x20);
+pub const GRAY: Color = Color::rgb(0x80, 0x80, 0x80);
+pub const GREEN: Color = Color::rgb(0x00, 0x80, 0x00);
+pub const GREENYELLOW: Color = Color::rgb(0xAD, 0xFF, 0x2F);
+pub const GREY: Color = Color::rgb(0x80, 0x80, 0x80);
+pub const HONEYDEW: Color = Color::rgb(0xF0, 0xFF, 0xF0);
+pub const HOTPINK: Color = Color::rgb(0xFF, 0x69, 0xB4);
+pub const INDIANRED: Color = Color::rgb(0xCD, 0x5C, 0x5C);
+pub const INDIGO: Color = Color::rgb(0x4B, 0x00, 0x82);
+pub const IVORY: Color = Color::rgb(0xFF, 0xFF, 0xF0);
+pub const KHAKI: Color = Color::rgb(0xF0, 0xE6, 0x8C);
+pub const LAVENDER: Color = Color::rgb(0xE6, 0xE6, 0xFA);
+pub const LAVENDERBLUSH: Color = Color::rgb(0xFF, 0xF0, 0xF5);
+pub const LAWNGREEN: Color = Color::rgb(0x7C, 0xFC, 0x00);
+pub const LEMONCHIFFON: Color = Color::rgb(0xFF, 0xFA, 0xCD);
+pub const LIGHTBLUE: Color = Color::rgb(0xAD, 0xD8, 0xE6);
+pub const LIGHTCORAL: Color = Color::rgb(0xF0, 0x80, 0x80);
+pub const LIGHTCYAN: Color = Color::rgb(0xE0, 0xFF, 0xFF);
+pub const LIGHTGOLDENRODYELLOW: Color = Color::rgb(0xFA, 0xFA, 0xD2);
+pub const LIGHTGRAY: Color = Color::rgb(0xD3, 0xD3, 0xD3);
+pub const LIGHTGREEN: Color = Color::rgb(0x90, 0xEE, 0x90);
+pub const LIGHTGREY: Color = Color::rgb(0xD3, 0xD3, 0xD3);
+pub const LIGHTPINK: Color = Color::rgb(0xFF, 0xB6, 0xC1);
+pub const LIGHTSALMON: Color = Color::rgb(0xFF, 0xA0, 0x7A);
+pub const LIGHTSEAGREEN: Color = Color::rgb(0x20, 0xB2, 0xAA);
+pub const LIGHTSKYBLUE: Color = Color::rgb(0x87, 0xCE, 0xFA);
+pub const LIGHTSLATEGRAY: Color = Color::rgb(0x77, 0x88, 0x99);
+pub const LIGHTSLATEGREY: Color = Color::rgb(0x77, 0x88, 0x99);
+pub const LIGHTSTEELBLUE: Color = Color::rgb(0xB0, 0xC4, 0xDE);
+pub const LIGHTYELLOW: Color = Color::rgb(0xFF, 0xFF, 0xE0);
+pub const LIME: Color = Color::rgb(0x00, 0xFF, 0x00);
+pub const LIMEGREEN: Color = Color::rgb(0x32, 0xCD, 0x32);
+pub const LINEN: Color = Color::rgb(0xFA, 0xF0, 0xE6);
+pub const MAGENTA: Color = Color::rgb(0xFF, 0x00, 0xFF);
+pub const MAROON: Color = Color::rgb(0x80, 0x00, 0x00);
+pub const MEDIUMAQUAMARINE: Color = Color::rgb(0x66, 0xCD, 0xAA);
+pub const MEDIUMBLUE: Color = Color::rgb(0x00, 0x00, 0xCD);
+pub const MEDIUMORCHID: Color = Color::rgb(0xBA, 0x55, 0xD3);
+pub const MEDIUMPURPLE: Color = Color::rgb(0x93, 0x70, 0xDB);
+pub const MEDIUMSEAGREEN: Color = Color::rgb(0x3C, 0xB3, 0x71);
+pub const MEDIUMSLATEBLUE: Color = Color::rgb(0x7B, 0x68, 0xEE);
+pub const MEDIUMSPRINGGREEN: Color = Color::rgb(0x00, 0xFA, 0x9A);
+pub const MEDIUMTURQUOISE: Color = Color::rgb(0x48, 0xD1, 0xCC);
+pub const MEDIUMVIOLETRED: Color = Color::rgb(0xC7, 0x15, 0x85);
+pub const MIDNIGHTBLUE: Color = Color::rgb(0x19, 0x19, 0x70);
+pub const MINTCREAM: Color = Color::rgb(0xF5, 0xFF, 0xFA);
+pub const MISTYROSE: Color = Color::rgb(0xFF, 0xE4, 0xE1);
+pub const MOCCASIN: Color = Color::rgb(0xFF, 0xE4, 0xB5);
+pub const NAVAJOWHITE: Color = Color::rgb(0xFF, 0xDE, 0xAD);
+pub const NAVY: Color = Color::rgb(0x00, 0x00, 0x80);
+pub const OLDLACE: Color = Color::rgb(0xFD, 0xF5, 0xE6);
+pub const OLIVE: Color = Color::rgb(0x80, 0x80, 0x00);
+pub const OLIVEDRAB: Color = Color::rgb(0x6B, 0x8E, 0x23);
+pub const ORANGE: Color = Color::rgb(0xFF, 0xA5, 0x00);
+pub const ORANGERED: Color = Color::rgb(0xFF, 0x45, 0x00);
+pub const ORCHID: Color = Color::rgb(0xDA, 0x70, 0xD6);
+pub const PALEGOLDENROD: Color = Color::rgb(0xEE, 0xE8, 0xAA);
+pub const PALEGREEN: Color = Color::rgb(0x98, 0xFB, 0x98);
+pub const PALETURQUOISE: Color = Color::rgb(0xAF, 0xEE, 0xEE);
+pub const PALEVIOLETRED: Color = Color::rgb(0xDB, 0x70, 0x93);
+pub const PAPAYAWHIP: Color = Color::rgb(0xFF, 0xEF, 0xD5);
+pub const PEACHPUFF: Color = Color::rgb(0xFF, 0xDA, 0xB9);
+pub const PERU: Color = Color::rgb(0xCD, 0x85, 0x3F);
+pub const PINK: Color = Color::rgb(0xFF, 0xC0, 0xCB);
+pub const PLUM: Color = Color::rgb(0xDD, 0xA0, 0xDD);
+pub const POWDERBLUE: Color = Color::rgb(0xB0, 0xE0, 0xE6);
+pub const PURPLE: Color = Color::rgb(0x80, 0x00, 0x80);
+pub const REBECCAPURPLE: Color = Color::rgb(0x66, 0x33, 0x99);
+pub const RED: Color = Color::rgb(0xFF, 0x00, 0x00);
+pub const ROSYBROWN: Color = Color::rgb(0xBC, 0x8F, 0x8F);
+pub const ROYALBLUE: Color = Color::rgb(0x41, 0x69, 0xE1);
+pub const SADDLEBROWN: Color = Color::rgb(0x8B, 0x45, 0x13);
+pub const SALMON: Color = Color::rgb(0xFA, 0x80, 0x72);
+pub const SANDYBROWN: Color = Color::rgb(0xF4, 0xA4, 0x60);
+pub const SEAGREEN: Color = Color::rgb(0x2E, 0x8B, 0x57);
+pub const SEASHELL: Color = Color::rgb(0xFF, 0xF5, 0xEE);
+pub const SIENNA: Color = Color::rgb(0xA0, 0x52, 0x2D);
+pub const SILVER: Color = Color::rgb(0xC0, 0xC0, 0xC0);
+pub const SKYBLUE: Color = Color::rgb(0x87, 0xCE, 0xEB);
+pub const SLATEBLUE: Color = Color::rgb(0x6A, 0x5A, 0xCD);
+pub const SLATEGRAY: Color = Color::rgb(0x70, 0x80, 0x90);
+pub const SLATEGREY: Color = Color::rgb(0x70, 0x80, 0x90);
+pub const SNOW: Color = Color::rgb(0xFF, 0xFA, 0xFA);
+pub const SPRINGGREEN: Color = Color::rgb(0x00, 0xFF, 0x7F);
+pub const STEELBLUE: Color = Color::rgb(0x46, 0x82, 0xB4);
+pub const TAN: Color = Color::rgb(0xD2, 0xB4, 0x8C);
+pub const TEAL: Color = Color::rgb(0x00, 0x80, 0x80);
+pub const THISTLE: Color = Color::rgb(0xD8, 0xBF, 0xD8);
+pub const TOMATO: Color = Color::rgb(0xFF, 0x63, 0x47);
+pub const TURQUOISE: Color = Color::rgb(0x40, 0xE0, 0xD0);
+pub const VIOLET: Color = Color::rgb(0xEE, 0x82, 0xEE);
+pub const WHEAT: Color = Color::rgb(0xF5, 0xDE, 0xB3);
+pub const WHITE: Color = Color::rgb(0xFF, 0xFF, 0xFF);
+pub const WHITESMOKE: Color = Color::rgb(0xF5, 0xF5, 0xF5);
+pub const YELLOW: Color = Color::rgb(0xFF, 0xFF, 0x00);
+pub const YELLOWGREEN: Color = Color::rgb(0x9A, 0xCD, 0x32);
+
+/// Every CSS named color, sorted by name
+pub const NAMED_COLORS: [(&str, Color); 148] = [
+    ("aliceblue", ALICEBLUE),
+    ("antiquewhite", ANTIQUEWHITE),
+    ("aqua", AQUA),
+    ("aquamarine", AQUAMARINE),
+    ("azure", AZURE),
+    ("beige", BEIGE),
+    ("bisque", BISQUE),
+    ("black", BLACK),
+    ("blanchedalmond", BLANCHEDALMOND),
+    ("blue", BLUE),
+    ("blueviolet", BLUEVIOLET),
+    ("brown", BROWN),
+    ("burlywood", BURLYWOOD),
+    ("cadetblue", CADETBLUE),
+    ("chartreuse", CHARTREUSE),
+    ("chocolate", CHOCOLATE),
+    ("coral", CORAL),
+    ("cornflowerblue", CORNFLOWERBLUE),
+    ("cornsilk", CORNSILK),
+    ("crimson", CRIMSON),
+    ("cyan", CYAN),
+    ("darkblue", DARKBLUE),
+    ("darkcyan", DARKCYAN),
+    ("darkgoldenrod", DARKGOLDENROD),
+    ("darkgray", DARKGRAY),
+    ("darkgreen", DARKGREEN),
+    ("darkgrey", DARKGREY),
+    ("darkkhaki", DARKKHAKI),
+    ("darkmagenta", DARKMAGENTA),
+    ("darkolivegreen", DARKOLIVEGREEN),
+    ("darkorange", DARKORANGE),
+    ("darkorchid", DARKORCHID),
+    ("darkred", DARKRED),
+    ("darksalmon", DARKSALMON),
+    ("darkseagreen", DARKSEAGREEN),
+    ("darkslateblue", DARKSLATEBLUE),
+    ("darkslategray", DARKSLATEGRAY),
+    ("darkslategrey", DARKSLATEGREY),
+    ("darkturquoise", DARKTURQUOISE),
+    ("darkviolet", DARKVIOLET),
+    ("deeppink", DEEPPINK),
+    ("deepskyblue", DEEPSKYBLUE),
+    ("dimgray", DIMGRAY),
+    ("dimgrey", DIMGREY),
+    ("dodgerblue", DODGERBLUE),
+    ("firebrick", FIREBRICK),
+    ("floralwhite", FLORALWHITE),
+    ("forestgreen", FORESTGREEN),
+    ("fuchsia", FUCHSIA),
+    ("gainsboro", GAINSBORO),
+    ("ghostwhite", GHOSTWHITE),
+    ("gold", GOLD),
+    ("goldenrod", GOLDENROD),
+    ("gray", GRAY),
+    ("green", GREEN),
+    ("greenyellow", GREENYELLOW),
+    ("grey", GREY),
+    ("honeydew", HONEYDEW),
+    ("hotpink", HOTPINK),
+    ("indianred", INDIANRED),
+    ("indigo", INDIGO),
+    ("ivory", IVORY),
+    ("khaki", KHAKI),
+    ("lavender", LAVENDER),
+    ("lavenderblush", LAVENDERBLUSH),
+    ("lawngreen", LAWNGREEN),
+    ("lemonchiffon", LEMONCHIFFON),
+    ("lightblue", LIGHTBLUE),
+    ("lightcoral", LIGHTCORAL),
+    ("lightcyan", LIGHTCYAN),
+    ("lightgoldenrodyellow", LIGHTGOLDENRODYELLOW),
+    ("lightgray", LIGHTGRAY),
+    ("lightgreen", LIGHTGREEN),
+    ("lightgrey", LIGHTGREY),
+    ("lightpink", LIGHTPINK),
+    ("lightsalmon", LIGHTSALMON),
+    ("lightseagreen", LIGHTSEAGREEN),
+    ("lightskyblue", LIGHTSKYBLUE),
+    ("lightslategray", LIGHTSLATEGRAY),
+    ("lightslategrey", LIGHTSLATEGREY),
+    ("lightsteelblue", LIGHTSTEELBLUE),
+    ("lightyellow", LIGHTYELLOW),
+    ("lime", LIME),
+    ("limegreen", LIMEGREEN),
+    ("linen", LINEN),
+    ("magenta", MAGENTA),
+    ("maroon", MAROON),
+    ("mediumaquamarine", MEDIUMAQUAMARINE),
+    ("mediumblue", MEDIUMBLUE),
+    ("mediumorchid", MEDIUMORCHID),
+    ("mediumpurple", MEDIUMPURPLE),
+    ("mediumseagreen", MEDIUMSEAGREEN),
+    ("mediumslateblue", MEDIUMSLATEBLUE),
+    ("mediumspringgreen", MEDIUMSPRINGGREEN),
+    ("mediumturquoise", MEDIUMTURQUOISE),
+    ("mediumvioletred", MEDIUMVIOLETRED),
+    ("midnightblue", MIDNIGHTBLUE),
+    ("mintcream", MINTCREAM),
+    ("mistyrose", MISTYROSE),
+    ("moccasin", MOCCASIN),
+    ("navajowhite", NAVAJOWHITE),
+    ("navy", NAVY),
+    ("oldlace", OLDLACE),
+    ("olive", OLIVE),
+    ("olivedrab", OLIVEDRAB),
+    ("orange", ORANGE),
+    ("orangered", ORANGERED),
+    ("orchid", ORCHID),
+    ("palegoldenrod", PALEGOLDENROD),
+    ("palegreen", PALEGREEN),
+    ("paleturquoise", PALETURQUOISE),
+    ("palevioletred", PALEVIOLETRED),
+    ("papayawhip", PAPAYAWHIP),
+    ("peachpuff", PEACHPUFF),
+    ("peru", PERU),
+    ("pink", PINK),
+    ("plum", PLUM),
+    ("powderblue", POWDERBLUE),
+    ("purple", PURPLE),
+    ("rebeccapurple", REBECCAPURPLE),
+    ("red", RED),
+    ("rosybrown", ROSYBROWN),
+    ("royalblue", ROYALBLUE),
+    ("saddlebrown", SADDLEBROWN),
+    ("salmon", SALMON),
+    ("sandybrown", SANDYBROWN),
+    ("seagreen", SEAGREEN),
+    ("seashell", SEASHELL),
+    ("sienna", SIENNA),
+    ("silver", SILVER),
+    ("skyblue", SKYBLUE),
+    ("slateblue", SLATEBLUE),
+    ("slategray", SLATEGRAY),
+    ("slategrey", SLATEGREY),
+    ("snow", SNOW),
+    ("springgreen", SPRINGGREEN),
+    ("steelblue", STEELBLUE),
+    ("tan", TAN),
+    ("teal", TEAL),
+    ("thistle", THISTLE),
+    ("tomato", TOMATO),
+    ("turquoise", TURQUOISE),
+    ("violet", VIOLET),
+    ("wheat", WHEAT),
+    ("white", WHITE),
+    ("whitesmoke", WHITESMOKE),
+    ("yellow", YELLOW),
+    ("yellowgreen", YELLOWGREEN),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_named_colors() {
+        assert!(NAMED_COLORS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        for (name, color) in NAMED_COLORS {
+            assert_eq!(Color::from_name(name), Some(color));
+            assert_eq!(name.to_ascii_uppercase().parse(), Ok(color));
+        }
+
+        assert_eq!(Color::from_name("Grey"), Some(GRAY));
+        assert_eq!(Color::from_name("transparent"), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        let parse = |s: &str| s.parse::<Color>();
+
+        assert_eq!(parse("#f80"), Ok(Color::rgb(0xFF, 0x88, 0x00)));
+        assert_eq!(parse("#f808"), Ok(Color::rgba(0xFF, 0x88, 0x00, 0x88)));
+        assert_eq!(parse(" #FF8800 "), Ok(Color::rgb(0xFF, 0x88, 0x00)));
+        assert_eq!(parse("#ff880080"), Ok(Color::rgba(0xFF, 0x88, 0x00, 0x80)));
+
+        assert_eq!(parse("rgb(255, 136, 0)"), Ok(Color::rgb(0xFF, 0x88, 0x00)));
+        assert_eq!(parse("RGB(100%,0%,50%)"), Ok(Color::rgb(0xFF, 0x00, 0x80)));
+        assert_eq!(parse("rgba(0, 0, 255, 0.5)"), Ok(BLUE.with_alpha(0x80)));
+        assert_eq!(parse("rgb(0 0 255 / 25%)"), Ok(BLUE.with_alpha(0x40)));
+        assert_eq!(parse("rgb(300, -20, 0)"), Ok(RED));
+
+        assert_eq!(parse("hsl(0, 100%, 50%)"), Ok(RED));
+        assert_eq!(parse("hsl(120deg 100% 25%)"), Ok(GREEN));
+        assert_eq!(parse("hsl(-120, 100%, 50%)"), Ok(BLUE));
+        assert_eq!(parse("hsla(0, 0%, 100%, 0)"), Ok(WHITE.with_alpha(0)));
+
+        // Every color survives being formatted & parsed again
+        for color in [ORANGE, Color::rgba(1, 2, 3, 4), BLACK.with_alpha(0)] {
+            assert_eq!(parse(&color.to_string()), Ok(color));
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let parse = |s: &str| s.parse::<Color>();
+
+        assert!(matches!(parse("  "), Err(ColorParseError::Empty(_))));
+        assert!(matches!(
+            parse("bluish"),
+            Err(ColorParseError::UnknownName(_))
+        ));
+        assert!(matches!(
+            parse("#12345"),
+            Err(ColorParseError::InvalidHex(_))
+        ));
+        assert!(matches!(parse("#ggg"), Err(ColorParseError::InvalidHex(_))));
+        assert!(matches!(parse("#ééé"), Err(ColorParseError::InvalidHex(_))));
+        assert!(matches!(
+            parse("cmyk(0, 0, 0, 0)"),
+            Err(ColorParseError::UnknownFunction(_))
+        ));
+        assert!(matches!(
+            parse("rgb(0, 0)"),
+            Err(ColorParseError::InvalidArguments(_))
+        ));
+        assert!(matches!(
+            parse("rgb(0, 0, 0"),
+            Err(ColorParseError::InvalidArguments(_))
+        ));
+        assert!(matches!(
+            parse("rgb(0, zero, 0)"),
+            Err(ColorParseError::InvalidComponent(_))
+        ));
+        assert!(matches!(
+            parse("rgb(0, NaN, 0)"),
+            Err(ColorParseError::InvalidComponent(_))
+        ));
+
+        assert_eq!(
+            parse("#12345").unwrap_err().to_string(),
+            "'#12345' must have 3, 4, 6 or 8 hexadecimal digits, but has 5."
+        );
+    }
+}
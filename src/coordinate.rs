@@ -15,6 +15,21 @@ pub struct LineCoordinates {
     pub second: Coordinate,
 }
 
+/// Coordinate on a Cartesian plane with sub-pixel precision, where Coordinate::new(x, y) is the
+/// center of the pixel at x & y.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct FloatCoordinate {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Endpoints of a line with sub-pixel precision.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct FloatLineCoordinates {
+    pub first: FloatCoordinate,
+    pub second: FloatCoordinate,
+}
+
 /// Represents a circle on a Cartesian plane.
 #[derive(Debug, PartialEq)]
 pub struct CircleCoordinates {
@@ -28,6 +43,12 @@ impl Display for Coordinate {
     }
 }
 
+impl Display for FloatCoordinate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}, {}", self.x, self.y)
+    }
+}
+
 impl Display for LineCoordinates {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -113,6 +134,59 @@ impl LineCoordinates {
     }
 }
 
+impl FloatCoordinate {
+    pub fn new(x: f32, y: f32) -> Self {
+        FloatCoordinate { x, y }
+    }
+
+    /// The pixel containing this coordinate. Coordinates that aren't numbers map to i32::MIN, so
+    /// that they are never within an image.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use libppm::coordinate::{Coordinate, FloatCoordinate};
+    ///
+    /// assert_eq!(FloatCoordinate::new(1.4, 2.5).nearest(), Coordinate::new(1, 3));
+    /// assert_eq!(FloatCoordinate::new(f32::NAN, 0.0).nearest(), Coordinate::new(i32::MIN, 0));
+    /// ```
+    pub fn nearest(&self) -> Coordinate {
+        let round = |value: f32| {
+            if value.is_nan() {
+                i32::MIN
+            } else {
+                // Saturates to i32::MIN & i32::MAX
+                value.round() as i32
+            }
+        };
+        Coordinate::new(round(self.x), round(self.y))
+    }
+}
+
+impl From<Coordinate> for FloatCoordinate {
+    fn from(coord: Coordinate) -> Self {
+        FloatCoordinate::new(coord.x as f32, coord.y as f32)
+    }
+}
+
+impl FloatLineCoordinates {
+    pub fn new(a_x: f32, a_y: f32, b_x: f32, b_y: f32) -> Self {
+        FloatLineCoordinates {
+            first: FloatCoordinate::new(a_x, a_y),
+            second: FloatCoordinate::new(b_x, b_y),
+        }
+    }
+}
+
+impl From<LineCoordinates> for FloatLineCoordinates {
+    fn from(coords: LineCoordinates) -> Self {
+        FloatLineCoordinates {
+            first: coords.first.into(),
+            second: coords.second.into(),
+        }
+    }
+}
+
 impl CircleCoordinates {
     pub fn new(x: i32, y: i32, radius: u32) -> Self {
         CircleCoordinates {
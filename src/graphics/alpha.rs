@@ -0,0 +1,131 @@
+//! Helpers for pixels carrying alpha in their top byte - 0xAA_RR_GG_BB
+//!
+//! An alpha of 0xFF is fully opaque & 0x00 is fully transparent. Straight alpha pixels store
+//! their color unchanged, while premultiplied pixels store their color already scaled by alpha.
+
+/// Alpha of a pixel
+pub fn alpha(pixel: u32) -> u8 {
+    (pixel >> 24) as u8
+}
+
+/// Replaces the alpha of a pixel
+///
+/// # Example
+///
+/// ```
+/// use libppm::colors::RED;
+/// use libppm::graphics::alpha::with_alpha;
+///
+/// assert_eq!(with_alpha(RED.into(), 0x80), 0x80_FF_00_00);
+/// ```
+pub const fn with_alpha(pixel: u32, alpha: u8) -> u32 {
+    (pixel & 0x00_FF_FF_FF) | ((alpha as u32) << 24)
+}
+
+/// Scales `value` by `alpha` / 255, rounding to the nearest integer.
+fn scale(value: u8, alpha: u8) -> u8 {
+    ((value as u32 * alpha as u32 + 127) / 255) as u8
+}
+
+/// Applies `f` to each of the red, green & blue channels of a pixel, keeping its alpha.
+fn map_channels(pixel: u32, f: impl Fn(u8) -> u8) -> u32 {
+    let [a, r, g, b] = pixel.to_be_bytes();
+    u32::from_be_bytes([a, f(r), f(g), f(b)])
+}
+
+/// Converts a straight alpha pixel to premultiplied alpha
+pub fn premultiply(pixel: u32) -> u32 {
+    let alpha = alpha(pixel);
+    map_channels(pixel, |channel| scale(channel, alpha))
+}
+
+/// Converts a premultiplied alpha pixel to straight alpha
+///
+/// Fully transparent pixels have no color to recover, so become transparent black.
+pub fn unpremultiply(pixel: u32) -> u32 {
+    match alpha(pixel) {
+        0 => 0,
+        alpha => map_channels(pixel, |channel| {
+            ((channel as u32 * 255 + alpha as u32 / 2) / alpha as u32).min(255) as u8
+        }),
+    }
+}
+
+/// Composites a straight alpha pixel over another straight alpha pixel, the Porter-Duff
+/// "source over" operator.
+///
+/// # Example
+///
+/// ```
+/// use libppm::colors::{BLUE, RED};
+/// use libppm::graphics::alpha::over;
+///
+/// // Half transparent red over opaque blue
+/// assert_eq!(over(RED.with_alpha(0x80).to_argb(), BLUE.to_argb()), 0xFF_80_00_7F);
+/// ```
+pub fn over(source: u32, destination: u32) -> u32 {
+    unpremultiply(over_premultiplied(
+        premultiply(source),
+        premultiply(destination),
+    ))
+}
+
+/// Composites a premultiplied alpha pixel over another premultiplied alpha pixel.
+pub fn over_premultiplied(source: u32, destination: u32) -> u32 {
+    let remaining = 255 - alpha(source);
+    let source = source.to_be_bytes();
+    let destination = destination.to_be_bytes();
+
+    u32::from_be_bytes(
+        [0, 1, 2, 3]
+            .map(|channel| source[channel].saturating_add(scale(destination[channel], remaining))),
+    )
+}
+
+/// Composites a straight alpha pixel over an opaque background, returning an opaque pixel
+/// whose alpha byte is cleared, as used by Images without alpha.
+pub fn flatten(pixel: u32, background: u32) -> u32 {
+    with_alpha(over(pixel, with_alpha(background, 0xFF)), 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors;
+
+    const RED: u32 = colors::RED.to_u32();
+    const BLUE: u32 = colors::BLUE.to_u32();
+    const WHITE: u32 = colors::WHITE.to_u32();
+
+    #[test]
+    fn test_premultiply() {
+        assert_eq!(premultiply(0x80_FF_80_00), 0x80_80_40_00);
+        assert_eq!(premultiply(0xFF_12_34_56), 0xFF_12_34_56);
+        assert_eq!(premultiply(0x00_12_34_56), 0x00_00_00_00);
+
+        assert_eq!(unpremultiply(0x80_80_40_00), 0x80_FF_80_00);
+        assert_eq!(unpremultiply(0x00_12_34_56), 0x00_00_00_00);
+
+        // Every color survives a round trip when fully opaque
+        for channel in 0..=255u32 {
+            let pixel = 0xFF_00_00_00 | (channel * 0x01_01_01);
+            assert_eq!(unpremultiply(premultiply(pixel)), pixel);
+        }
+    }
+
+    #[test]
+    fn test_compositing() {
+        let half_red = with_alpha(RED, 0x80);
+
+        assert_eq!(over(half_red, with_alpha(BLUE, 0xFF)), 0xFF_80_00_7F);
+        assert_eq!(over(with_alpha(RED, 0xFF), BLUE), with_alpha(RED, 0xFF));
+        assert_eq!(
+            over(with_alpha(RED, 0x00), with_alpha(BLUE, 0xFF)),
+            0xFF_00_00_FF
+        );
+
+        assert_eq!(flatten(half_red, WHITE), 0x00_FF_7F_7F);
+        assert_eq!(flatten(with_alpha(RED, 0xFF), WHITE), RED);
+        assert_eq!(flatten(RED, BLUE), BLUE);
+    }
+}
@@ -0,0 +1,172 @@
+use crate::colors::{BLACK, WHITE};
+use crate::graphics::image::{pixel_count, Image, ImageBuilderError};
+use crate::graphics::pixel::luma;
+
+/// 1-bit image, for stencils, masks & dithered output.
+///
+/// Follows the PBM convention where a set pixel (true) is black & an unset pixel is white.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Bitmap {
+    rows: usize,
+    cols: usize,
+    data: Vec<bool>,
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct BitmapBuilder {
+    rows: Option<usize>,
+    cols: Option<usize>,
+    data: Option<Vec<bool>>,
+}
+
+impl Bitmap {
+    pub fn builder() -> BitmapBuilder {
+        BitmapBuilder::new()
+    }
+
+    /// Converts an Image to a Bitmap, setting every pixel whose luma is below `threshold`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use libppm::colors::{RED, WHITE};
+    /// use libppm::coordinate::CircleCoordinates;
+    /// use libppm::graphics::bitmap::Bitmap;
+    /// use libppm::graphics::image::Image;
+    ///
+    /// let mut image = Image::builder().rows(16).cols(16).build().unwrap();
+    /// image
+    ///     .fill(WHITE)
+    ///     .draw_filled_circle(RED, CircleCoordinates::new(8, 8, 4))
+    ///     .unwrap();
+    ///
+    /// let mask = Bitmap::threshold(&image, 128);
+    /// assert!(mask.get_data()[8 * 16 + 8]);
+    /// assert!(!mask.get_data()[0]);
+    /// ```
+    pub fn threshold(image: &Image, threshold: u8) -> Self {
+        Bitmap {
+            rows: *image.get_rows(),
+            cols: *image.get_cols(),
+            data: image
+                .get_data()
+                .iter()
+                .map(|pixel| luma(*pixel) < threshold)
+                .collect(),
+        }
+    }
+
+    /// Fills a bitmap, setting every pixel when `bit` is true & clearing every pixel otherwise
+    pub fn fill(&mut self, bit: bool) -> &mut Self {
+        for index in 0..self.data.len() {
+            self.data[index] = bit;
+        }
+        self
+    }
+
+    /// Converts to an Image, where set pixels are black & unset pixels are white.
+    pub fn to_image(&self) -> Image {
+        let data = self
+            .data
+            .iter()
+            .map(|bit| u32::from(if *bit { BLACK } else { WHITE }))
+            .collect();
+
+        // Dimensions were already validated when self was built
+        Image::builder()
+            .rows(self.rows)
+            .cols(self.cols)
+            .data(data)
+            .build()
+            .expect("Bitmap dimensions are valid for an Image")
+    }
+
+    pub fn get_rows(&self) -> &usize {
+        &self.rows
+    }
+
+    pub fn get_cols(&self) -> &usize {
+        &self.cols
+    }
+
+    pub fn get_data(&self) -> &Vec<bool> {
+        &self.data
+    }
+
+    pub fn get_data_length(&self) -> usize {
+        self.data.len()
+    }
+}
+
+impl BitmapBuilder {
+    pub fn new() -> Self {
+        Self {
+            rows: None,
+            cols: None,
+            data: None,
+        }
+    }
+
+    pub fn rows(&mut self, rows: usize) -> &mut Self {
+        self.rows = Some(rows);
+        self
+    }
+
+    pub fn cols(&mut self, cols: usize) -> &mut Self {
+        self.cols = Some(cols);
+        self
+    }
+
+    pub fn data(&mut self, data: Vec<bool>) -> &mut Self {
+        self.data = Some(data);
+        self
+    }
+
+    pub fn build(&self) -> Result<Bitmap, ImageBuilderError> {
+        let rows = match self.rows {
+            Some(0) => {
+                return Err(ImageBuilderError::ZeroSizedImage(String::from(
+                    "Rows can't be zero.",
+                )));
+            }
+            Some(rows) => rows,
+            None => {
+                return Err(ImageBuilderError::RowsNotProvided(String::from(
+                    "Rows must be provided to build an image.",
+                )));
+            }
+        };
+
+        let cols = match self.cols {
+            Some(0) => {
+                return Err(ImageBuilderError::ZeroSizedImage(String::from(
+                    "Columns can't be zero.",
+                )));
+            }
+            Some(cols) => cols,
+            None => {
+                return Err(ImageBuilderError::ColumnsNotProvided(String::from(
+                    "Columns must be provided to build an image.",
+                )));
+            }
+        };
+
+        let pixels = pixel_count(rows, cols, size_of::<bool>())?;
+
+        let data = match &self.data {
+            Some(data) => {
+                if data.len() != pixels {
+                    return Err(ImageBuilderError::DataDoesntMatchDimensions(String::from(
+                        "The number of elements in the provided data doesn't match the dimensions of the image being constructed.",
+                    )));
+                }
+                data.to_vec()
+            }
+            None => {
+                vec![false; pixels]
+            }
+        };
+
+        Ok(Bitmap { rows, cols, data })
+    }
+}
@@ -1,62 +1,86 @@
 use crate::{
+    colors::Color,
     coordinate::{CircleCoordinates, Coordinate, LineCoordinates},
-    graphics::image::Image,
+    graphics::{dash::DashPattern, image::Image, pixel::Pixel},
     validate,
 };
+use std::f32::consts::TAU;
 
-impl Image {
+impl<P: Pixel> Image<P> {
     /// Draws a circle using the Midpoint-Circle Algorithm.
+    ///
+    /// # Errors
+    ///
+    /// Will return ValidationError::OutOfBoundsInImageError if the circle doesn't fit in the
+    /// image, in which case nothing is drawn.
     pub fn draw_circle(
         &mut self,
-        color: u32,
+        color: impl Into<Color>,
         coords: CircleCoordinates,
-    ) -> Result<&mut Self, validate::ValidationError> {
+    ) -> Result<&mut Self, validate::ValidationError<P>> {
         validate::circle_coordinates(self, &coords)?;
 
-        let mut x = 0;
-        let mut y = -(coords.radius as i32);
+        let color = self.to_pixel(color);
+        for coord in midpoint_circle(&coords) {
+            self.put_pixel(coord, color)?;
+        }
 
-        while x < -y {
-            let y_midpoint = (y as f32) + 0.5;
+        Ok(self)
+    }
 
-            if (x * x) as f32 + y_midpoint * y_midpoint > (coords.radius * coords.radius) as f32 {
-                y += 1;
-            }
+    /// Draws a dashed circle using the Midpoint-Circle Algorithm. The dash pattern starts at the
+    /// top of the circle & goes clockwise, measured in pixels around the circumference.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use libppm::colors::WHITE;
+    /// use libppm::coordinate::{CircleCoordinates, Coordinate};
+    /// use libppm::graphics::dash::DashPattern;
+    /// use libppm::graphics::image::Image;
+    ///
+    /// // Quarters of the circumference on & off, with the dashes centered on the top & bottom
+    /// let quarter = std::f32::consts::PI * 10.0 / 2.0;
+    /// let dash = DashPattern::new(&[quarter, quarter], quarter / 2.0).unwrap();
+    /// let mut image = Image::builder().rows(32).cols(32).build().unwrap();
+    /// image
+    ///     .draw_dashed_circle(
+    ///         WHITE,
+    ///         &dash,
+    ///         CircleCoordinates {
+    ///             center: Coordinate::new(16, 16),
+    ///             radius: 10,
+    ///         },
+    ///     )
+    ///     .unwrap();
+    ///
+    /// assert_eq!(image.get_pixel(Coordinate::new(6, 16)).unwrap(), 0x00_FF_FF_FF);
+    /// assert_eq!(image.get_pixel(Coordinate::new(16, 26)).unwrap(), 0);
+    /// assert_eq!(image.get_pixel(Coordinate::new(26, 16)).unwrap(), 0x00_FF_FF_FF);
+    /// assert_eq!(image.get_pixel(Coordinate::new(16, 6)).unwrap(), 0);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return ValidationError::OutOfBoundsInImageError if the circle doesn't fit in the
+    /// image, in which case nothing is drawn.
+    pub fn draw_dashed_circle(
+        &mut self,
+        color: impl Into<Color>,
+        dash: &DashPattern,
+        coords: CircleCoordinates,
+    ) -> Result<&mut Self, validate::ValidationError<P>> {
+        validate::circle_coordinates(self, &coords)?;
 
-            self.set_pixel(
-                Coordinate::new(coords.center.x + x, coords.center.y + y),
-                color,
-            )?;
-            self.set_pixel(
-                Coordinate::new(coords.center.x - x, coords.center.y + y),
-                color,
-            )?;
-            self.set_pixel(
-                Coordinate::new(coords.center.x + x, coords.center.y - y),
-                color,
-            )?;
-            self.set_pixel(
-                Coordinate::new(coords.center.x - x, coords.center.y - y),
-                color,
-            )?;
-            self.set_pixel(
-                Coordinate::new(coords.center.x + y, coords.center.y + x),
-                color,
-            )?;
-            self.set_pixel(
-                Coordinate::new(coords.center.x + y, coords.center.y - x),
-                color,
-            )?;
-            self.set_pixel(
-                Coordinate::new(coords.center.x - y, coords.center.y + x),
-                color,
-            )?;
-            self.set_pixel(
-                Coordinate::new(coords.center.x - y, coords.center.y - x),
-                color,
-            )?;
-
-            x += 1;
+        let color = self.to_pixel(color);
+        for coord in midpoint_circle(&coords) {
+            // Angle clockwise from the top, where x is the row & y the column
+            let angle = ((coord.y - coords.center.y) as f32)
+                .atan2((coords.center.x - coord.x) as f32)
+                .rem_euclid(TAU);
+            if dash.is_on(angle * coords.radius as f32) {
+                self.put_pixel(coord, color)?;
+            }
         }
 
         Ok(self)
@@ -66,23 +90,26 @@ impl Image {
     /// with the provided color.
     pub fn draw_filled_circle(
         &mut self,
-        color: u32,
+        color: impl Into<Color>,
         coords: CircleCoordinates,
-    ) -> Result<&mut Self, validate::ValidationError> {
+    ) -> Result<&mut Self, validate::ValidationError<P>> {
         // Circle will be validated inside of draw_circle function
+        let color = color.into();
         self.draw_circle(color, coords)?;
 
+        let pixel = self.to_pixel(color);
+
         for row in 0..(*self.get_rows() as i32) {
             let mut horizontal_line_coords = LineCoordinates::new(0, 0, 0, 0);
 
             for col in 0..(*self.get_cols() as i32) {
                 let maybe_circle_border = Coordinate { x: row, y: col };
 
-                if self.get_pixel(maybe_circle_border)? == color
+                if self.get_pixel(maybe_circle_border)? == pixel
                     && horizontal_line_coords.first.y == horizontal_line_coords.second.y
                 {
                     horizontal_line_coords.first = Coordinate { x: row, y: col };
-                } else if self.get_pixel(maybe_circle_border)? == color {
+                } else if self.get_pixel(maybe_circle_border)? == pixel {
                     horizontal_line_coords.second = Coordinate { x: row, y: col };
                 }
             }
@@ -96,6 +123,34 @@ impl Image {
     }
 }
 
+/// Pixels of a circle from the Midpoint-Circle Algorithm, found an eighth of the circle at a time.
+fn midpoint_circle(coords: &CircleCoordinates) -> Vec<Coordinate> {
+    let mut pixels = Vec::new();
+    let mut x = 0;
+    let mut y = -(coords.radius as i32);
+
+    while x < -y {
+        let y_midpoint = (y as f32) + 0.5;
+
+        if (x * x) as f32 + y_midpoint * y_midpoint > (coords.radius * coords.radius) as f32 {
+            y += 1;
+        }
+
+        pixels.push(Coordinate::new(coords.center.x + x, coords.center.y + y));
+        pixels.push(Coordinate::new(coords.center.x - x, coords.center.y + y));
+        pixels.push(Coordinate::new(coords.center.x + x, coords.center.y - y));
+        pixels.push(Coordinate::new(coords.center.x - x, coords.center.y - y));
+        pixels.push(Coordinate::new(coords.center.x + y, coords.center.y + x));
+        pixels.push(Coordinate::new(coords.center.x + y, coords.center.y - x));
+        pixels.push(Coordinate::new(coords.center.x - y, coords.center.y + x));
+        pixels.push(Coordinate::new(coords.center.x - y, coords.center.y - x));
+
+        x += 1;
+    }
+
+    pixels
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -124,6 +179,81 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_draw_dashed_circle() -> Result<(), Box<dyn Error>> {
+        use crate::graphics::dash::DashPattern;
+        use std::f32::consts::PI;
+
+        let circle = || CircleCoordinates {
+            center: Coordinate::new(20, 20),
+            radius: 15,
+        };
+        let mut solid = Image::builder().rows(41).cols(41).build()?;
+        solid.draw_circle(WHITE, circle())?;
+
+        // Without gaps, the same pixels are drawn as a solid circle
+        let mut image = Image::builder().rows(41).cols(41).build()?;
+        image.draw_dashed_circle(WHITE, &DashPattern::new(&[1.0, 0.0], 0.0)?, circle())?;
+        assert_eq!(image, solid);
+
+        // The right half of the circle, from the top clockwise to the bottom
+        let half = PI * 15.0;
+        let mut image = Image::builder().rows(41).cols(41).build()?;
+        image.draw_dashed_circle(WHITE, &DashPattern::new(&[half, half], 0.0)?, circle())?;
+        for row in 0..41 {
+            for col in 0..41 {
+                let coord = Coordinate::new(row, col);
+                let expected = if col > 20 || (col == 20 && row < 20) {
+                    solid.get_pixel(coord)?
+                } else {
+                    0
+                };
+                assert_eq!(image.get_pixel(coord)?, expected, "{}", coord);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_circles_out_of_bounds() -> Result<(), Box<dyn Error>> {
+        use crate::graphics::dash::DashPattern;
+
+        // The center is inside the image, but the top of the circle isn't
+        let circle = || CircleCoordinates {
+            center: Coordinate::new(2, 5),
+            radius: 4,
+        };
+        let blank = Image::builder().rows(10).cols(10).build()?;
+        let dash = DashPattern::new(&[2.0, 1.0], 0.0)?;
+
+        let mut image = blank.clone();
+        assert!(image.draw_circle(WHITE, circle()).is_err());
+        assert!(image.draw_dashed_circle(WHITE, &dash, circle()).is_err());
+        assert!(image.draw_filled_circle(WHITE, circle()).is_err());
+        assert_eq!(image, blank);
+
+        // Circles touching the edges fit
+        image.draw_circle(
+            WHITE,
+            CircleCoordinates {
+                center: Coordinate::new(4, 5),
+                radius: 4,
+            },
+        )?;
+        assert!(image
+            .draw_circle(
+                WHITE,
+                CircleCoordinates {
+                    center: Coordinate::new(4, 5),
+                    radius: u32::MAX,
+                },
+            )
+            .is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_draw_japanese_flag() -> Result<(), Box<dyn Error>> {
         // Dimensions from https://www.japan.go.jp/japan/flagandanthem/index.html
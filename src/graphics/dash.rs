@@ -0,0 +1,259 @@
+//! Dash patterns, which turn lines on & off along their length
+
+use std::error::Error;
+use std::fmt::Display;
+
+/// Shortest period a pattern may repeat with, in pixels. Anything shorter is far below what a
+/// pixel can show, & would take millions of dashes to cover a line.
+pub const MIN_PERIOD: f32 = 1e-3;
+
+/// Lengths of alternating dashes & gaps, starting with a dash, plus how far into the pattern lines
+/// start. A pattern with an odd number of lengths is repeated twice, so [4.0] is 4 on, 4 off.
+///
+/// The pattern follows the whole length of a line, so it carries on around the corners of
+/// polylines rather than restarting at each segment.
+///
+/// # Example
+///
+/// ```
+/// use libppm::graphics::dash::DashPattern;
+///
+/// let dash = DashPattern::new(&[3.0, 1.0], 1.0).unwrap();
+/// assert!(dash.is_on(0.0));
+/// assert!(!dash.is_on(2.5));
+/// assert!(dash.is_on(3.0));
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct DashPattern {
+    lengths: Vec<f32>,
+    offset: f32,
+}
+
+impl DashPattern {
+    /// # Errors
+    ///
+    /// Will return a DashPatternError if there are no lengths, if any length is negative or not
+    /// finite, if the lengths are all zero or add up to less than MIN_PERIOD, or if the offset
+    /// isn't finite.
+    pub fn new(lengths: &[f32], offset: f32) -> Result<Self, DashPatternError> {
+        if lengths.is_empty() {
+            return Err(DashPatternError::EmptyPattern(String::from(
+                "A dash pattern needs at least one length.",
+            )));
+        }
+        if let Some(length) = lengths
+            .iter()
+            .find(|length| !length.is_finite() || **length < 0.0)
+        {
+            return Err(DashPatternError::InvalidLength(format!(
+                "Dash lengths must be positive numbers or zero, but one was {}.",
+                length
+            )));
+        }
+        if lengths.iter().all(|length| *length == 0.0) {
+            return Err(DashPatternError::ZeroLengthPattern(String::from(
+                "Dash lengths can't all be zero.",
+            )));
+        }
+        let period: f32 = lengths.iter().sum();
+        if period < MIN_PERIOD {
+            return Err(DashPatternError::PeriodTooShort(format!(
+                "Dash lengths must add up to at least {}, but added up to {}.",
+                MIN_PERIOD, period
+            )));
+        }
+        if !offset.is_finite() {
+            return Err(DashPatternError::InvalidOffset(format!(
+                "Dash offset must be a number, but was {}.",
+                offset
+            )));
+        }
+
+        Ok(DashPattern {
+            lengths: lengths.to_vec(),
+            offset,
+        })
+    }
+
+    pub fn get_lengths(&self) -> &Vec<f32> {
+        &self.lengths
+    }
+
+    pub fn get_offset(&self) -> &f32 {
+        &self.offset
+    }
+
+    /// Whether the pattern is on at a distance along a line. Each dash includes its start but not
+    /// its end, so a dash of length zero is never on.
+    pub fn is_on(&self, distance: f32) -> bool {
+        let mut position = (distance as f64 + self.offset as f64).rem_euclid(self.period());
+        for (index, length) in self.entries().enumerate() {
+            if position < length {
+                return index % 2 == 0;
+            }
+            position -= length;
+        }
+
+        // Only reached through rounding at the very end of the pattern, which wraps to the start
+        true
+    }
+
+    /// Start & end of each dash along a line of a given length, clipped to the line. Dashes of
+    /// length zero are included, as are those touching either end of the line.
+    pub(crate) fn dashes(&self, length: f32) -> Vec<(f32, f32)> {
+        let period = self.period();
+        let phase = (self.offset as f64).rem_euclid(period);
+        let length = length as f64;
+        let mut dashes = Vec::new();
+
+        // Each repetition's start is calculated afresh, so that tiny dashes on long lines can't
+        // stop the pattern from advancing
+        let mut repetition: u64 = 0;
+        loop {
+            let mut start = repetition as f64 * period - phase;
+            if start > length {
+                break;
+            }
+
+            for (index, dash_length) in self.entries().enumerate() {
+                let end = start + dash_length;
+                if index % 2 == 0 && (end > 0.0 || start >= 0.0) && start <= length {
+                    dashes.push((start.max(0.0) as f32, end.min(length) as f32));
+                }
+                start = end;
+            }
+            repetition += 1;
+        }
+
+        dashes
+    }
+
+    /// Lengths of dashes & gaps in turn, repeating the lengths if there are an odd number of them.
+    fn entries(&self) -> impl Iterator<Item = f64> + '_ {
+        let repeats = if self.lengths.len() % 2 == 0 { 1 } else { 2 };
+        self.lengths
+            .iter()
+            .cycle()
+            .take(self.lengths.len() * repeats)
+            .map(|length| *length as f64)
+    }
+
+    fn period(&self) -> f64 {
+        self.entries().sum()
+    }
+}
+
+#[derive(Debug)]
+pub enum DashPatternError {
+    EmptyPattern(String),
+    InvalidLength(String),
+    ZeroLengthPattern(String),
+    PeriodTooShort(String),
+    InvalidOffset(String),
+}
+
+impl Display for DashPatternError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DashPatternError::EmptyPattern(msg) => {
+                write!(f, "{}", msg)
+            }
+            DashPatternError::InvalidLength(msg) => {
+                write!(f, "{}", msg)
+            }
+            DashPatternError::ZeroLengthPattern(msg) => {
+                write!(f, "{}", msg)
+            }
+            DashPatternError::PeriodTooShort(msg) => {
+                write!(f, "{}", msg)
+            }
+            DashPatternError::InvalidOffset(msg) => {
+                write!(f, "{}", msg)
+            }
+        }
+    }
+}
+
+impl Error for DashPatternError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dash_pattern() -> Result<(), Box<dyn Error>> {
+        let dash = DashPattern::new(&[3.0, 2.0], 0.0)?;
+        let on: Vec<bool> = (0..10)
+            .map(|distance| dash.is_on(distance as f32))
+            .collect();
+        assert_eq!(
+            on,
+            [true, true, true, false, false, true, true, true, false, false]
+        );
+        assert_eq!(
+            dash.dashes(12.0),
+            vec![(0.0, 3.0), (5.0, 8.0), (10.0, 12.0)]
+        );
+
+        // Offsets move the pattern back along the line, & may be negative or past the pattern
+        let dash = DashPattern::new(&[3.0, 2.0], 4.0)?;
+        assert_eq!(dash.dashes(7.0), vec![(1.0, 4.0), (6.0, 7.0)]);
+        assert_eq!(
+            DashPattern::new(&[3.0, 2.0], -1.0)?.dashes(7.0),
+            dash.dashes(7.0)
+        );
+        assert_eq!(
+            DashPattern::new(&[3.0, 2.0], 9.0)?.dashes(7.0),
+            dash.dashes(7.0)
+        );
+        assert!(!dash.is_on(0.5));
+        assert!(dash.is_on(1.0));
+
+        // Odd patterns repeat, turning dashes into gaps the second time around
+        let dash = DashPattern::new(&[1.0, 1.0, 2.0], 0.0)?;
+        assert_eq!(
+            dash.dashes(8.0),
+            vec![(0.0, 1.0), (2.0, 4.0), (5.0, 6.0), (8.0, 8.0)]
+        );
+
+        // Dots, which are only drawn by caps
+        let dash = DashPattern::new(&[0.0, 2.0], 0.0)?;
+        assert_eq!(dash.dashes(4.0), vec![(0.0, 0.0), (2.0, 2.0), (4.0, 4.0)]);
+        assert!(!dash.is_on(0.0));
+
+        // Tiny dashes still make it along long lines
+        let dash = DashPattern::new(&[0.1, 0.1], 0.0)?;
+        assert!((499_999..=500_001).contains(&dash.dashes(100_000.0).len()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_dash_patterns() {
+        assert!(matches!(
+            DashPattern::new(&[], 0.0),
+            Err(DashPatternError::EmptyPattern(_))
+        ));
+        for length in [-1.0, f32::NAN, f32::INFINITY] {
+            assert!(matches!(
+                DashPattern::new(&[1.0, length], 0.0),
+                Err(DashPatternError::InvalidLength(_))
+            ));
+        }
+        assert!(matches!(
+            DashPattern::new(&[0.0, 0.0], 0.0),
+            Err(DashPatternError::ZeroLengthPattern(_))
+        ));
+        for lengths in [&[1e-30, 1e-30][..], &[0.0, 1e-4], &[4e-4]] {
+            assert!(matches!(
+                DashPattern::new(lengths, 0.0),
+                Err(DashPatternError::PeriodTooShort(_))
+            ));
+        }
+        assert!(DashPattern::new(&[MIN_PERIOD], 0.0).is_ok());
+        assert!(matches!(
+            DashPattern::new(&[1.0], f32::NAN),
+            Err(DashPatternError::InvalidOffset(_))
+        ));
+    }
+}
@@ -1,27 +1,46 @@
-use crate::colors::BLACK;
+use crate::colors::Color;
 use crate::coordinate::Coordinate;
+use crate::graphics::alpha;
+use crate::graphics::pixel::{Pixel, Rgb32F};
 use crate::validate;
 use std::error::Error;
 use std::fmt::Display;
 
+/// How the alpha channel of the pixels of an Image is interpreted.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum ColorMode {
+    /// 0x00_RR_GG_BB - the top byte, or any other alpha channel, is unused & every pixel is opaque.
+    #[default]
+    Rgb,
+    /// 0xAA_RR_GG_BB with straight alpha, where 0xFF is opaque.
+    Rgba,
+    /// 0xAA_RR_GG_BB with each color channel already multiplied by alpha.
+    PremultipliedRgba,
+}
+
 /// General form of an image
 ///
-/// TODO : Is there a generic that allows the elements within data to be any type?
+/// Pixels may be any type implementing Pixel, defaulting to packed `u32` pixels - 0xAA_RR_GG_BB -
+/// which is what the codecs read & write. Images of other pixel types are built with
+/// `ImageBuilder::<P>::new()` & converted with `Image::convert` when read or written.
 #[derive(Debug, PartialEq, Clone)]
-pub struct Image {
+pub struct Image<P: Pixel = u32> {
     rows: usize,
     cols: usize,
-    data: Vec<u32>,
+    data: Vec<P>,
+    color_mode: ColorMode,
 }
 
 #[derive(Debug, PartialEq, Clone)]
-pub struct ImageBuilder {
+pub struct ImageBuilder<P: Pixel = u32> {
     rows: Option<usize>,
     cols: Option<usize>,
-    data: Option<Vec<u32>>,
+    data: Option<Vec<P>>,
+    color_mode: ColorMode,
 }
 
 impl Image {
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         todo!()
     }
@@ -29,9 +48,12 @@ impl Image {
     pub fn builder() -> ImageBuilder {
         ImageBuilder::new()
     }
+}
 
+impl<P: Pixel> Image<P> {
     /// Produces a checkerboard pattern
-    pub fn checkerboard(&mut self, tile_size: usize, tile_color: u32) -> &Self {
+    pub fn checkerboard(&mut self, tile_size: usize, tile_color: impl Into<Color>) -> &Self {
+        let tile_color = self.to_pixel(tile_color);
         for row in 0..self.rows {
             for col in 0..self.cols {
                 let pixel_index = row * self.cols + col;
@@ -45,14 +67,19 @@ impl Image {
     }
 
     /// Fills an image with a provided color
-    pub fn fill(&mut self, color: u32) -> &mut Self {
+    pub fn fill(&mut self, color: impl Into<Color>) -> &mut Self {
+        let color = self.to_pixel(color);
         for index in 0..self.data.len() {
             self.data[index] = color;
         }
         self
     }
 
-    /// Sets a single pixel to a provided color
+    /// Sets a single pixel to a provided color, converted by Image::to_pixel
+    ///
+    /// A `u32` color is 0x00_RR_GG_BB & opaque whatever the color mode, so passing a pixel from
+    /// get_pixel loses the alpha of RGBA images. Copy pixels with put_pixel instead, or pass the
+    /// color from get_color.
     ///
     /// # Errors
     ///
@@ -61,35 +88,139 @@ impl Image {
     pub fn set_pixel(
         &mut self,
         coord: Coordinate,
-        color: u32,
-    ) -> Result<(), validate::ValidationError> {
-        if let Err(e) = validate::coordinate(&self, &coord) {
-            return Err(e);
-        }
+        color: impl Into<Color>,
+    ) -> Result<(), validate::ValidationError<P>> {
+        let pixel = self.to_pixel(color);
+        self.put_pixel(coord, pixel)
+    }
+
+    /// Sets a single pixel to a provided pixel value, without any conversion, e.g. to copy a
+    /// pixel from get_pixel
+    ///
+    /// # Errors
+    ///
+    /// Will return ValidationError::OutOfBoundsError if provided pixel is outside of the range of
+    /// the image.
+    pub fn put_pixel(
+        &mut self,
+        coord: Coordinate,
+        pixel: P,
+    ) -> Result<(), validate::ValidationError<P>> {
+        validate::coordinate(self, &coord)?;
 
         let pixel_index = (coord.x as usize) * self.get_cols() + (coord.y as usize);
 
-        self.data[pixel_index] = color;
+        self.data[pixel_index] = pixel;
 
         Ok(())
     }
 
+    /// Blends a color over a single pixel, where `coverage` is the fraction of the pixel covered
+    /// by the color, from 0.0 to 1.0. The color's own alpha is taken into account as well.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use libppm::colors::{BLACK, WHITE};
+    /// use libppm::coordinate::Coordinate;
+    /// use libppm::graphics::image::Image;
+    ///
+    /// let mut image = Image::builder().rows(1).cols(1).build().unwrap();
+    /// image.fill(BLACK).blend_pixel(Coordinate::new(0, 0), WHITE, 0.25).unwrap();
+    /// assert_eq!(image.get_data(), &vec![0x00_40_40_40]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return ValidationError::OutOfBoundsError if provided pixel is outside of the range of
+    /// the image.
+    pub fn blend_pixel(
+        &mut self,
+        coord: Coordinate,
+        color: impl Into<Color>,
+        coverage: f32,
+    ) -> Result<(), validate::ValidationError<P>> {
+        let pixel = self.get_pixel(coord)?;
+        let [r, g, b, a] = color.into().to_rgba();
+        let [dest_r, dest_g, dest_b, dest_a] = to_straight_rgba(pixel, self.color_mode);
+
+        // Porter-Duff source over, with straight alpha
+        let source_a = a * coverage.clamp(0.0, 1.0);
+        let dest_weight = dest_a * (1.0 - source_a);
+        let out_a = source_a + dest_weight;
+        let blend = |source: f32, dest: f32| {
+            if out_a == 0.0 {
+                0.0
+            } else {
+                (source * source_a + dest * dest_weight) / out_a
+            }
+        };
+
+        self.put_pixel(
+            coord,
+            from_straight_rgba(
+                [blend(r, dest_r), blend(g, dest_g), blend(b, dest_b), out_a],
+                self.color_mode,
+            ),
+        )
+    }
+
+    /// Whether a coordinate is within the image
+    pub(crate) fn contains(&self, coord: Coordinate) -> bool {
+        coord.x >= 0
+            && coord.y >= 0
+            && (coord.x as usize) < self.rows
+            && (coord.y as usize) < self.cols
+    }
+
     /// Gets a single pixel color
     ///
     /// # Errors
     ///
     /// Will return ValidationError::OutOfBoundsError if provided pixel is outside of the range of
     /// the image.
-    pub fn get_pixel(&self, coord: Coordinate) -> Result<u32, validate::ValidationError> {
-        if let Err(e) = validate::coordinate(&self, &coord) {
-            return Err(e);
-        }
+    pub fn get_pixel(&self, coord: Coordinate) -> Result<P, validate::ValidationError<P>> {
+        validate::coordinate(self, &coord)?;
 
         let pixel_index = (coord.x as usize) * self.get_cols() + (coord.y as usize);
 
         Ok(self.data[pixel_index])
     }
 
+    /// Gets the color of a single pixel, as interpreted by the image's color mode, so that
+    /// set_pixel with the color gives back the same pixel
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use libppm::colors::RED;
+    /// use libppm::coordinate::Coordinate;
+    /// use libppm::graphics::image::{ColorMode, Image};
+    ///
+    /// let mut image = Image::builder()
+    ///     .rows(1)
+    ///     .cols(2)
+    ///     .color_mode(ColorMode::Rgba)
+    ///     .build()
+    ///     .unwrap();
+    /// image.set_pixel(Coordinate::new(0, 0), RED.with_alpha(0x40)).unwrap();
+    ///
+    /// let color = image.get_color(Coordinate::new(0, 0)).unwrap();
+    /// assert_eq!(color, RED.with_alpha(0x40));
+    /// image.set_pixel(Coordinate::new(0, 1), color).unwrap();
+    /// assert_eq!(image.get_data(), &vec![0x40_FF_00_00, 0x40_FF_00_00]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return ValidationError::OutOfBoundsError if provided pixel is outside of the range of
+    /// the image.
+    pub fn get_color(&self, coord: Coordinate) -> Result<Color, validate::ValidationError<P>> {
+        let [r, g, b, a] = to_straight_rgba(self.get_pixel(coord)?, self.color_mode)
+            .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8);
+        Ok(Color::rgba(r, g, b, a))
+    }
+
     pub fn get_rows(&self) -> &usize {
         &self.rows
     }
@@ -98,13 +229,246 @@ impl Image {
         &self.cols
     }
 
-    pub fn get_data(&self) -> &Vec<u32> {
+    pub fn get_data(&self) -> &Vec<P> {
         &self.data
     }
 
     pub fn get_data_length(&self) -> usize {
         self.data.len()
     }
+
+    pub fn get_color_mode(&self) -> &ColorMode {
+        &self.color_mode
+    }
+
+    /// Converts a color to a pixel, as interpreted by the image's color mode
+    ///
+    /// Alpha is dropped for RGB images & multiplied into the color for premultiplied images.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use libppm::colors::RED;
+    /// use libppm::graphics::image::{ColorMode, Image};
+    ///
+    /// let image = Image::builder().rows(1).cols(1).build().unwrap();
+    /// assert_eq!(image.to_pixel(RED.with_alpha(0x80)), 0x00_FF_00_00);
+    ///
+    /// let image = Image::builder().rows(1).cols(1).color_mode(ColorMode::Rgba).build().unwrap();
+    /// assert_eq!(image.to_pixel(RED.with_alpha(0x80)), 0x80_FF_00_00);
+    /// ```
+    pub fn to_pixel(&self, color: impl Into<Color>) -> P {
+        from_straight_rgba(color.into().to_rgba(), self.color_mode)
+    }
+
+    /// Converts every pixel to a color space, such as `color_space::Lab`, in row major order.
+    /// Alpha is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use libppm::color_space::Hsv;
+    /// use libppm::colors::{BLUE, RED};
+    /// use libppm::coordinate::Coordinate;
+    /// use libppm::graphics::image::Image;
+    ///
+    /// let mut image = Image::builder().rows(1).cols(2).build().unwrap();
+    /// image.fill(RED).set_pixel(Coordinate::new(0, 1), BLUE).unwrap();
+    ///
+    /// let hues: Vec<f32> = image.to_color_space::<Hsv>().iter().map(|hsv| hsv.h).collect();
+    /// assert_eq!(hues, vec![0.0, 240.0]);
+    /// ```
+    pub fn to_color_space<C: From<Rgb32F>>(&self) -> Vec<C> {
+        self.data
+            .iter()
+            .map(|pixel| {
+                let [r, g, b, _] = to_straight_rgba(*pixel, self.color_mode);
+                C::from(Rgb32F([r, g, b]))
+            })
+            .collect()
+    }
+
+    /// Modifies every pixel in a color space, such as `color_space::Hsl`, keeping its alpha.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use libppm::color_space::Hsl;
+    /// use libppm::colors::{Color, RED};
+    /// use libppm::graphics::image::Image;
+    ///
+    /// let mut image = Image::builder().rows(1).cols(1).build().unwrap();
+    /// image.fill(RED).map_color_space(|hsl: Hsl| Hsl { s: 0.0, ..hsl });
+    /// assert_eq!(image.get_data(), &vec![Color::rgb(0x80, 0x80, 0x80).into()]);
+    /// ```
+    pub fn map_color_space<C>(&mut self, mut f: impl FnMut(C) -> C) -> &mut Self
+    where
+        C: From<Rgb32F> + Into<Rgb32F>,
+    {
+        for pixel in self.data.iter_mut() {
+            let [r, g, b, a] = to_straight_rgba(*pixel, self.color_mode);
+            let Rgb32F([r, g, b]) = f(C::from(Rgb32F([r, g, b]))).into();
+            *pixel = from_straight_rgba([r, g, b, a], self.color_mode);
+        }
+        self
+    }
+
+    /// Converts to an image of another pixel type
+    ///
+    /// Alpha is kept when both pixel types have room for it & the image uses it, & becomes straight
+    /// alpha. Otherwise every pixel is made opaque. Channels are rounded to the nearest value the
+    /// new pixel type can hold.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use libppm::colors::{RED, WHITE};
+    /// use libppm::graphics::image::Image;
+    /// use libppm::graphics::pixel::{Luma8, Rgb16};
+    ///
+    /// let image = Image::builder()
+    ///     .rows(1)
+    ///     .cols(2)
+    ///     .data(vec![RED.into(), WHITE.into()])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let deep = image.convert::<Rgb16>();
+    /// assert_eq!(deep.get_data(), &vec![Rgb16([65535, 0, 0]), Rgb16([65535; 3])]);
+    /// assert_eq!(image.convert::<Luma8>().get_data(), &vec![Luma8(76), Luma8(255)]);
+    /// assert_eq!(deep.convert::<u32>(), image);
+    /// ```
+    pub fn convert<Q: Pixel>(&self) -> Image<Q> {
+        let color_mode = match self.color_mode {
+            _ if !Q::HAS_ALPHA => ColorMode::Rgb,
+            ColorMode::Rgb => Q::COLOR_MODE,
+            _ => ColorMode::Rgba,
+        };
+
+        let data = self
+            .data
+            .iter()
+            .map(|pixel| from_straight_rgba(to_straight_rgba(*pixel, self.color_mode), color_mode))
+            .collect();
+
+        Image {
+            rows: self.rows,
+            cols: self.cols,
+            data,
+            color_mode,
+        }
+    }
+}
+
+/// Straight alpha channels of a pixel, as interpreted by a color mode. RGB pixels are opaque.
+fn to_straight_rgba<P: Pixel>(pixel: P, color_mode: ColorMode) -> [f32; 4] {
+    let [r, g, b, a] = pixel.to_rgba();
+    match color_mode {
+        ColorMode::Rgb => [r, g, b, 1.0],
+        ColorMode::Rgba => [r, g, b, a],
+        ColorMode::PremultipliedRgba if a == 0.0 => [0.0; 4],
+        ColorMode::PremultipliedRgba => [r / a, g / a, b / a, a],
+    }
+}
+
+/// Pixel from straight alpha channels, as interpreted by a color mode.
+fn from_straight_rgba<P: Pixel>([r, g, b, a]: [f32; 4], color_mode: ColorMode) -> P {
+    P::from_rgba(match color_mode {
+        // Packed pixels keep their alpha byte clear when it's unused
+        ColorMode::Rgb => [r, g, b, 0.0],
+        ColorMode::Rgba => [r, g, b, a],
+        ColorMode::PremultipliedRgba => [r * a, g * a, b * a, a],
+    })
+}
+
+impl Image {
+    /// Converts to straight alpha RGBA. Pixels of an RGB image become fully opaque.
+    pub fn to_rgba(&self) -> Image {
+        let data = match self.color_mode {
+            ColorMode::Rgb => self
+                .data
+                .iter()
+                .map(|pixel| alpha::with_alpha(*pixel, 0xFF))
+                .collect(),
+            ColorMode::Rgba => self.data.clone(),
+            ColorMode::PremultipliedRgba => self
+                .data
+                .iter()
+                .map(|pixel| alpha::unpremultiply(*pixel))
+                .collect(),
+        };
+
+        Image {
+            rows: self.rows,
+            cols: self.cols,
+            data,
+            color_mode: ColorMode::Rgba,
+        }
+    }
+
+    /// Converts a straight alpha image to premultiplied alpha. Other images are unchanged.
+    pub fn premultiply_alpha(&mut self) -> &mut Self {
+        if self.color_mode == ColorMode::Rgba {
+            for pixel in self.data.iter_mut() {
+                *pixel = alpha::premultiply(*pixel);
+            }
+            self.color_mode = ColorMode::PremultipliedRgba;
+        }
+        self
+    }
+
+    /// Converts a premultiplied alpha image to straight alpha. Other images are unchanged.
+    pub fn unpremultiply_alpha(&mut self) -> &mut Self {
+        if self.color_mode == ColorMode::PremultipliedRgba {
+            for pixel in self.data.iter_mut() {
+                *pixel = alpha::unpremultiply(*pixel);
+            }
+            self.color_mode = ColorMode::Rgba;
+        }
+        self
+    }
+
+    /// Composites the image over an opaque background color, producing an RGB image
+    ///
+    /// RGB images are returned unchanged, as every pixel is already opaque.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use libppm::colors::{RED, WHITE};
+    /// use libppm::graphics::image::{ColorMode, Image};
+    ///
+    /// let sprite = Image::builder()
+    ///     .rows(1)
+    ///     .cols(2)
+    ///     .data(vec![RED.to_argb(), RED.with_alpha(0x00).to_argb()])
+    ///     .color_mode(ColorMode::Rgba)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let flat = sprite.flatten(WHITE);
+    /// assert_eq!(flat.get_data(), &vec![RED.into(), WHITE.into()]);
+    /// ```
+    pub fn flatten(&self, background: impl Into<Color>) -> Image {
+        if self.color_mode == ColorMode::Rgb {
+            return self.clone();
+        }
+
+        let background = background.into().to_u32();
+        let data = self
+            .to_rgba()
+            .data
+            .iter()
+            .map(|pixel| alpha::flatten(*pixel, background))
+            .collect();
+
+        Image {
+            rows: self.rows,
+            cols: self.cols,
+            data,
+            color_mode: ColorMode::Rgb,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -113,6 +477,7 @@ pub enum ImageBuilderError {
     ColumnsNotProvided(String),
     DataDoesntMatchDimensions(String),
     ZeroSizedImage(String),
+    DimensionsTooLarge(String),
 }
 
 impl Display for ImageBuilderError {
@@ -130,18 +495,49 @@ impl Display for ImageBuilderError {
             ImageBuilderError::ZeroSizedImage(msg) => {
                 write!(f, "{}", msg)
             }
+            ImageBuilderError::DimensionsTooLarge(msg) => {
+                write!(f, "{}", msg)
+            }
         }
     }
 }
 
 impl Error for ImageBuilderError {}
 
-impl ImageBuilder {
+/// Number of pixels in an image of the given dimensions, checking that the pixel data, at
+/// `pixel_size` bytes per pixel, can be allocated.
+pub(crate) fn pixel_count(
+    rows: usize,
+    cols: usize,
+    pixel_size: usize,
+) -> Result<usize, ImageBuilderError> {
+    rows.checked_mul(cols)
+        .filter(|pixels| {
+            pixels
+                .checked_mul(pixel_size)
+                .is_some_and(|bytes| bytes <= isize::MAX as usize)
+        })
+        .ok_or_else(|| {
+            ImageBuilderError::DimensionsTooLarge(format!(
+                "An image of {} rows by {} columns is too large to allocate.",
+                rows, cols
+            ))
+        })
+}
+
+impl<P: Pixel> Default for ImageBuilder<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: Pixel> ImageBuilder<P> {
     pub fn new() -> Self {
         Self {
             rows: None,
             cols: None,
             data: None,
+            color_mode: P::COLOR_MODE,
         }
     }
 
@@ -155,12 +551,19 @@ impl ImageBuilder {
         self
     }
 
-    pub fn data(&mut self, data: Vec<u32>) -> &mut Self {
+    pub fn data(&mut self, data: Vec<P>) -> &mut Self {
         self.data = Some(data);
         self
     }
 
-    pub fn build(&self) -> Result<Image, ImageBuilderError> {
+    /// Sets how the alpha channel of the pixels is interpreted. Defaults to the pixel type's
+    /// COLOR_MODE, which is ColorMode::Rgb for every pixel type but Rgba8.
+    pub fn color_mode(&mut self, color_mode: ColorMode) -> &mut Self {
+        self.color_mode = color_mode;
+        self
+    }
+
+    pub fn build(&self) -> Result<Image<P>, ImageBuilderError> {
         let rows = match self.rows {
             Some(rows) => match rows {
                 0 => {
@@ -194,9 +597,11 @@ impl ImageBuilder {
         };
 
         // TODO : Don't clone with '.to_vec()' here
+        let pixels = pixel_count(rows, cols, size_of::<P>())?;
+
         let data = match &self.data {
             Some(data) => {
-                if data.len() != rows * cols {
+                if data.len() != pixels {
                     return Err(ImageBuilderError::DataDoesntMatchDimensions(String::from(
                         "The number of elements in the provided data doesn't match the dimensions of the image being constructed.",
                     )));
@@ -204,17 +609,23 @@ impl ImageBuilder {
                 data.to_vec()
             }
             None => {
-                vec![BLACK; rows * cols]
+                vec![P::default(); pixels]
             }
         };
 
-        Ok(Image { rows, cols, data })
+        Ok(Image {
+            rows,
+            cols,
+            data,
+            color_mode: self.color_mode,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::colors::BLACK;
 
     #[test]
     fn test_image_builder() {
@@ -224,8 +635,220 @@ mod tests {
             Image {
                 rows: 512,
                 cols: 512,
-                data: vec![BLACK; 512 * 512],
+                data: vec![BLACK.into(); 512 * 512],
+                color_mode: ColorMode::Rgb,
+            }
+        );
+    }
+
+    #[test]
+    fn test_alpha_modes() {
+        let data = vec![0x80_FF_80_00, 0x00_12_34_56];
+        let mut image = Image::builder()
+            .rows(1)
+            .cols(2)
+            .data(data.clone())
+            .color_mode(ColorMode::Rgba)
+            .build()
+            .unwrap();
+
+        image.premultiply_alpha();
+        assert_eq!(image.get_color_mode(), &ColorMode::PremultipliedRgba);
+        assert_eq!(image.get_data(), &vec![0x80_80_40_00, 0x00_00_00_00]);
+        assert_eq!(
+            image.flatten(0x00_00_00_FF).get_data(),
+            &vec![0x00_80_40_7F, 0x00_00_00_FF]
+        );
+
+        image.unpremultiply_alpha();
+        assert_eq!(image.get_color_mode(), &ColorMode::Rgba);
+        assert_eq!(image.get_data(), &vec![0x80_FF_80_00, 0x00_00_00_00]);
+
+        // RGB images are opaque, whatever their top byte holds
+        let image = Image::builder().rows(1).cols(2).data(data).build().unwrap();
+        assert_eq!(
+            image.to_rgba().get_data(),
+            &vec![0xFF_FF_80_00, 0xFF_12_34_56]
+        );
+        assert_eq!(image.flatten(0x00_00_00_FF), image.clone());
+    }
+
+    #[test]
+    fn test_colors_follow_color_mode() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::colors::{Color, RED};
+        use crate::graphics::pixel::Rgba8;
+
+        let half_red = RED.with_alpha(0x80);
+        let corner = Coordinate::new(0, 0);
+
+        let mut image = Image::builder().rows(1).cols(1).build()?;
+        image.set_pixel(corner, half_red)?;
+        assert_eq!(image.get_pixel(corner)?, 0x00_FF_00_00);
+
+        // Raw u32 colors are 0x00_RR_GG_BB, as they always were
+        image.set_pixel(corner, 0x00_12_34_56)?;
+        assert_eq!(image.get_pixel(corner)?, 0x00_12_34_56);
+
+        let mut image = Image::builder()
+            .rows(1)
+            .cols(1)
+            .color_mode(ColorMode::Rgba)
+            .build()?;
+        image.fill(half_red);
+        assert_eq!(image.get_pixel(corner)?, 0x80_FF_00_00);
+        assert_eq!(Color::from_argb(image.get_pixel(corner)?), half_red);
+
+        image.premultiply_alpha().fill(half_red);
+        assert_eq!(image.get_pixel(corner)?, 0x80_80_00_00);
+
+        let mut image = ImageBuilder::<Rgba8>::new().rows(1).cols(1).build()?;
+        image.set_pixel(corner, half_red)?;
+        assert_eq!(image.get_pixel(corner)?, Rgba8([0xFF, 0, 0, 0x80]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_copying_pixels_keeps_alpha() -> Result<(), Box<dyn std::error::Error>> {
+        let (from, to) = (Coordinate::new(0, 0), Coordinate::new(0, 1));
+
+        for color_mode in [
+            ColorMode::Rgb,
+            ColorMode::Rgba,
+            ColorMode::PremultipliedRgba,
+        ] {
+            let mut image = Image::builder()
+                .rows(1)
+                .cols(2)
+                .color_mode(color_mode)
+                .build()?;
+            for pixel in [0x40_FF_FF_FF, 0x80_40_20_10, 0xFF_12_34_56, 0x00_00_00_00] {
+                // Premultiplied channels can't exceed the alpha
+                let pixel = match color_mode {
+                    ColorMode::PremultipliedRgba => alpha::premultiply(pixel),
+                    ColorMode::Rgb => pixel & 0x00_FF_FF_FF,
+                    ColorMode::Rgba => pixel,
+                };
+                image.put_pixel(from, pixel)?;
+
+                image.put_pixel(to, image.get_pixel(from)?)?;
+                assert_eq!(image.get_pixel(to)?, pixel);
+
+                image.set_pixel(to, image.get_color(from)?)?;
+                assert_eq!(image.get_pixel(to)?, pixel, "{:?}", color_mode);
             }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_color_spaces() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::color_space::{Hsv, Lab};
+        use crate::colors::{NAMED_COLORS, RED};
+
+        let data: Vec<u32> = NAMED_COLORS
+            .iter()
+            .enumerate()
+            .map(|(index, (_, color))| color.with_alpha(index as u8).to_argb())
+            .collect();
+        let mut image = Image::builder()
+            .rows(4)
+            .cols(37)
+            .data(data)
+            .color_mode(ColorMode::Rgba)
+            .build()?;
+        let original = image.clone();
+
+        // Colors & alpha survive a round trip through any color space
+        image.map_color_space(|lab: Lab| lab);
+        assert_eq!(image, original);
+
+        let lab: Vec<Lab> = image.to_color_space();
+        assert_eq!(lab.len(), NAMED_COLORS.len());
+
+        image.map_color_space(|_: Hsv| Hsv {
+            h: 0.0,
+            s: 1.0,
+            v: 1.0,
+        });
+        assert!(image
+            .get_data()
+            .iter()
+            .zip(original.get_data())
+            .all(|(pixel, original)| *pixel
+                == (RED.to_argb() & 0x00_FF_FF_FF) | (original & 0xFF_00_00_00)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generic_pixels() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::colors::{GRAY, WHITE};
+        use crate::coordinate::{CircleCoordinates, LineCoordinates};
+        use crate::graphics::pixel::{Luma16, Rgb32F, Rgba8};
+
+        // Drawing works the same whatever the pixel type
+        let mut image = ImageBuilder::<Luma16>::new().rows(8).cols(8).build()?;
+        image
+            .draw_line_bresenham(GRAY, LineCoordinates::new(0, 0, 0, 7))?
+            .draw_circle(
+                WHITE,
+                CircleCoordinates {
+                    center: Coordinate::new(4, 4),
+                    radius: 2,
+                },
+            )?;
+        assert_eq!(image.get_pixel(Coordinate::new(0, 5))?, Luma16(32896));
+        assert_eq!(image.get_pixel(Coordinate::new(2, 4))?, Luma16(65535));
+        assert_eq!(image.get_pixel(Coordinate::new(4, 4))?, Luma16(0));
+
+        let mut hdr = ImageBuilder::<Rgb32F>::new().rows(1).cols(1).build()?;
+        hdr.put_pixel(Coordinate::new(0, 0), Rgb32F([4.0, 0.5, 0.0]))?;
+        assert_eq!(hdr.convert::<u32>().get_data(), &vec![0x00_FF_80_00]);
+
+        // Alpha survives conversions between pixel types with room for it
+        let sprite = ImageBuilder::<Rgba8>::new()
+            .rows(1)
+            .cols(2)
+            .data(vec![Rgba8([255, 0, 0, 128]), Rgba8([0, 0, 255, 0])])
+            .build()?;
+        assert_eq!(sprite.get_color_mode(), &ColorMode::Rgba);
+
+        let packed = sprite.convert::<u32>();
+        assert_eq!(packed.get_color_mode(), &ColorMode::Rgba);
+        assert_eq!(packed.get_data(), &vec![0x80_FF_00_00, 0x00_00_00_FF]);
+        assert_eq!(packed.convert::<Rgba8>(), sprite);
+
+        let mut premultiplied = packed.clone();
+        premultiplied.premultiply_alpha();
+        assert_eq!(
+            premultiplied.convert::<Rgba8>().get_data()[0],
+            Rgba8([255, 0, 0, 128])
         );
+
+        // & is dropped by pixel types without it, or images that don't use it
+        let opaque = sprite.convert::<Rgb32F>();
+        assert_eq!(opaque.get_color_mode(), &ColorMode::Rgb);
+        assert_eq!(
+            opaque.get_data(),
+            &vec![Rgb32F([1.0, 0.0, 0.0]), Rgb32F([0.0, 0.0, 1.0])]
+        );
+        assert_eq!(
+            opaque.convert::<Rgba8>().get_data(),
+            &vec![Rgba8([255, 0, 0, 255]), Rgba8([0, 0, 255, 255])]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_image_builder_too_large() {
+        for (rows, cols) in [(usize::MAX, 2), (1 << 32, 1 << 31)] {
+            assert!(matches!(
+                Image::builder().rows(rows).cols(cols).build(),
+                Err(ImageBuilderError::DimensionsTooLarge(_))
+            ));
+        }
     }
 }
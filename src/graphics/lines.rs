@@ -1,17 +1,19 @@
+use crate::colors::Color;
 use crate::coordinate;
+use crate::graphics::dash::DashPattern;
 use crate::graphics::image::Image;
+use crate::graphics::pixel::Pixel;
 use crate::validate;
+use std::collections::HashSet;
 
-impl Image {
+impl<P: Pixel> Image<P> {
     /// Renders a line using the Digital Differential Analyzer algorithm.
     pub fn draw_line_dda(
         &mut self,
-        color: u32,
+        color: impl Into<Color>,
         coords: coordinate::LineCoordinates,
-    ) -> Result<&mut Self, validate::ValidationError> {
-        if let Err(e) = validate::line_coordinates(&self, &coords) {
-            return Err(e);
-        }
+    ) -> Result<&mut Self, validate::ValidationError<P>> {
+        validate::line_coordinates(self, &coords)?;
 
         let coordinate::LineCoordinates {
             first: a,
@@ -27,12 +29,13 @@ impl Image {
         let x_increment: f32 = (dx as f32) / (steps as f32);
         let y_increment: f32 = (dy as f32) / (steps as f32);
 
+        let color = self.to_pixel(color);
         for _ in 0..steps {
             let coord = coordinate::Coordinate {
                 x: x as i32,
                 y: y as i32,
             };
-            self.set_pixel(coord, color)?;
+            self.put_pixel(coord, color)?;
             x += x_increment;
             y += y_increment;
         }
@@ -40,31 +43,325 @@ impl Image {
         Ok(self)
     }
 
+    /// Renders an anti-aliased line using Xiaolin Wu's algorithm, blending the color into the
+    /// existing pixels by how much of each pixel the line covers.
+    ///
+    /// Endpoints may lie between pixels, as FloatLineCoordinates, where the center of each pixel
+    /// is at whole numbers. An endpoint on a pixel's center covers half of that pixel, & a line
+    /// within a single pixel covers it by its length, so a line of zero length draws nothing.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use libppm::colors::{BLACK, WHITE};
+    /// use libppm::coordinate::FloatLineCoordinates;
+    /// use libppm::graphics::image::Image;
+    ///
+    /// let mut image = Image::builder().rows(2).cols(4).build().unwrap();
+    /// image
+    ///     .fill(BLACK)
+    ///     .draw_line_wu(WHITE, FloatLineCoordinates::new(0.5, 0.0, 0.5, 3.0))
+    ///     .unwrap();
+    ///
+    /// // Halfway between the rows, so both are half covered
+    /// assert_eq!(image.get_data()[1], 0x00_80_80_80);
+    /// assert_eq!(image.get_data()[5], 0x00_80_80_80);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return ValidationError::OutOfBoundsInImageError if the pixel nearest to either
+    /// endpoint is outside of the image, in which case nothing is drawn.
+    pub fn draw_line_wu(
+        &mut self,
+        color: impl Into<Color>,
+        coords: impl Into<coordinate::FloatLineCoordinates>,
+    ) -> Result<&mut Self, validate::ValidationError<P>> {
+        let coords = coords.into();
+        validate::float_line_coordinates(self, &coords)?;
+
+        let color = color.into();
+        let coordinate::FloatLineCoordinates {
+            first: a,
+            second: b,
+        } = coords;
+
+        // Step along the major axis, u, & interpolate along the minor axis, v
+        let steep = (b.y - a.y).abs() > (b.x - a.x).abs();
+        let (mut a, mut b) = if steep {
+            ((a.y, a.x), (b.y, b.x))
+        } else {
+            ((a.x, a.y), (b.x, b.y))
+        };
+        if a.0 > b.0 {
+            std::mem::swap(&mut a, &mut b);
+        }
+
+        let du = b.0 - a.0;
+        let gradient = if du == 0.0 { 0.0 } else { (b.1 - a.1) / du };
+
+        let fpart = |value: f32| value - value.floor();
+        let rfpart = |value: f32| 1.0 - fpart(value);
+
+        // Pixels next to the line may be outside of the image, even though the endpoints aren't
+        let plot = |image: &mut Self, u: f32, v: f32, coverage: f32| {
+            let coord = if steep {
+                coordinate::Coordinate::new(v as i32, u as i32)
+            } else {
+                coordinate::Coordinate::new(u as i32, v as i32)
+            };
+            if coverage > 0.0 && image.contains(coord) {
+                image.blend_pixel(coord, color, coverage)
+            } else {
+                Ok(())
+            }
+        };
+
+        // Each endpoint only covers part of its pixel along the major axis
+        let endpoint = |image: &mut Self, (u, v): (f32, f32), gap: f32| {
+            let u_end = u.round();
+            let v_end = v + gradient * (u_end - u);
+            plot(image, u_end, v_end.floor(), rfpart(v_end) * gap)?;
+            plot(image, u_end, v_end.floor() + 1.0, fpart(v_end) * gap)?;
+            Ok((u_end, v_end))
+        };
+
+        // Lines within a single pixel along the major axis cover it by their length, blended
+        // once rather than once for each endpoint
+        if a.0.round() == b.0.round() {
+            endpoint(self, a, du)?;
+            return Ok(self);
+        }
+
+        let (u_first, v_first) = endpoint(self, a, rfpart(a.0 + 0.5))?;
+        let (u_last, _) = endpoint(self, b, fpart(b.0 + 0.5))?;
+
+        let mut v = v_first + gradient;
+        let mut u = u_first + 1.0;
+        while u < u_last {
+            plot(self, u, v.floor(), rfpart(v))?;
+            plot(self, u, v.floor() + 1.0, fpart(v))?;
+            v += gradient;
+            u += 1.0;
+        }
+
+        Ok(self)
+    }
+
     /// Renders a line using Bresenham's Line Algorithm.
+    ///
+    /// Lines in every direction are drawn with integer arithmetic only, & the same pixels are
+    /// drawn whichever endpoint comes first. See BresenhamLine for the pixels that are drawn.
+    ///
+    /// Pixels are overwritten with the color, as by put_pixel, so a translucent color replaces
+    /// what was there rather than blending with it. draw_polyline & draw_polygon blend instead.
+    ///
+    /// # Errors
+    ///
+    /// Will return ValidationError::OutOfBoundsInImageError if either endpoint is outside of the
+    /// image, in which case nothing is drawn.
     pub fn draw_line_bresenham(
         &mut self,
-        color: u32,
+        color: impl Into<Color>,
         coords: coordinate::LineCoordinates,
-    ) -> Result<&mut Self, validate::ValidationError> {
-        if let Err(e) = validate::line_coordinates(&self, &coords) {
-            return Err(e);
+    ) -> Result<&mut Self, validate::ValidationError<P>> {
+        validate::line_coordinates(self, &coords)?;
+
+        let color = self.to_pixel(color);
+        for coord in BresenhamLine::new(&coords) {
+            self.put_pixel(coord, color)?;
         }
 
-        let slope = coords.slope().abs();
-
-        if slope == 1.0 {
-            Image::draw_diagonal_line(self, color, coords)
-        } else if slope == 0.0 {
-            Image::draw_horizontal_line(self, color, coords)
-        } else if slope == f32::INFINITY {
-            Image::draw_vertical_line(self, color, coords)
-        } else if slope > 1.0 {
-            Image::bresenham_slope_greater_than_1(self, color, coords)
-        } else if slope > 0.0 && slope < 1.0 {
-            Image::bresenham_general(self, color, coords)
-        } else {
-            panic!("Caught case in that doesn't fit Bresenham Line Algorithm Implementation. Input was {coords} and slope was {slope}");
+        Ok(self)
+    }
+
+    /// Renders a dashed line using Bresenham's Line Algorithm, where the dash pattern starts at
+    /// the first endpoint & is measured in pixels along the line.
+    ///
+    /// Like draw_line_bresenham, pixels are overwritten with the color rather than blended.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use libppm::colors::WHITE;
+    /// use libppm::coordinate::LineCoordinates;
+    /// use libppm::graphics::dash::DashPattern;
+    /// use libppm::graphics::image::Image;
+    ///
+    /// let dash = DashPattern::new(&[2.0, 1.0], 0.0).unwrap();
+    /// let mut image = Image::builder().rows(1).cols(6).build().unwrap();
+    /// image
+    ///     .draw_dashed_line(WHITE, &dash, LineCoordinates::new(0, 0, 0, 5))
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     image.get_data(),
+    ///     &vec![0x00_FF_FF_FF, 0x00_FF_FF_FF, 0, 0x00_FF_FF_FF, 0x00_FF_FF_FF, 0]
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return ValidationError::OutOfBoundsInImageError if either endpoint is outside of the
+    /// image, in which case nothing is drawn.
+    pub fn draw_dashed_line(
+        &mut self,
+        color: impl Into<Color>,
+        dash: &DashPattern,
+        coords: coordinate::LineCoordinates,
+    ) -> Result<&mut Self, validate::ValidationError<P>> {
+        validate::line_coordinates(self, &coords)?;
+
+        let color = self.to_pixel(color);
+        for coord in BresenhamLine::new(&coords) {
+            if dash.is_on(distance_along(&coords, coord)) {
+                self.put_pixel(coord, color)?;
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Draws lines through each of the given points in turn, using Bresenham's Line Algorithm.
+    ///
+    /// Unlike draw_line_bresenham, which overwrites pixels, the color is blended over the image
+    /// as by blend_pixel. Each pixel is blended once, even where segments meet or cross, so
+    /// translucent colors are even along the whole polyline.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use libppm::colors::{BLACK, WHITE};
+    /// use libppm::coordinate::Coordinate;
+    /// use libppm::graphics::image::Image;
+    ///
+    /// let points = [
+    ///     Coordinate::new(0, 0),
+    ///     Coordinate::new(0, 3),
+    ///     Coordinate::new(3, 3),
+    /// ];
+    /// let mut image = Image::builder().rows(4).cols(4).build().unwrap();
+    /// image
+    ///     .fill(BLACK)
+    ///     .draw_polyline(WHITE.with_alpha(0x80), &points)
+    ///     .unwrap();
+    ///
+    /// // The corner is no darker than the rest of the polyline
+    /// assert_eq!(image.get_pixel(Coordinate::new(0, 2)).unwrap(), 0x00_80_80_80);
+    /// assert_eq!(image.get_pixel(Coordinate::new(0, 3)).unwrap(), 0x00_80_80_80);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return ValidationError::OutOfBoundsInImageError if any of the points is outside of
+    /// the image, in which case nothing is drawn.
+    pub fn draw_polyline(
+        &mut self,
+        color: impl Into<Color>,
+        points: &[coordinate::Coordinate],
+    ) -> Result<&mut Self, validate::ValidationError<P>> {
+        self.draw_path(color.into(), None, points, false)
+    }
+
+    /// Draws the outline of a polygon, joining each of the given points in turn & the last point
+    /// back to the first, using Bresenham's Line Algorithm. Like draw_polyline, each pixel is
+    /// blended with the color once.
+    ///
+    /// # Errors
+    ///
+    /// Will return ValidationError::OutOfBoundsInImageError if any of the points is outside of
+    /// the image, in which case nothing is drawn.
+    pub fn draw_polygon(
+        &mut self,
+        color: impl Into<Color>,
+        points: &[coordinate::Coordinate],
+    ) -> Result<&mut Self, validate::ValidationError<P>> {
+        self.draw_path(color.into(), None, points, true)
+    }
+
+    /// Draws a dashed polyline, like draw_polyline, where the dash pattern starts at the first
+    /// point & carries on around each corner, measured in pixels along the polyline.
+    ///
+    /// # Errors
+    ///
+    /// Will return ValidationError::OutOfBoundsInImageError if any of the points is outside of
+    /// the image, in which case nothing is drawn.
+    pub fn draw_dashed_polyline(
+        &mut self,
+        color: impl Into<Color>,
+        dash: &DashPattern,
+        points: &[coordinate::Coordinate],
+    ) -> Result<&mut Self, validate::ValidationError<P>> {
+        self.draw_path(color.into(), Some(dash), points, false)
+    }
+
+    /// Draws the dashed outline of a polygon, like draw_polygon, where the dash pattern starts at
+    /// the first point & carries on around each corner, measured in pixels along the outline.
+    ///
+    /// # Errors
+    ///
+    /// Will return ValidationError::OutOfBoundsInImageError if any of the points is outside of
+    /// the image, in which case nothing is drawn.
+    pub fn draw_dashed_polygon(
+        &mut self,
+        color: impl Into<Color>,
+        dash: &DashPattern,
+        points: &[coordinate::Coordinate],
+    ) -> Result<&mut Self, validate::ValidationError<P>> {
+        self.draw_path(color.into(), Some(dash), points, true)
+    }
+
+    /// Blends each pixel of the lines through the points once, skipping the pixels a dash pattern
+    /// turns off, where the pattern is on at the first time a pixel is reached.
+    fn draw_path(
+        &mut self,
+        color: Color,
+        dash: Option<&DashPattern>,
+        points: &[coordinate::Coordinate],
+        closed: bool,
+    ) -> Result<&mut Self, validate::ValidationError<P>> {
+        for point in points {
+            validate::coordinate(self, point)?;
         }
+
+        let mut segments: Vec<coordinate::LineCoordinates> = points
+            .windows(2)
+            .map(|pair| {
+                coordinate::LineCoordinates::new(pair[0].x, pair[0].y, pair[1].x, pair[1].y)
+            })
+            .collect();
+        if let (true, Some(first), Some(last)) = (closed, points.first(), points.last()) {
+            segments.push(coordinate::LineCoordinates::new(
+                last.x, last.y, first.x, first.y,
+            ));
+        }
+        if let [point] = points {
+            segments.push(coordinate::LineCoordinates::new(
+                point.x, point.y, point.x, point.y,
+            ));
+        }
+
+        let mut drawn = HashSet::new();
+        let mut length = 0.0;
+        for segment in &segments {
+            // BresenhamLine may start from either end, but distances are measured from the first
+            let mut pixels: Vec<coordinate::Coordinate> = BresenhamLine::new(segment).collect();
+            if pixels.first() != Some(&segment.first) {
+                pixels.reverse();
+            }
+
+            for coord in pixels {
+                let on = match dash {
+                    Some(dash) => dash.is_on(length + distance_along(segment, coord)),
+                    None => true,
+                };
+                if drawn.insert((coord.x, coord.y)) && on {
+                    self.blend_pixel(coord, color, 1.0)?;
+                }
+            }
+            length += distance_along(segment, segment.second);
+        }
+
+        Ok(self)
     }
 
     /// Function to calculate the pixels to be rendered in a cartesian plane where both
@@ -73,12 +370,10 @@ impl Image {
     /// slope == 0
     pub fn draw_horizontal_line(
         &mut self,
-        color: u32,
+        color: impl Into<Color>,
         coords: coordinate::LineCoordinates,
-    ) -> Result<&mut Self, validate::ValidationError> {
-        if let Err(e) = validate::line_coordinates(&self, &coords) {
-            return Err(e);
-        }
+    ) -> Result<&mut Self, validate::ValidationError<P>> {
+        validate::line_coordinates(self, &coords)?;
 
         let coordinate::LineCoordinates {
             first: a,
@@ -86,8 +381,9 @@ impl Image {
         } = coords.ensure_x_lr();
 
         // Only x increments
+        let color = self.to_pixel(color);
         for x_coord in a.x..=b.x {
-            self.set_pixel(coordinate::Coordinate::new(x_coord, a.y), color)?;
+            self.put_pixel(coordinate::Coordinate::new(x_coord, a.y), color)?;
         }
 
         Ok(self)
@@ -99,12 +395,10 @@ impl Image {
     /// slope == INFINITY
     pub fn draw_vertical_line(
         &mut self,
-        color: u32,
+        color: impl Into<Color>,
         coords: coordinate::LineCoordinates,
-    ) -> Result<&mut Self, validate::ValidationError> {
-        if let Err(e) = validate::line_coordinates(&self, &coords) {
-            return Err(e);
-        }
+    ) -> Result<&mut Self, validate::ValidationError<P>> {
+        validate::line_coordinates(self, &coords)?;
 
         let coordinate::LineCoordinates {
             first: a,
@@ -112,109 +406,128 @@ impl Image {
         } = coords.ensure_y_lr();
 
         // Only y increments
+        let color = self.to_pixel(color);
         for y_coord in a.y..=b.y {
-            self.set_pixel(coordinate::Coordinate::new(a.x, y_coord), color)?;
+            self.put_pixel(coordinate::Coordinate::new(a.x, y_coord), color)?;
         }
 
         Ok(self)
     }
+}
 
-    /// Function to calculate the pixels to be rendered in a cartesian plane where both
-    /// coordinates are within the space enclosed by the image (origin is at the top left of the
-    /// image) and the slope of the line represented by the LineCoordinates provided is:
-    /// slope == 1
-    fn draw_diagonal_line(
-        &mut self,
-        color: u32,
-        coords: coordinate::LineCoordinates,
-    ) -> Result<&mut Self, validate::ValidationError> {
-        if let Err(e) = validate::line_coordinates(&self, &coords) {
-            return Err(e);
-        }
+/// Distance from the first endpoint of a line to where a pixel is on the line.
+fn distance_along(coords: &coordinate::LineCoordinates, coord: coordinate::Coordinate) -> f32 {
+    let (dx, dy) = (
+        coords.second.x as f32 - coords.first.x as f32,
+        coords.second.y as f32 - coords.first.y as f32,
+    );
+    let length = (dx * dx + dy * dy).sqrt();
+    if length == 0.0 {
+        return 0.0;
+    }
 
-        let coordinate::LineCoordinates {
-            first: a,
-            second: b,
-        } = coords.ensure_x_lr();
+    ((coord.x as f32 - coords.first.x as f32) * dx + (coord.y as f32 - coords.first.y as f32) * dy)
+        / length
+}
 
-        // Should be lesser x of the two
-        let mut point = coordinate::Coordinate::new(a.x, a.y);
+/// Pixels of a line, from Bresenham's Line Algorithm, that work for lines in every direction
+///
+/// Each step along the major axis, whichever of x & y changes the most, has exactly one pixel,
+/// the one nearest to the exact line. Halfway cases are rounded towards the endpoint with the
+/// larger coordinate along the major axis, so that both directions give the same pixels.
+///
+/// # Example
+///
+/// ```
+/// use libppm::coordinate::{Coordinate, LineCoordinates};
+/// use libppm::graphics::lines::BresenhamLine;
+///
+/// let pixels: Vec<Coordinate> = BresenhamLine::new(&LineCoordinates::new(0, 3, 2, 0)).collect();
+/// assert_eq!(
+///     pixels,
+///     vec![
+///         Coordinate::new(2, 0),
+///         Coordinate::new(1, 1),
+///         Coordinate::new(1, 2),
+///         Coordinate::new(0, 3)
+///     ]
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct BresenhamLine {
+    major: i64,
+    minor: i64,
+    minor_step: i64,
+    steep: bool,
+    // Twice the change along each axis, so that the error term stays an integer
+    major_delta: i64,
+    minor_delta: i64,
+    error: i64,
+    remaining: u64,
+}
 
-        // x & y increment together
-        for _ in a.y..=b.y {
-            self.set_pixel(point, color)?;
-            point.x += 1;
-            point.y += 1;
-        }
+impl BresenhamLine {
+    pub fn new(coords: &coordinate::LineCoordinates) -> Self {
+        let (dx, dy) = (
+            coords.second.x as i64 - coords.first.x as i64,
+            coords.second.y as i64 - coords.first.y as i64,
+        );
+        let steep = dy.abs() > dx.abs();
 
-        Ok(self)
-    }
+        // Step along the major axis from its smaller coordinate
+        let (start, end) = if steep {
+            let coordinate::LineCoordinates { first, second } = coords.ensure_y_lr();
+            ((first.y, first.x), (second.y, second.x))
+        } else {
+            let coordinate::LineCoordinates { first, second } = coords.ensure_x_lr();
+            ((first.x, first.y), (second.x, second.y))
+        };
 
-    /// Private function to calculate the pixels to be rendered in a cartesian plane where both
-    /// coordinates are within the space enclosed by the image (origin is at the top left of the
-    /// image) and the slope of the line represented by the LineCoordinates provided is:
-    /// 1 < slope
-    fn bresenham_slope_greater_than_1(
-        &mut self,
-        color: u32,
-        coords: coordinate::LineCoordinates,
-    ) -> Result<&mut Self, validate::ValidationError> {
-        // Assume that this function was called from draw_line_bresenham & coordinates have alredy
-        // been validated.
-        let coordinate::LineCoordinates {
-            first: a,
-            second: b,
-        } = coords.ensure_y_lr();
+        let major_length = end.0 as i64 - start.0 as i64;
+        let minor_length = end.1 as i64 - start.1 as i64;
 
-        let (dx, dy) = a.delta_wrt(&b);
-        let mut d = 2 * dx - dy;
-        let mut x = a.x;
-
-        for y in a.y..=b.y {
-            let coord = coordinate::Coordinate::new(x, y);
-            self.set_pixel(coord, color)?;
-            if d > 0 {
-                d = d + (2 * dx - 2 * dy);
-                x += 1;
-            } else {
-                d = d + 2 * dx;
-            }
+        BresenhamLine {
+            major: start.0 as i64,
+            minor: start.1 as i64,
+            minor_step: minor_length.signum(),
+            steep,
+            major_delta: 2 * major_length,
+            minor_delta: 2 * minor_length.abs(),
+            error: major_length,
+            remaining: major_length as u64 + 1,
         }
-
-        Ok(self)
     }
+}
 
-    /// Private function to calculate the pixels to be rendered in a cartesian plane where both
-    /// coordinates are within the space enclosed by the image (origin is at the top left of the
-    /// image) and the slope of the line represented by the LineCoordinates provided is:
-    /// 0 < slope < 1
-    fn bresenham_general(
-        &mut self,
-        color: u32,
-        coords: coordinate::LineCoordinates,
-    ) -> Result<&mut Self, validate::ValidationError> {
-        // Assume that this function was called from draw_line_bresenham & coordinates have alredy
-        // been validated.
-        let coordinate::LineCoordinates {
-            first: a,
-            second: b,
-        } = coords.ensure_x_lr();
+impl Iterator for BresenhamLine {
+    type Item = coordinate::Coordinate;
 
-        let (dx, dy) = a.delta_wrt(&b);
-        let mut d = 2 * dy - dx;
-        let mut y = a.y;
-
-        for x in a.x..=b.x {
-            self.set_pixel(coordinate::Coordinate::new(x, y), color)?;
-            if d > 0 {
-                d = d + (2 * dy - 2 * dx);
-                y += 1;
-            } else {
-                d = d + 2 * dy;
-            }
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
         }
 
-        Ok(self)
+        // Both values are between the endpoints, so fit in an i32
+        let coord = if self.steep {
+            coordinate::Coordinate::new(self.minor as i32, self.major as i32)
+        } else {
+            coordinate::Coordinate::new(self.major as i32, self.minor as i32)
+        };
+
+        self.remaining -= 1;
+        self.major += 1;
+        self.error += self.minor_delta;
+        if self.error >= self.major_delta {
+            self.error -= self.major_delta;
+            self.minor += self.minor_step;
+        }
+
+        Some(coord)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = usize::try_from(self.remaining).ok();
+        (remaining.unwrap_or(usize::MAX), remaining)
     }
 }
 
@@ -413,4 +726,398 @@ mod tests {
 
         Ok(())
     }
+
+    /// Pixels nearest to the exact line, one per step along the major axis, with halfway cases
+    /// rounded away from the endpoint with the smaller major coordinate.
+    fn reference_line(a: (i32, i32), b: (i32, i32)) -> Vec<(i32, i32)> {
+        let steep = (b.1 - a.1).abs() > (b.0 - a.0).abs();
+        let swap = |(x, y): (i32, i32)| if steep { (y, x) } else { (x, y) };
+        let (mut start, mut end) = (swap(a), swap(b));
+        if start.0 > end.0 {
+            std::mem::swap(&mut start, &mut end);
+        }
+
+        let major = (end.0 - start.0) as f64;
+        let minor = (end.1 - start.1) as f64;
+        let mut pixels: Vec<(i32, i32)> = (start.0..=end.0)
+            .map(|m| {
+                let offset = if major == 0.0 {
+                    0.0
+                } else {
+                    ((m - start.0) as f64 * minor.abs() / major + 0.5).floor()
+                };
+                swap((m, start.1 + (offset * minor.signum()) as i32))
+            })
+            .collect();
+        pixels.sort();
+        pixels
+    }
+
+    #[test]
+    fn test_draw_line_bresenham_every_direction() -> Result<(), Box<dyn Error>> {
+        use crate::coordinate::{Coordinate, LineCoordinates};
+
+        let size = 9;
+        let grid: Vec<(i32, i32)> = (0..size)
+            .flat_map(|x| (0..size).map(move |y| (x, y)))
+            .collect();
+
+        for &a in &grid {
+            for &b in &grid {
+                let coords = LineCoordinates::new(a.0, a.1, b.0, b.1);
+                let expected = reference_line(a, b);
+
+                // Pixels come in order from one endpoint to the other, each touching the last
+                let pixels: Vec<Coordinate> = BresenhamLine::new(&coords).collect();
+                assert_eq!(pixels.len(), expected.len());
+                assert!(
+                    pixels.first() == Some(&coords.first) || pixels.first() == Some(&coords.second)
+                );
+                assert!(
+                    pixels.last() == Some(&coords.first) || pixels.last() == Some(&coords.second)
+                );
+                assert!(pixels
+                    .windows(2)
+                    .all(|pair| (pair[0].x - pair[1].x).abs() <= 1
+                        && (pair[0].y - pair[1].y).abs() <= 1));
+
+                let mut image = Image::builder()
+                    .rows(size as usize)
+                    .cols(size as usize)
+                    .build()?;
+                image.draw_line_bresenham(WHITE, coords)?;
+                let drawn: Vec<(i32, i32)> = grid
+                    .iter()
+                    .copied()
+                    .filter(|&(x, y)| {
+                        image
+                            .get_pixel(Coordinate::new(x, y))
+                            .is_ok_and(|pixel| pixel != 0)
+                    })
+                    .collect();
+                assert_eq!(drawn, expected, "line from {:?} to {:?}", a, b);
+            }
+        }
+
+        // Far apart endpoints don't overflow
+        let coords = LineCoordinates::new(i32::MIN, i32::MAX, i32::MAX, i32::MIN);
+        let mut pixels = BresenhamLine::new(&coords);
+        assert_eq!(pixels.size_hint(), (1 << 32, Some(1 << 32)));
+        assert_eq!(pixels.next(), Some(Coordinate::new(i32::MIN, i32::MAX)));
+        assert_eq!(
+            pixels.next(),
+            Some(Coordinate::new(i32::MIN + 1, i32::MAX - 1))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_draw_dashed_line() -> Result<(), Box<dyn Error>> {
+        use crate::coordinate::{Coordinate, LineCoordinates};
+        use crate::graphics::dash::DashPattern;
+
+        let drawn = |image: &Image| -> Vec<bool> {
+            image.get_data().iter().map(|pixel| *pixel != 0).collect()
+        };
+
+        // The pattern starts at the first endpoint, whichever way the line goes
+        let dash = DashPattern::new(&[3.0, 2.0], 0.0)?;
+        let mut image = Image::builder().rows(1).cols(12).build()?;
+        image.draw_dashed_line(WHITE, &dash, LineCoordinates::new(0, 0, 0, 11))?;
+        assert_eq!(
+            drawn(&image),
+            [true, true, true, false, false, true, true, true, false, false, true, true]
+        );
+
+        let mut image = Image::builder().rows(1).cols(12).build()?;
+        image.draw_dashed_line(WHITE, &dash, LineCoordinates::new(0, 11, 0, 0))?;
+        assert_eq!(
+            drawn(&image),
+            [true, true, false, false, true, true, true, false, false, true, true, true]
+        );
+
+        // Distances are measured along the line, so diagonal dashes are fewer pixels long
+        let dash = DashPattern::new(&[3.0, 3.0], 0.0)?;
+        let mut image = Image::builder().rows(6).cols(6).build()?;
+        image.draw_dashed_line(WHITE, &dash, LineCoordinates::new(0, 0, 5, 5))?;
+        let diagonal: Vec<bool> = (0..6)
+            .map(|step| {
+                image
+                    .get_pixel(Coordinate::new(step, step))
+                    .is_ok_and(|pixel| pixel != 0)
+            })
+            .collect();
+        assert_eq!(diagonal, [true, true, true, false, false, true]);
+
+        // Without gaps, the same pixels are drawn as a solid line
+        let solid = DashPattern::new(&[1.0, 0.0], 0.5)?;
+        for (a_x, a_y, b_x, b_y) in [(0, 0, 5, 2), (5, 1, 0, 4), (2, 5, 3, 0), (4, 4, 4, 4)] {
+            let coords = LineCoordinates::new(a_x, a_y, b_x, b_y);
+            let mut dashed = Image::builder().rows(6).cols(6).build()?;
+            dashed.draw_dashed_line(WHITE, &solid, coords)?;
+
+            let mut image = Image::builder().rows(6).cols(6).build()?;
+            image.draw_line_bresenham(WHITE, LineCoordinates::new(a_x, a_y, b_x, b_y))?;
+            assert_eq!(dashed, image);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_draw_polyline_and_polygon() -> Result<(), Box<dyn Error>> {
+        use crate::coordinate::{Coordinate, LineCoordinates};
+        use crate::graphics::image::ImageBuilder;
+        use crate::graphics::pixel::Rgb32F;
+
+        let points = [
+            Coordinate::new(1, 1),
+            Coordinate::new(1, 14),
+            Coordinate::new(12, 3),
+            Coordinate::new(14, 12),
+            Coordinate::new(2, 8),
+        ];
+        let segments = |closed: bool| {
+            let mut segments: Vec<LineCoordinates> = points
+                .windows(2)
+                .map(|pair| LineCoordinates::new(pair[0].x, pair[0].y, pair[1].x, pair[1].y))
+                .collect();
+            if closed {
+                segments.push(LineCoordinates::new(2, 8, 1, 1));
+            }
+            segments
+        };
+
+        for closed in [false, true] {
+            // Opaque colors draw the same pixels as each of the lines
+            let mut lines = Image::builder().rows(16).cols(16).build()?;
+            for segment in segments(closed) {
+                lines.draw_line_bresenham(WHITE, segment)?;
+            }
+            let mut image = Image::builder().rows(16).cols(16).build()?;
+            if closed {
+                image.draw_polygon(WHITE, &points)?;
+            } else {
+                image.draw_polyline(WHITE, &points)?;
+            }
+            assert_eq!(image, lines);
+
+            // Translucent colors are blended once, even at corners & crossings
+            let mut image = ImageBuilder::<Rgb32F>::new().rows(16).cols(16).build()?;
+            if closed {
+                image.draw_polygon(WHITE.with_alpha(0x80), &points)?;
+            } else {
+                image.draw_polyline(WHITE.with_alpha(0x80), &points)?;
+            }
+            for (pixel, line_pixel) in image.get_data().iter().zip(lines.get_data()) {
+                let expected = if *line_pixel == 0 { 0.0 } else { 128.0 / 255.0 };
+                assert_eq!(pixel, &Rgb32F([expected; 3]));
+            }
+        }
+
+        // A single point, & no points at all
+        let mut image = Image::builder().rows(4).cols(4).build()?;
+        image.draw_polyline(WHITE, &[Coordinate::new(2, 1)])?;
+        image.draw_polygon(WHITE, &[])?;
+        assert_eq!(
+            image.get_data().iter().filter(|pixel| **pixel != 0).count(),
+            1
+        );
+        assert_eq!(image.get_pixel(Coordinate::new(2, 1))?, 0x00_FF_FF_FF);
+
+        let result = image.draw_polygon(WHITE, &[Coordinate::new(0, 0), Coordinate::new(0, 4)]);
+        assert!(matches!(
+            result,
+            Err(ValidationError::OutOfBoundsInImageError(coord, _)) if coord == Coordinate::new(0, 4)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_draw_dashed_polyline_and_polygon() -> Result<(), Box<dyn Error>> {
+        use crate::coordinate::{Coordinate, LineCoordinates};
+        use crate::graphics::dash::DashPattern;
+
+        // The pattern carries on through the points, rather than restarting
+        let dash = DashPattern::new(&[3.0, 2.0], 1.0)?;
+        let mut line = Image::builder().rows(4).cols(16).build()?;
+        line.draw_dashed_line(WHITE, &dash, LineCoordinates::new(1, 0, 1, 15))?;
+        let mut polyline = Image::builder().rows(4).cols(16).build()?;
+        polyline.draw_dashed_polyline(
+            WHITE,
+            &dash,
+            &[
+                Coordinate::new(1, 0),
+                Coordinate::new(1, 4),
+                Coordinate::new(1, 7),
+                Coordinate::new(1, 15),
+            ],
+        )?;
+        assert_eq!(polyline, line);
+
+        // Around the corners of a square, 4 on & 2 off is 2 dashes a side
+        let dash = DashPattern::new(&[4.0, 2.0], 0.0)?;
+        let mut image = Image::builder().rows(13).cols(13).build()?;
+        image.draw_dashed_polygon(
+            WHITE,
+            &dash,
+            &[
+                Coordinate::new(0, 0),
+                Coordinate::new(0, 12),
+                Coordinate::new(12, 12),
+                Coordinate::new(12, 0),
+            ],
+        )?;
+        let top: Vec<bool> = image.get_data()[..13]
+            .iter()
+            .map(|pixel| *pixel != 0)
+            .collect();
+        assert_eq!(
+            top,
+            [true, true, true, true, false, false, true, true, true, true, false, false, true]
+        );
+        let right: Vec<bool> = (0..13)
+            .map(|row| {
+                image
+                    .get_pixel(Coordinate::new(row, 12))
+                    .is_ok_and(|pixel| pixel != 0)
+            })
+            .collect();
+        assert_eq!(
+            right,
+            [true, true, true, true, false, false, true, true, true, true, false, false, true]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_draw_line_wu() -> Result<(), Box<dyn Error>> {
+        use crate::coordinate::{Coordinate, FloatLineCoordinates, LineCoordinates};
+        use crate::graphics::image::ImageBuilder;
+        use crate::graphics::pixel::Rgb32F;
+
+        // On a black image, the red channel of each pixel is how much of it the line covers
+        let blank = ImageBuilder::<Rgb32F>::new().rows(9).cols(9).build()?;
+        let coverage = |image: &Image<Rgb32F>, x: i32, y: i32| {
+            image.get_pixel(Coordinate::new(x, y)).unwrap().0[0]
+        };
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+
+        // Lines from the center in every direction, including the axes & diagonals, & a line of
+        // zero length, which covers nothing
+        let ends = [0, 2, 4, 6, 8]
+            .iter()
+            .flat_map(|&i| [(0, i), (8, i), (i, 0), (i, 8)])
+            .chain([(4, 4)]);
+        for (x, y) in ends {
+            let mut image = blank.clone();
+            image.draw_line_wu(WHITE, LineCoordinates::new(4, 4, x, y))?;
+
+            // Drawn the same in both directions
+            let mut reversed = blank.clone();
+            reversed.draw_line_wu(WHITE, LineCoordinates::new(x, y, 4, 4))?;
+            assert_eq!(image, reversed);
+
+            // Every step along the major axis is covered once, & each endpoint half covered
+            let steep = (y - 4).abs() > (x - 4).abs();
+            let (start, end) = if steep {
+                (4.min(y), 4.max(y))
+            } else {
+                (4.min(x), 4.max(x))
+            };
+            for step in 0..9 {
+                let covered: f32 = (0..9)
+                    .map(|minor| {
+                        if steep {
+                            coverage(&image, minor, step)
+                        } else {
+                            coverage(&image, step, minor)
+                        }
+                    })
+                    .sum();
+                let expected = match step {
+                    _ if start == end => 0.0,
+                    _ if step == start || step == end => 0.5,
+                    _ if step > start && step < end => 1.0,
+                    _ => 0.0,
+                };
+                assert!(
+                    close(covered, expected),
+                    "{:?} at {}: {}",
+                    (x, y),
+                    step,
+                    covered
+                );
+            }
+        }
+
+        // Sub-pixel endpoints split coverage between neighboring pixels
+        let mut image = blank.clone();
+        image.draw_line_wu(WHITE, FloatLineCoordinates::new(1.25, 0.0, 1.25, 8.0))?;
+        assert!(close(coverage(&image, 1, 4), 0.75));
+        assert!(close(coverage(&image, 2, 4), 0.25));
+        assert!(close(coverage(&image, 1, 8), 0.375));
+
+        // Lines along the edge of the image only draw the pixels within it
+        let mut image = blank.clone();
+        image.draw_line_wu(WHITE, FloatLineCoordinates::new(8.4, 0.0, 8.4, 8.0))?;
+        assert!(close(coverage(&image, 8, 4), 0.6));
+
+        // Short lines cover their pixels by their length, on either side of a pixel boundary
+        for (end, covered) in [
+            (1.0, [0.0, 0.0]),
+            (1.2, [0.2, 0.0]),
+            (1.49, [0.49, 0.0]),
+            (1.51, [0.5, 0.01]),
+        ] {
+            let mut image = blank.clone();
+            image.draw_line_wu(WHITE, FloatLineCoordinates::new(1.0, 1.0, 1.0, end))?;
+            assert!(close(coverage(&image, 1, 1), covered[0]), "1.0 to {}", end);
+            assert!(close(coverage(&image, 1, 2), covered[1]), "1.0 to {}", end);
+            let total: f32 = (0..9)
+                .flat_map(|x| (0..9).map(move |y| (x, y)))
+                .map(|(x, y)| coverage(&image, x, y))
+                .sum();
+            assert!(close(total, end - 1.0), "1.0 to {}: {}", end, total);
+        }
+
+        // Colors are blended into the existing pixels
+        let mut image = Image::builder().rows(3).cols(3).build()?;
+        image
+            .fill(BLACK)
+            .draw_line_wu(WHITE.with_alpha(0x80), LineCoordinates::new(0, 0, 2, 2))?
+            .draw_line_wu(WHITE.with_alpha(0x80), LineCoordinates::new(0, 2, 2, 0))?;
+        assert_eq!(image.get_pixel(Coordinate::new(1, 1))?, 0x00_C0_C0_C0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_draw_line_wu_with_invalid_coordinates() -> Result<(), Box<dyn Error>> {
+        use crate::coordinate::FloatLineCoordinates;
+
+        let mut image = Image::builder().rows(8).cols(8).build()?;
+        image.fill(MAGENTA);
+        let original = image.clone();
+
+        for coords in [
+            FloatLineCoordinates::new(0.0, 0.0, 7.5, 7.0),
+            FloatLineCoordinates::new(-0.6, 0.0, 7.0, 7.0),
+            FloatLineCoordinates::new(0.0, f32::NAN, 7.0, 7.0),
+            FloatLineCoordinates::new(0.0, 0.0, f32::INFINITY, 7.0),
+        ] {
+            match image.draw_line_wu(BLACK, coords) {
+                Err(ValidationError::OutOfBoundsInImageError(_, unchanged)) => {
+                    assert_eq!(unchanged, original);
+                }
+                _ => panic!("Expected {} to be out of bounds.", coords.first),
+            }
+        }
+        assert_eq!(image, original);
+
+        Ok(())
+    }
 }
@@ -1,3 +1,8 @@
+pub mod alpha;
+pub mod bitmap;
 pub mod circles;
+pub mod dash;
 pub mod image;
 pub mod lines;
+pub mod pixel;
+pub mod stroke;
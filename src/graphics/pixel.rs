@@ -0,0 +1,284 @@
+//! Pixel types an Image can be made of
+//!
+//! Every pixel type converts to & from normalized red, green, blue & alpha channels, each ranging
+//! from 0.0 to 1.0, so that images of any pixel type can be converted to one another with
+//! `Image::convert`. The default pixel type of an Image is a packed `u32` - 0xAA_RR_GG_BB - while
+//! the codecs also read & write `Rgb16`, `Luma8` & `Luma16` images.
+
+use crate::colors::Color;
+use crate::graphics::image::ColorMode;
+use std::fmt::Debug;
+
+/// A single pixel of an Image
+pub trait Pixel: Copy + Debug + Default + PartialEq {
+    /// Whether the pixel has room for an alpha channel.
+    const HAS_ALPHA: bool;
+
+    /// Color mode of images built from this pixel type, unless one is provided.
+    const COLOR_MODE: ColorMode = ColorMode::Rgb;
+
+    /// Red, green, blue & alpha channels, each ranging from 0.0 to 1.0. Pixels without an alpha
+    /// channel are opaque.
+    fn to_rgba(self) -> [f32; 4];
+
+    /// Builds a pixel from red, green, blue & alpha channels, each ranging from 0.0 to 1.0.
+    /// Channels out of that range are clamped, except by floating point pixels.
+    fn from_rgba(rgba: [f32; 4]) -> Self;
+}
+
+/// 8 bits per channel RGB
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Rgb8(pub [u8; 3]);
+
+/// 8 bits per channel RGB with straight alpha, where 255 is opaque
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Rgba8(pub [u8; 4]);
+
+/// 8 bit grayscale, from 0 (black) to 255 (white), e.g. for masks
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Luma8(pub u8);
+
+/// 16 bit grayscale, from 0 (black) to 65535 (white), e.g. for depth maps
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Luma16(pub u16);
+
+/// 16 bits per channel RGB
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Rgb16(pub [u16; 3]);
+
+/// Floating point RGB, where 0.0 to 1.0 is the displayable range of each channel
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct Rgb32F(pub [f32; 3]);
+
+/// Luma weights from ITU-R BT.601, the same weights used by ppmtopgm, in thousandths.
+const LUMA_WEIGHTS: [u32; 3] = [299, 587, 114];
+
+/// Calculates the luma of a color, ignoring alpha
+///
+/// # Example
+///
+/// ```
+/// use libppm::colors::{BLACK, LIME, WHITE};
+/// use libppm::graphics::pixel::luma;
+///
+/// assert_eq!(luma(BLACK), 0);
+/// assert_eq!(luma(WHITE), 255);
+/// assert_eq!(luma(LIME), 150);
+/// ```
+pub fn luma(color: impl Into<Color>) -> u8 {
+    let Color { r, g, b, .. } = color.into();
+    let weighted: u32 = [r, g, b]
+        .iter()
+        .zip(LUMA_WEIGHTS)
+        .map(|(channel, weight)| *channel as u32 * weight)
+        .sum();
+
+    ((weighted + 500) / 1000) as u8
+}
+
+/// Scales a sample ranging from 0 to `from_max` to the nearest value ranging from 0 to `to_max`.
+///
+/// # Example
+///
+/// ```
+/// use libppm::graphics::pixel::rescale;
+///
+/// assert_eq!(rescale(255, 255, 65535), 65535);
+/// assert_eq!(rescale(1, 3, 255), 85);
+/// assert_eq!(rescale(32768, 65535, 255), 128);
+/// ```
+pub fn rescale(sample: u16, from_max: u16, to_max: u16) -> u16 {
+    if from_max == to_max {
+        return sample;
+    }
+
+    let (sample, from_max, to_max) = (sample as u64, from_max as u64, to_max as u64);
+    ((sample * to_max + from_max / 2) / from_max) as u16
+}
+
+/// Normalizes an integer sample ranging from 0 to `max`.
+fn normalize(sample: u16, max: u16) -> f32 {
+    sample as f32 / max as f32
+}
+
+/// Converts a normalized channel to the nearest integer sample ranging from 0 to `max`.
+fn quantize(channel: f32, max: u16) -> u16 {
+    // NaN clamps to 0 when cast
+    (channel.clamp(0.0, 1.0) * max as f32).round() as u16
+}
+
+/// Luma of normalized red, green & blue channels, using the same weights as `luma`.
+fn normalized_luma([r, g, b, _]: [f32; 4]) -> f32 {
+    [r, g, b]
+        .iter()
+        .zip(LUMA_WEIGHTS)
+        .map(|(channel, weight)| channel * weight as f32 / 1000.0)
+        .sum()
+}
+
+impl Pixel for u32 {
+    /// The top byte is reserved for alpha, which the image's ColorMode decides whether to use.
+    const HAS_ALPHA: bool = true;
+
+    fn to_rgba(self) -> [f32; 4] {
+        let [a, r, g, b] = self.to_be_bytes();
+        [r, g, b, a].map(|channel| normalize(channel as u16, 255))
+    }
+
+    fn from_rgba([r, g, b, a]: [f32; 4]) -> Self {
+        u32::from_be_bytes([a, r, g, b].map(|channel| quantize(channel, 255) as u8))
+    }
+}
+
+impl Pixel for Rgb8 {
+    const HAS_ALPHA: bool = false;
+
+    fn to_rgba(self) -> [f32; 4] {
+        let [r, g, b] = self.0.map(|channel| normalize(channel as u16, 255));
+        [r, g, b, 1.0]
+    }
+
+    fn from_rgba([r, g, b, _]: [f32; 4]) -> Self {
+        Rgb8([r, g, b].map(|channel| quantize(channel, 255) as u8))
+    }
+}
+
+impl Pixel for Rgba8 {
+    const HAS_ALPHA: bool = true;
+    const COLOR_MODE: ColorMode = ColorMode::Rgba;
+
+    fn to_rgba(self) -> [f32; 4] {
+        self.0.map(|channel| normalize(channel as u16, 255))
+    }
+
+    fn from_rgba(rgba: [f32; 4]) -> Self {
+        Rgba8(rgba.map(|channel| quantize(channel, 255) as u8))
+    }
+}
+
+impl Pixel for Luma8 {
+    const HAS_ALPHA: bool = false;
+
+    fn to_rgba(self) -> [f32; 4] {
+        let gray = normalize(self.0 as u16, 255);
+        [gray, gray, gray, 1.0]
+    }
+
+    fn from_rgba(rgba: [f32; 4]) -> Self {
+        Luma8(quantize(normalized_luma(rgba), 255) as u8)
+    }
+}
+
+impl Pixel for Luma16 {
+    const HAS_ALPHA: bool = false;
+
+    fn to_rgba(self) -> [f32; 4] {
+        let gray = normalize(self.0, 65535);
+        [gray, gray, gray, 1.0]
+    }
+
+    fn from_rgba(rgba: [f32; 4]) -> Self {
+        Luma16(quantize(normalized_luma(rgba), 65535))
+    }
+}
+
+impl Pixel for Rgb16 {
+    const HAS_ALPHA: bool = false;
+
+    fn to_rgba(self) -> [f32; 4] {
+        let [r, g, b] = self.0.map(|channel| normalize(channel, 65535));
+        [r, g, b, 1.0]
+    }
+
+    fn from_rgba([r, g, b, _]: [f32; 4]) -> Self {
+        Rgb16([r, g, b].map(|channel| quantize(channel, 65535)))
+    }
+}
+
+impl Pixel for Rgb32F {
+    const HAS_ALPHA: bool = false;
+
+    fn to_rgba(self) -> [f32; 4] {
+        let [r, g, b] = self.0;
+        [r, g, b, 1.0]
+    }
+
+    fn from_rgba([r, g, b, _]: [f32; 4]) -> Self {
+        Rgb32F([r, g, b])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::{LIME, RED, WHITE};
+
+    #[test]
+    fn test_pixel_conversions() {
+        assert_eq!(Rgb8::from_rgba(RED.to_rgba()), Rgb8([255, 0, 0]));
+        assert_eq!(u32::from_rgba(Rgba8([1, 2, 3, 4]).to_rgba()), 0x04_01_02_03);
+        assert_eq!(Luma8::from_rgba(LIME.to_rgba()), Luma8(150));
+        assert_eq!(Luma16::from_rgba(WHITE.to_rgba()), Luma16(65535));
+        assert_eq!(
+            Rgb16::from_rgba(Rgb8([255, 128, 0]).to_rgba()),
+            Rgb16([65535, 32896, 0])
+        );
+
+        // Floating point pixels keep values out of the displayable range
+        let bright = Rgb32F([2.0, 0.5, -1.0]);
+        assert_eq!(Rgb32F::from_rgba(bright.to_rgba()), bright);
+        assert_eq!(Rgb8::from_rgba(bright.to_rgba()), Rgb8([255, 128, 0]));
+
+        // Every sample survives a round trip through the normalized channels
+        for sample in 0..=65535 {
+            assert_eq!(Luma16::from_rgba(Luma16(sample).to_rgba()), Luma16(sample));
+        }
+        for sample in 0..=255 {
+            assert_eq!(Luma8::from_rgba(Luma8(sample).to_rgba()), Luma8(sample));
+        }
+    }
+
+    #[test]
+    fn test_image_conversions() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::colors::{BLUE, GRAY, MAGENTA, TEAL};
+        use crate::graphics::image::{Image, ImageBuilder};
+
+        let mut image = Image::builder().rows(16).cols(16).build()?;
+        image.fill(MAGENTA).checkerboard(4, TEAL);
+
+        let image16 = image.convert::<Rgb16>();
+        assert_eq!(image16.get_data()[0], Rgb16([0, 0x8080, 0x8080]));
+        assert_eq!(image16.convert::<u32>(), image);
+
+        let image = Image::builder()
+            .rows(2)
+            .cols(2)
+            .data([RED, BLUE, GRAY, WHITE].map(u32::from).to_vec())
+            .build()?;
+
+        let gray_image = image.convert::<Luma8>();
+        assert_eq!(
+            gray_image.get_data(),
+            &[76, 29, 128, 255].map(Luma8).to_vec()
+        );
+        assert_eq!(
+            gray_image.get_data(),
+            &image
+                .get_data()
+                .iter()
+                .map(|pixel| Luma8(luma(*pixel)))
+                .collect::<Vec<_>>()
+        );
+
+        // Gray levels are unchanged by a round trip through an Image
+        assert_eq!(gray_image.convert::<u32>().convert::<Luma8>(), gray_image);
+        let depth = ImageBuilder::<Luma16>::new()
+            .rows(1)
+            .cols(3)
+            .data(vec![Luma16(0), Luma16(1), Luma16(65535)])
+            .build()?;
+        assert_eq!(depth.convert::<Rgb16>().convert::<Luma16>(), depth);
+
+        Ok(())
+    }
+}
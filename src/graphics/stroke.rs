@@ -0,0 +1,871 @@
+//! Lines of any width, with caps at their ends & joins between the segments of polylines
+//!
+//! A stroke is the union of simple shapes - a rectangle for each segment, plus discs, squares
+//! or triangles for the caps & joins - so overlapping shapes never cover a pixel twice. Pixels
+//! are blended with the fraction of them the stroke covers, measured with 64 samples per pixel.
+
+use crate::colors::Color;
+use crate::coordinate::{Coordinate, FloatCoordinate, FloatLineCoordinates};
+use crate::graphics::dash::DashPattern;
+use crate::graphics::image::Image;
+use crate::graphics::pixel::Pixel;
+use crate::validate;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Display;
+
+/// Samples of each pixel, one bit each in a u64. Every sample is on a row & column of its own, so
+/// edges in any direction, even nearly horizontal or vertical ones, are measured precisely.
+const SAMPLES: usize = 64;
+
+/// Columns between consecutive samples, which spreads the samples evenly over the pixel.
+const SAMPLE_STRIDE: usize = 29;
+
+/// Shape of the ends of an open stroke
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum LineCap {
+    /// Ends exactly at the endpoint.
+    #[default]
+    Butt,
+    /// Ends with a half disc around the endpoint.
+    Round,
+    /// Extends half of the width past the endpoint.
+    Square,
+}
+
+/// Shape of the corner where two segments of a polyline meet
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum LineJoin {
+    /// Extends the outer edges of both segments until they meet, unless that point is further
+    /// than the miter limit allows, in which case the corner is beveled.
+    #[default]
+    Miter,
+    /// Rounds the corner with a disc around the vertex.
+    Round,
+    /// Cuts the corner off with a straight line between the outer edges of both segments.
+    Bevel,
+}
+
+/// How lines are stroked: their width, caps, joins & dashes
+#[derive(Debug, PartialEq, Clone)]
+pub struct Stroke {
+    width: f32,
+    cap: LineCap,
+    join: LineJoin,
+    miter_limit: f32,
+    dash: Option<DashPattern>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct StrokeBuilder {
+    width: Option<f32>,
+    cap: LineCap,
+    join: LineJoin,
+    miter_limit: f32,
+    dash: Option<DashPattern>,
+}
+
+impl Stroke {
+    pub fn builder() -> StrokeBuilder {
+        StrokeBuilder::new()
+    }
+
+    pub fn get_width(&self) -> &f32 {
+        &self.width
+    }
+
+    pub fn get_cap(&self) -> &LineCap {
+        &self.cap
+    }
+
+    pub fn get_join(&self) -> &LineJoin {
+        &self.join
+    }
+
+    pub fn get_miter_limit(&self) -> &f32 {
+        &self.miter_limit
+    }
+
+    pub fn get_dash(&self) -> &Option<DashPattern> {
+        &self.dash
+    }
+
+    /// Shapes making up a stroke, or each of its dashes, through the given distinct points.
+    /// Dashes are stroked on their own, with caps at both ends.
+    fn shapes(&self, points: &[(f32, f32)]) -> Vec<Shape> {
+        let Some(dash) = &self.dash else {
+            return self.path_shapes(points);
+        };
+        if points.is_empty() {
+            return Vec::new();
+        }
+
+        // Distance along the path to each point
+        let mut distances = vec![0.0];
+        for segment in points.windows(2) {
+            let length = distance(segment[0], segment[1]);
+            distances.push(distances[distances.len() - 1] + length);
+        }
+        let total = distances[distances.len() - 1];
+
+        let point_at = |along: f32| {
+            let index = distances[1..]
+                .iter()
+                .position(|distance| along <= *distance)
+                .unwrap_or(points.len().saturating_sub(2));
+            let Some(&end) = points.get(index + 1) else {
+                return (points[index], (1.0, 0.0));
+            };
+            let start = points[index];
+            let length = distances[index + 1] - distances[index];
+            let fraction = ((along - distances[index]) / length).clamp(0.0, 1.0);
+            (
+                add(start, scale(sub(end, start), fraction)),
+                unit(sub(end, start)),
+            )
+        };
+
+        let mut shapes = Vec::new();
+        for (start, end) in dash.dashes(total) {
+            let (first, direction) = point_at(start);
+            if start == end {
+                shapes.extend(self.dot(first, direction));
+                continue;
+            }
+
+            let mut dash_points = vec![first];
+            dash_points.extend(
+                points
+                    .iter()
+                    .zip(&distances)
+                    .filter(|(_, distance)| start < **distance && **distance < end)
+                    .map(|(point, _)| *point),
+            );
+            dash_points.push(point_at(end).0);
+            dash_points.dedup();
+            shapes.extend(self.path_shapes(&dash_points));
+        }
+
+        shapes
+    }
+
+    /// Caps of a path of length zero, facing along `direction`.
+    fn dot(&self, point: (f32, f32), direction: (f32, f32)) -> Option<Shape> {
+        let half_width = self.width / 2.0;
+        let along = scale(direction, half_width);
+        let across = scale(perpendicular(direction), half_width);
+        match self.cap {
+            LineCap::Butt => None,
+            LineCap::Round => Some(Shape::Disc(point, half_width)),
+            LineCap::Square => Some(Shape::Polygon(vec![
+                sub(sub(point, along), across),
+                add(sub(point, along), across),
+                add(add(point, along), across),
+                sub(add(point, along), across),
+            ])),
+        }
+    }
+
+    /// Shapes making up a stroke through the given points, which are all distinct.
+    fn path_shapes(&self, points: &[(f32, f32)]) -> Vec<Shape> {
+        let half_width = self.width / 2.0;
+        let mut shapes = Vec::new();
+
+        let Some(&first) = points.first() else {
+            return shapes;
+        };
+        let last = points[points.len() - 1];
+
+        if points.len() == 1 {
+            // A single point has no direction, so square caps are aligned with the axes
+            shapes.extend(self.dot(first, (1.0, 0.0)));
+            return shapes;
+        }
+
+        for (index, segment) in points.windows(2).enumerate() {
+            let direction = unit(sub(segment[1], segment[0]));
+            let normal = scale(perpendicular(direction), half_width);
+
+            let mut start = segment[0];
+            let mut end = segment[1];
+            if self.cap == LineCap::Square {
+                if index == 0 {
+                    start = sub(start, scale(direction, half_width));
+                }
+                if index == points.len() - 2 {
+                    end = add(end, scale(direction, half_width));
+                }
+            }
+
+            shapes.push(Shape::Polygon(vec![
+                add(start, normal),
+                add(end, normal),
+                sub(end, normal),
+                sub(start, normal),
+            ]));
+        }
+
+        if self.cap == LineCap::Round {
+            shapes.push(Shape::Disc(first, half_width));
+            shapes.push(Shape::Disc(last, half_width));
+        }
+
+        for corner in points.windows(3) {
+            shapes.extend(self.join_shape(corner[0], corner[1], corner[2]));
+        }
+
+        shapes
+    }
+
+    /// Shape filling the gap on the outside of the corner at `vertex`.
+    fn join_shape(
+        &self,
+        previous: (f32, f32),
+        vertex: (f32, f32),
+        next: (f32, f32),
+    ) -> Option<Shape> {
+        let half_width = self.width / 2.0;
+        if self.join == LineJoin::Round {
+            return Some(Shape::Disc(vertex, half_width));
+        }
+
+        let incoming = unit(sub(vertex, previous));
+        let outgoing = unit(sub(next, vertex));
+        let turn = cross(incoming, outgoing);
+        if turn == 0.0 {
+            // Straight on leaves no gap, & turning straight back leaves nothing to bevel
+            return None;
+        }
+
+        // The gap is on the side the polyline turns away from
+        let side = if turn > 0.0 { -half_width } else { half_width };
+        let incoming_edge = add(vertex, scale(perpendicular(incoming), side));
+        let outgoing_edge = add(vertex, scale(perpendicular(outgoing), side));
+
+        // Ratio of the miter's length to the width of the stroke, 1 / sin(angle / 2) where angle
+        // is the angle between the segments
+        let miter_ratio = 1.0 / ((1.0 + dot(incoming, outgoing)) / 2.0).sqrt();
+        if self.join == LineJoin::Miter
+            && miter_ratio.is_finite()
+            && miter_ratio <= self.miter_limit
+        {
+            let bisector = unit(add(perpendicular(incoming), perpendicular(outgoing)));
+            let miter = add(vertex, scale(bisector, side * miter_ratio));
+            Some(Shape::Polygon(vec![
+                vertex,
+                incoming_edge,
+                miter,
+                outgoing_edge,
+            ]))
+        } else {
+            Some(Shape::Polygon(vec![vertex, incoming_edge, outgoing_edge]))
+        }
+    }
+}
+
+impl StrokeBuilder {
+    pub fn new() -> Self {
+        Self {
+            width: None,
+            cap: LineCap::default(),
+            join: LineJoin::default(),
+            miter_limit: 4.0,
+            dash: None,
+        }
+    }
+
+    pub fn width(&mut self, width: f32) -> &mut Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Sets the shape of the ends of the stroke. Defaults to LineCap::Butt.
+    pub fn cap(&mut self, cap: LineCap) -> &mut Self {
+        self.cap = cap;
+        self
+    }
+
+    /// Sets the shape of the corners of polylines. Defaults to LineJoin::Miter.
+    pub fn join(&mut self, join: LineJoin) -> &mut Self {
+        self.join = join;
+        self
+    }
+
+    /// Sets the longest a miter can be, relative to the width of the stroke, before the corner is
+    /// beveled instead. Defaults to 4.0, which bevels corners sharper than about 29 degrees.
+    pub fn miter_limit(&mut self, miter_limit: f32) -> &mut Self {
+        self.miter_limit = miter_limit;
+        self
+    }
+
+    /// Dashes the stroke, with each dash capped by the stroke's LineCap. Strokes are solid unless
+    /// a dash pattern is provided.
+    pub fn dash(&mut self, dash: DashPattern) -> &mut Self {
+        self.dash = Some(dash);
+        self
+    }
+
+    pub fn build(&self) -> Result<Stroke, StrokeBuilderError> {
+        let width = match self.width {
+            Some(width) if width.is_finite() && width > 0.0 => width,
+            Some(width) => {
+                return Err(StrokeBuilderError::InvalidWidth(format!(
+                    "Width must be a positive number, but was {}.",
+                    width
+                )));
+            }
+            None => {
+                return Err(StrokeBuilderError::WidthNotProvided(String::from(
+                    "Width must be provided to build a stroke.",
+                )));
+            }
+        };
+
+        // Miters are never shorter than the width of the stroke
+        if self.miter_limit.is_nan() || self.miter_limit < 1.0 {
+            return Err(StrokeBuilderError::InvalidMiterLimit(format!(
+                "Miter limit must be at least 1, but was {}.",
+                self.miter_limit
+            )));
+        }
+
+        Ok(Stroke {
+            width,
+            cap: self.cap,
+            join: self.join,
+            miter_limit: self.miter_limit,
+            dash: self.dash.clone(),
+        })
+    }
+}
+
+impl Default for StrokeBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+pub enum StrokeBuilderError {
+    WidthNotProvided(String),
+    InvalidWidth(String),
+    InvalidMiterLimit(String),
+}
+
+impl Display for StrokeBuilderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StrokeBuilderError::WidthNotProvided(msg) => {
+                write!(f, "{}", msg)
+            }
+            StrokeBuilderError::InvalidWidth(msg) => {
+                write!(f, "{}", msg)
+            }
+            StrokeBuilderError::InvalidMiterLimit(msg) => {
+                write!(f, "{}", msg)
+            }
+        }
+    }
+}
+
+impl Error for StrokeBuilderError {}
+
+impl<P: Pixel> Image<P> {
+    /// Strokes a line between two sub-pixel endpoints, where the center of each pixel is at
+    /// whole numbers.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use libppm::colors::{BLACK, WHITE};
+    /// use libppm::coordinate::FloatLineCoordinates;
+    /// use libppm::graphics::image::Image;
+    /// use libppm::graphics::stroke::{LineCap, Stroke};
+    ///
+    /// let stroke = Stroke::builder().width(2.0).cap(LineCap::Square).build().unwrap();
+    /// let mut image = Image::builder().rows(4).cols(4).build().unwrap();
+    /// image
+    ///     .fill(BLACK)
+    ///     .stroke_line(WHITE, &stroke, FloatLineCoordinates::new(1.5, 1.5, 1.5, 2.5))
+    ///     .unwrap();
+    ///
+    /// // A 2x3 rectangle, from the top left corner of pixel 1, 1
+    /// assert_eq!(image.get_data()[4..8], [0, 0x00_FF_FF_FF, 0x00_FF_FF_FF, 0x00_FF_FF_FF]);
+    /// assert_eq!(image.get_data()[8..12], [0, 0x00_FF_FF_FF, 0x00_FF_FF_FF, 0x00_FF_FF_FF]);
+    /// assert_eq!(image.get_data()[12..16], [0; 4]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return ValidationError::OutOfBoundsInImageError if the pixel nearest to either
+    /// endpoint is outside of the image, in which case nothing is drawn. Parts of the stroke
+    /// outside of the image are clipped.
+    pub fn stroke_line(
+        &mut self,
+        color: impl Into<Color>,
+        stroke: &Stroke,
+        coords: impl Into<FloatLineCoordinates>,
+    ) -> Result<&mut Self, validate::ValidationError<P>> {
+        let coords = coords.into();
+        self.stroke_polyline(color, stroke, &[coords.first, coords.second])
+    }
+
+    /// Strokes a line through each of the given sub-pixel points in turn, joining consecutive
+    /// segments as the stroke's LineJoin describes. The stroke's caps are only at the first &
+    /// last points.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use libppm::colors::{BLACK, WHITE};
+    /// use libppm::coordinate::FloatCoordinate;
+    /// use libppm::graphics::image::Image;
+    /// use libppm::graphics::stroke::{LineJoin, Stroke};
+    ///
+    /// let stroke = Stroke::builder().width(3.0).join(LineJoin::Round).build().unwrap();
+    /// let points = [
+    ///     FloatCoordinate::new(2.0, 2.0),
+    ///     FloatCoordinate::new(2.0, 12.0),
+    ///     FloatCoordinate::new(12.0, 12.0),
+    /// ];
+    /// let mut image = Image::builder().rows(16).cols(16).build().unwrap();
+    /// image.fill(BLACK).stroke_polyline(WHITE, &stroke, &points).unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Will return ValidationError::OutOfBoundsInImageError if the pixel nearest to any of the
+    /// points is outside of the image, in which case nothing is drawn. Parts of the stroke
+    /// outside of the image are clipped.
+    pub fn stroke_polyline(
+        &mut self,
+        color: impl Into<Color>,
+        stroke: &Stroke,
+        points: &[FloatCoordinate],
+    ) -> Result<&mut Self, validate::ValidationError<P>> {
+        for point in points {
+            validate::coordinate(self, &point.nearest())?;
+        }
+
+        let mut path: Vec<(f32, f32)> = points.iter().map(|point| (point.x, point.y)).collect();
+        path.dedup();
+
+        let color = color.into();
+        for ((row, col), samples) in self.coverage(&stroke.shapes(&path)) {
+            let coverage = samples.count_ones() as f32 / SAMPLES as f32;
+            self.blend_pixel(Coordinate::new(row, col), color, coverage)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Samples of each pixel in the image covered by any of the shapes, one bit per sample.
+    fn coverage(&self, shapes: &[Shape]) -> HashMap<(i32, i32), u64> {
+        let mut coverage: HashMap<(i32, i32), u64> = HashMap::new();
+        let last_row = *self.get_rows() as f32 - 1.0;
+        let last_col = *self.get_cols() as f32 - 1.0;
+
+        for shape in shapes {
+            let (top, bottom) = shape.rows();
+            let first_row = (top + 0.5).floor().max(0.0);
+            let final_row = (bottom + 0.5).floor().min(last_row);
+
+            let mut row = first_row;
+            while row <= final_row {
+                if let Some((left, right)) = shape.cols_in_row(row) {
+                    let first_col = (left + 0.5).floor().max(0.0);
+                    let final_col = (right + 0.5).floor().min(last_col);
+
+                    let mut col = first_col;
+                    while col <= final_col {
+                        let samples = shape.samples(row, col);
+                        if samples != 0 {
+                            *coverage.entry((row as i32, col as i32)).or_default() |= samples;
+                        }
+                        col += 1.0;
+                    }
+                }
+                row += 1.0;
+            }
+        }
+
+        coverage
+    }
+}
+
+/// A convex polygon, or a disc with a center & radius
+#[derive(Debug, PartialEq, Clone)]
+enum Shape {
+    Polygon(Vec<(f32, f32)>),
+    Disc((f32, f32), f32),
+}
+
+impl Shape {
+    /// Smallest & largest x of the shape.
+    fn rows(&self) -> (f32, f32) {
+        match self {
+            Shape::Polygon(vertices) => vertices.iter().fold(
+                (f32::INFINITY, f32::NEG_INFINITY),
+                |(top, bottom), vertex| (top.min(vertex.0), bottom.max(vertex.0)),
+            ),
+            Shape::Disc(center, radius) => (center.0 - radius, center.0 + radius),
+        }
+    }
+
+    /// Smallest & largest y of the part of the shape within the pixels of a row.
+    fn cols_in_row(&self, row: f32) -> Option<(f32, f32)> {
+        let (top, bottom) = (row - 0.5, row + 0.5);
+        match self {
+            Shape::Polygon(vertices) => {
+                let mut cols: Option<(f32, f32)> = None;
+                let mut include = |y: f32| {
+                    cols = Some(cols.map_or((y, y), |(left, right)| (left.min(y), right.max(y))));
+                };
+
+                // Clip each edge to the row
+                for (index, &start) in vertices.iter().enumerate() {
+                    let end = vertices[(index + 1) % vertices.len()];
+                    if start.0 == end.0 {
+                        if start.0 >= top && start.0 <= bottom {
+                            include(start.1);
+                            include(end.1);
+                        }
+                        continue;
+                    }
+
+                    let along = |x: f32| (x - start.0) / (end.0 - start.0);
+                    let (t_top, t_bottom) = (along(top), along(bottom));
+                    let t_first = t_top.min(t_bottom).max(0.0);
+                    let t_last = t_top.max(t_bottom).min(1.0);
+                    if t_first <= t_last {
+                        include(start.1 + (end.1 - start.1) * t_first);
+                        include(start.1 + (end.1 - start.1) * t_last);
+                    }
+                }
+
+                cols
+            }
+            Shape::Disc(center, radius) => {
+                let distance = center.0.clamp(top, bottom) - center.0;
+                if distance.abs() > *radius {
+                    return None;
+                }
+                let half_chord = (radius * radius - distance * distance).sqrt();
+                Some((center.1 - half_chord, center.1 + half_chord))
+            }
+        }
+    }
+
+    /// Samples of a pixel within the shape, one bit per sample.
+    fn samples(&self, row: f32, col: f32) -> u64 {
+        let mut samples = 0;
+        for sample in 0..SAMPLES {
+            let point = (
+                row - 0.5 + (sample as f32 + 0.5) / SAMPLES as f32,
+                col - 0.5
+                    + ((sample * SAMPLE_STRIDE) % SAMPLES) as f32 / SAMPLES as f32
+                    + 0.5 / SAMPLES as f32,
+            );
+            if self.contains(point) {
+                samples |= 1 << sample;
+            }
+        }
+        samples
+    }
+
+    fn contains(&self, point: (f32, f32)) -> bool {
+        match self {
+            Shape::Polygon(vertices) => {
+                // Inside a convex polygon, the point is on the same side of every edge
+                let (mut left, mut right) = (false, false);
+                for (index, &start) in vertices.iter().enumerate() {
+                    let end = vertices[(index + 1) % vertices.len()];
+                    let side = cross(sub(end, start), sub(point, start));
+                    left |= side > 0.0;
+                    right |= side < 0.0;
+                }
+                !(left && right)
+            }
+            Shape::Disc(center, radius) => {
+                let offset = sub(point, *center);
+                dot(offset, offset) <= radius * radius
+            }
+        }
+    }
+}
+
+fn add(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    (a.0 + b.0, a.1 + b.1)
+}
+
+fn sub(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+fn scale(a: (f32, f32), factor: f32) -> (f32, f32) {
+    (a.0 * factor, a.1 * factor)
+}
+
+fn dot(a: (f32, f32), b: (f32, f32)) -> f32 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+fn cross(a: (f32, f32), b: (f32, f32)) -> f32 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    let offset = sub(b, a);
+    dot(offset, offset).sqrt()
+}
+
+fn perpendicular(a: (f32, f32)) -> (f32, f32) {
+    (-a.1, a.0)
+}
+
+fn unit(a: (f32, f32)) -> (f32, f32) {
+    scale(a, 1.0 / dot(a, a).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::WHITE;
+    use crate::graphics::image::ImageBuilder;
+    use crate::graphics::pixel::Rgb32F;
+    use crate::validate::ValidationError;
+    use std::f32::consts::PI;
+
+    /// Strokes white over a black image, returning the area covered & the most any pixel is
+    /// covered.
+    fn stroked_area(stroke: &Stroke, points: &[FloatCoordinate]) -> (f32, f32) {
+        let mut image = ImageBuilder::<Rgb32F>::new()
+            .rows(48)
+            .cols(48)
+            .build()
+            .unwrap();
+        image.stroke_polyline(WHITE, stroke, points).unwrap();
+        image
+            .get_data()
+            .iter()
+            .fold((0.0, 0.0), |(area, most), Rgb32F([r, _, _])| {
+                (area + r, most.max(*r))
+            })
+    }
+
+    fn assert_area(stroke: &Stroke, points: &[FloatCoordinate], expected: f32) {
+        let (area, most) = stroked_area(stroke, points);
+        assert!(
+            (area - expected).abs() <= expected * 0.01,
+            "{:?} through {:?} covered {} instead of {}",
+            stroke,
+            points,
+            area,
+            expected
+        );
+        assert!(most <= 1.0 + 1e-5);
+    }
+
+    #[test]
+    fn test_stroke_builder() -> Result<(), Box<dyn Error>> {
+        let stroke = Stroke::builder().width(2.5).build()?;
+        assert_eq!(stroke.get_width(), &2.5);
+        assert_eq!(stroke.get_cap(), &LineCap::Butt);
+        assert_eq!(stroke.get_join(), &LineJoin::Miter);
+        assert_eq!(stroke.get_miter_limit(), &4.0);
+
+        assert!(matches!(
+            Stroke::builder().build(),
+            Err(StrokeBuilderError::WidthNotProvided(_))
+        ));
+        for width in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert!(matches!(
+                Stroke::builder().width(width).build(),
+                Err(StrokeBuilderError::InvalidWidth(_))
+            ));
+        }
+        for miter_limit in [0.5, f32::NAN] {
+            assert!(matches!(
+                Stroke::builder()
+                    .width(1.0)
+                    .miter_limit(miter_limit)
+                    .build(),
+                Err(StrokeBuilderError::InvalidMiterLimit(_))
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stroke_caps_in_every_direction() -> Result<(), Box<dyn Error>> {
+        let (length, width) = (20.0, 5.0);
+        let half_width: f32 = width / 2.0;
+
+        for (cap, cap_area) in [
+            (LineCap::Butt, 0.0),
+            (LineCap::Square, width * width),
+            (LineCap::Round, PI * half_width * half_width),
+        ] {
+            let stroke = Stroke::builder().width(width).cap(cap).build()?;
+            for degrees in (0..360).step_by(15) {
+                let (sin, cos) = (degrees as f32).to_radians().sin_cos();
+                let offset = (cos * length / 2.0, sin * length / 2.0);
+                let points = [
+                    FloatCoordinate::new(24.0 - offset.0, 24.0 - offset.1),
+                    FloatCoordinate::new(24.0 + offset.0, 24.0 + offset.1),
+                ];
+                assert_area(&stroke, &points, length * width + cap_area);
+            }
+
+            // A single point only has caps
+            let point = [FloatCoordinate::new(24.3, 24.6)];
+            let (area, _) = stroked_area(&stroke, &point);
+            assert!((area - cap_area).abs() <= 0.1);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stroke_joins() -> Result<(), Box<dyn Error>> {
+        // Two 20x4 rectangles overlapping in a 2x2 square, with a 2x2 gap outside the corner
+        let corner = [
+            FloatCoordinate::new(10.0, 10.0),
+            FloatCoordinate::new(10.0, 30.0),
+            FloatCoordinate::new(30.0, 30.0),
+        ];
+        for (join, join_area) in [
+            (LineJoin::Miter, 4.0),
+            (LineJoin::Bevel, 2.0),
+            (LineJoin::Round, PI),
+        ] {
+            let stroke = Stroke::builder().width(4.0).join(join).build()?;
+            assert_area(&stroke, &corner, 156.0 + join_area);
+        }
+
+        // A sharp corner, whose miter is about 6.2 times the width of the stroke
+        let sharp = [
+            FloatCoordinate::new(10.0, 10.0),
+            FloatCoordinate::new(10.0, 40.0),
+            FloatCoordinate::new(20.0, 10.0),
+        ];
+        let stroke = |join: LineJoin, miter_limit: f32| {
+            Stroke::builder()
+                .width(2.0)
+                .join(join)
+                .miter_limit(miter_limit)
+                .build()
+                .unwrap()
+        };
+        let (bevel, _) = stroked_area(&stroke(LineJoin::Bevel, 4.0), &sharp);
+        let (limited, _) = stroked_area(&stroke(LineJoin::Miter, 4.0), &sharp);
+        let (mitered, _) = stroked_area(&stroke(LineJoin::Miter, 7.0), &sharp);
+        assert_eq!(limited, bevel);
+        assert!(mitered > bevel + 1.0);
+
+        // Crossing itself & turning straight back still covers each pixel at most once
+        let tangled = [
+            FloatCoordinate::new(5.0, 5.0),
+            FloatCoordinate::new(40.0, 40.0),
+            FloatCoordinate::new(40.0, 5.0),
+            FloatCoordinate::new(5.0, 40.0),
+            FloatCoordinate::new(5.0, 40.0),
+            FloatCoordinate::new(20.0, 40.0),
+            FloatCoordinate::new(10.0, 40.0),
+        ];
+        for join in [LineJoin::Miter, LineJoin::Bevel, LineJoin::Round] {
+            let (_, most) = stroked_area(&stroke(join, 10.0), &tangled);
+            assert!(most <= 1.0 + 1e-5);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dashed_stroke() -> Result<(), Box<dyn Error>> {
+        use crate::graphics::dash::DashPattern;
+
+        let dashed = |cap: LineCap, lengths: &[f32], width: f32| {
+            Stroke::builder()
+                .width(width)
+                .cap(cap)
+                .dash(DashPattern::new(lengths, 0.0).unwrap())
+                .build()
+                .unwrap()
+        };
+        let stroke = dashed(LineCap::Butt, &[4.0, 6.0], 2.0);
+        assert_eq!(
+            stroke.get_dash(),
+            &Some(DashPattern::new(&[4.0, 6.0], 0.0)?)
+        );
+
+        // The pattern carries on through the points of a polyline, rather than restarting
+        let straight = [
+            FloatCoordinate::new(10.0, 5.0),
+            FloatCoordinate::new(10.0, 40.0),
+        ];
+        let through = [
+            FloatCoordinate::new(10.0, 5.0),
+            FloatCoordinate::new(10.0, 18.0),
+            FloatCoordinate::new(10.0, 40.0),
+        ];
+        let mut image = Image::builder().rows(20).cols(48).build()?;
+        image.stroke_polyline(WHITE, &stroke, &straight)?;
+        let mut polyline = Image::builder().rows(20).cols(48).build()?;
+        polyline.stroke_polyline(WHITE, &stroke, &through)?;
+        assert_eq!(polyline, image);
+        assert_eq!(polyline.get_pixel(Coordinate::new(10, 20))?, 0);
+
+        // Dashes of 4 every 10 along two segments of 23, so the third dash turns the corner,
+        // where the miter makes up for the overlap on the inside
+        let corner = [
+            FloatCoordinate::new(5.0, 5.0),
+            FloatCoordinate::new(5.0, 28.0),
+            FloatCoordinate::new(28.0, 28.0),
+        ];
+        assert_area(&stroke, &corner, 2.0 * 20.0);
+
+        // Dots of zero length are drawn by round & square caps alone
+        let dotted = [
+            FloatCoordinate::new(5.0, 5.0),
+            FloatCoordinate::new(5.0, 41.0),
+        ];
+        let (area, _) = stroked_area(&dashed(LineCap::Butt, &[0.0, 6.0], 4.0), &dotted);
+        assert_eq!(area, 0.0);
+        let square = dashed(LineCap::Square, &[0.0, 6.0], 4.0);
+        assert_area(&square, &dotted, 7.0 * 16.0);
+        let round = dashed(LineCap::Round, &[0.0, 6.0], 4.0);
+        assert_area(&round, &dotted, 7.0 * 4.0 * PI);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_stroke_with_invalid_coordinates() -> Result<(), Box<dyn Error>> {
+        let stroke = Stroke::builder().width(3.0).build()?;
+        let mut image = Image::builder().rows(8).cols(8).build()?;
+        let result = image.stroke_line(
+            WHITE,
+            &stroke,
+            FloatLineCoordinates::new(1.0, 1.0, 8.0, 2.0),
+        );
+        assert!(matches!(
+            result,
+            Err(ValidationError::OutOfBoundsInImageError(coord, _)) if coord == Coordinate::new(8, 2)
+        ));
+        assert_eq!(image.get_data(), &vec![0; 64]);
+
+        // The stroke itself may spill over the edges
+        image.stroke_line(
+            WHITE,
+            &stroke,
+            FloatLineCoordinates::new(0.0, 1.0, 0.0, 6.0),
+        )?;
+        assert_eq!(image.get_pixel(Coordinate::new(0, 3))?, 0x00_FF_FF_FF);
+        assert_eq!(image.get_pixel(Coordinate::new(1, 3))?, 0x00_FF_FF_FF);
+        assert_eq!(image.get_pixel(Coordinate::new(2, 3))?, 0);
+
+        Ok(())
+    }
+}
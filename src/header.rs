@@ -0,0 +1,769 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::io;
+use std::io::Read;
+
+/// Magic numbers of the Netpbm formats that share the PNM header grammar.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MagicNumber {
+    /// Plain (ASCII) bitmap
+    P1,
+    /// Plain (ASCII) graymap
+    P2,
+    /// Plain (ASCII) pixmap
+    P3,
+    /// Raw (binary) bitmap
+    P4,
+    /// Raw (binary) graymap
+    P5,
+    /// Raw (binary) pixmap
+    P6,
+}
+
+impl MagicNumber {
+    pub fn as_bytes(&self) -> &'static [u8; 2] {
+        match self {
+            MagicNumber::P1 => b"P1",
+            MagicNumber::P2 => b"P2",
+            MagicNumber::P3 => b"P3",
+            MagicNumber::P4 => b"P4",
+            MagicNumber::P5 => b"P5",
+            MagicNumber::P6 => b"P6",
+        }
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        match self {
+            MagicNumber::P1 | MagicNumber::P2 | MagicNumber::P3 => Encoding::Plain,
+            MagicNumber::P4 | MagicNumber::P5 | MagicNumber::P6 => Encoding::Raw,
+        }
+    }
+
+    /// Bitmaps (P1 & P4) have no maxval in their header.
+    pub fn has_maxval(&self) -> bool {
+        !matches!(self, MagicNumber::P1 | MagicNumber::P4)
+    }
+}
+
+impl Display for MagicNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(self.as_bytes()))
+    }
+}
+
+/// How the raster following a header is encoded.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Encoding {
+    /// Binary encoded samples (P4, P5 & P6)
+    #[default]
+    Raw,
+    /// ASCII encoded decimal samples (P1, P2 & P3)
+    Plain,
+}
+
+/// Location within a header, used to report where parsing failed.
+///
+/// Lines start at 1, bytes are offsets from the start of the header and start at 0.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Position {
+    pub line: usize,
+    pub byte: usize,
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, byte {}", self.line, self.byte)
+    }
+}
+
+/// Header shared by the PBM, PGM & PPM formats.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct PnmHeader {
+    pub magic: MagicNumber,
+    pub width: usize,
+    pub height: usize,
+    /// Always 1 for bitmaps, which don't store a maxval.
+    pub maxval: u16,
+}
+
+/// Header of the PAM (P7) format.
+///
+/// Unlike the other formats, a PAM header is made of lines of keywords & values, ending with
+/// an ENDHDR line.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PamHeader {
+    pub width: usize,
+    pub height: usize,
+    pub depth: usize,
+    pub maxval: u16,
+    /// Values of all TUPLTYPE lines joined by a space, or None if there were none. TUPLTYPE
+    /// lines without a value are ignored.
+    pub tuple_type: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum HeaderError {
+    Io(io::Error),
+    UnexpectedEndOfHeader(Position),
+    InvalidMagicNumber(String, Position),
+    UnexpectedCharacter(u8, Position),
+    InvalidNumber(String, Position),
+    InvalidMaxval(usize, Position),
+    InvalidKeyword(String, Position),
+    MissingKeyword(String, Position),
+}
+
+impl Display for HeaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HeaderError::Io(e) => {
+                write!(f, "{}", e)
+            }
+            HeaderError::UnexpectedEndOfHeader(position) => {
+                write!(f, "Header ended unexpectedly at {}.", position)
+            }
+            HeaderError::InvalidMagicNumber(magic, position) => {
+                write!(f, "Invalid magic number '{}' at {}.", magic, position)
+            }
+            HeaderError::UnexpectedCharacter(byte, position) => {
+                write!(
+                    f,
+                    "Unexpected character {:?} at {}.",
+                    char::from(*byte),
+                    position
+                )
+            }
+            HeaderError::InvalidNumber(token, position) => {
+                write!(f, "Invalid number '{}' at {}.", token, position)
+            }
+            HeaderError::InvalidMaxval(maxval, position) => {
+                write!(
+                    f,
+                    "Maxval must be between 1 and 65535, but found {} at {}.",
+                    maxval, position
+                )
+            }
+            HeaderError::InvalidKeyword(keyword, position) => {
+                write!(f, "Invalid keyword '{}' at {}.", keyword, position)
+            }
+            HeaderError::MissingKeyword(keyword, position) => {
+                write!(
+                    f,
+                    "Header ended at {} without the required keyword '{}'.",
+                    position, keyword
+                )
+            }
+        }
+    }
+}
+
+impl Error for HeaderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            HeaderError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for HeaderError {
+    fn from(e: io::Error) -> Self {
+        HeaderError::Io(e)
+    }
+}
+
+impl PnmHeader {
+    pub fn new(magic: MagicNumber, width: usize, height: usize, maxval: u16) -> Self {
+        PnmHeader {
+            magic,
+            width,
+            height,
+            maxval,
+        }
+    }
+
+    /// Parses a header from the start of `bytes`.
+    ///
+    /// Returns the header along with the offset of the first byte of the raster.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use libppm::header::{MagicNumber, PnmHeader};
+    ///
+    /// let bytes = b"P6\n# made by hand\n3\t2\r\n255\n";
+    /// let (header, offset) = PnmHeader::parse(bytes).unwrap();
+    ///
+    /// assert_eq!(header, PnmHeader::new(MagicNumber::P6, 3, 2, 255));
+    /// assert_eq!(offset, bytes.len());
+    /// ```
+    pub fn parse(bytes: &[u8]) -> Result<(PnmHeader, usize), HeaderError> {
+        let mut remaining = bytes;
+        let header = PnmHeader::read_from(&mut remaining)?;
+        Ok((header, bytes.len() - remaining.len()))
+    }
+
+    /// Reads a header from `reader`, consuming exactly the header & the single whitespace
+    /// character that separates it from the raster.
+    ///
+    /// Bytes are read one at a time, so unbuffered readers should be wrapped in a BufReader.
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<PnmHeader, HeaderError> {
+        let (header, maxval_position) = PnmHeader::read_any_maxval(reader)?;
+        match maxval_position {
+            Some(position) if header.maxval == 0 => Err(HeaderError::InvalidMaxval(0, position)),
+            _ => Ok(header),
+        }
+    }
+
+    /// Reads a header like PnmHeader::read_from, but accepts a maxval of 0, returning the
+    /// position of the maxval along with the header.
+    pub(crate) fn read_any_maxval<R: Read>(
+        reader: &mut R,
+    ) -> Result<(PnmHeader, Option<Position>), HeaderError> {
+        let mut tokenizer = Tokenizer::new(reader);
+
+        let magic_position = tokenizer.position();
+        let magic = match [tokenizer.next_raw()?, tokenizer.next_raw()?] {
+            [b'P', b'1'] => MagicNumber::P1,
+            [b'P', b'2'] => MagicNumber::P2,
+            [b'P', b'3'] => MagicNumber::P3,
+            [b'P', b'4'] => MagicNumber::P4,
+            [b'P', b'5'] => MagicNumber::P5,
+            [b'P', b'6'] => MagicNumber::P6,
+            other => {
+                return Err(HeaderError::InvalidMagicNumber(
+                    String::from_utf8_lossy(&other).into_owned(),
+                    magic_position,
+                ));
+            }
+        };
+
+        // The magic number must be followed by whitespace (or a comment)
+        let delimiter_position = tokenizer.position();
+        let delimiter = tokenizer.next_char()?;
+        if !delimiter.is_ascii_whitespace() {
+            return Err(HeaderError::UnexpectedCharacter(
+                delimiter,
+                delimiter_position,
+            ));
+        }
+
+        let (width, _) = tokenizer.next_number()?;
+        let (height, _) = tokenizer.next_number()?;
+
+        let (maxval, maxval_position) = if magic.has_maxval() {
+            let (maxval, maxval_position) = tokenizer.next_number()?;
+            match maxval {
+                0..=65535 => (maxval as u16, Some(maxval_position)),
+                _ => return Err(HeaderError::InvalidMaxval(maxval, maxval_position)),
+            }
+        } else {
+            (1, None)
+        };
+
+        Ok((
+            PnmHeader {
+                magic,
+                width,
+                height,
+                maxval,
+            },
+            maxval_position,
+        ))
+    }
+
+    /// Encodes the header, writing each comment on its own line after the magic number.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use libppm::header::{MagicNumber, PnmHeader};
+    ///
+    /// let header = PnmHeader::new(MagicNumber::P6, 3, 2, 255);
+    /// let bytes = header.encode(&[String::from("made by hand")]);
+    ///
+    /// assert_eq!(bytes, b"P6\n# made by hand\n3 2\n255\n");
+    /// ```
+    pub fn encode(&self, comments: &[String]) -> Vec<u8> {
+        let mut buffer: Vec<u8> = Vec::new();
+
+        buffer.extend_from_slice(self.magic.as_bytes());
+        buffer.push(b'\n');
+
+        for line in comment_lines(comments) {
+            buffer.extend_from_slice(format!("# {}\n", line).as_bytes());
+        }
+
+        buffer.extend_from_slice(format!("{} {}\n", self.width, self.height).as_bytes());
+
+        if self.magic.has_maxval() {
+            buffer.extend_from_slice(format!("{}\n", self.maxval).as_bytes());
+        }
+
+        buffer
+    }
+}
+
+impl PamHeader {
+    pub fn new(
+        width: usize,
+        height: usize,
+        depth: usize,
+        maxval: u16,
+        tuple_type: Option<&str>,
+    ) -> Self {
+        PamHeader {
+            width,
+            height,
+            depth,
+            maxval,
+            tuple_type: tuple_type.map(|tuple_type| tuple_type.to_string()),
+        }
+    }
+
+    /// Parses a header from the start of `bytes`.
+    ///
+    /// Returns the header along with the offset of the first byte of the raster.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use libppm::header::PamHeader;
+    ///
+    /// let bytes = b"P7\nWIDTH 3\nHEIGHT 2\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n";
+    /// let (header, offset) = PamHeader::parse(bytes).unwrap();
+    ///
+    /// assert_eq!(header, PamHeader::new(3, 2, 4, 255, Some("RGB_ALPHA")));
+    /// assert_eq!(offset, bytes.len());
+    /// ```
+    pub fn parse(bytes: &[u8]) -> Result<(PamHeader, usize), HeaderError> {
+        let mut remaining = bytes;
+        let header = PamHeader::read_from(&mut remaining)?;
+        Ok((header, bytes.len() - remaining.len()))
+    }
+
+    /// Reads a header from `reader`, consuming exactly the header including the newline that
+    /// ends the ENDHDR line.
+    ///
+    /// Bytes are read one at a time, so unbuffered readers should be wrapped in a BufReader.
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<PamHeader, HeaderError> {
+        let mut tokenizer = Tokenizer::new(reader);
+
+        let magic_position = tokenizer.position();
+        let magic = [tokenizer.next_raw()?, tokenizer.next_raw()?];
+        let newline_position = tokenizer.position();
+        match (magic, tokenizer.next_raw()?) {
+            ([b'P', b'7'], b'\n') => {}
+            ([b'P', b'7'], byte) => {
+                return Err(HeaderError::UnexpectedCharacter(byte, newline_position));
+            }
+            (other, _) => {
+                return Err(HeaderError::InvalidMagicNumber(
+                    String::from_utf8_lossy(&other).into_owned(),
+                    magic_position,
+                ));
+            }
+        }
+
+        let mut width = None;
+        let mut height = None;
+        let mut depth = None;
+        let mut maxval = None;
+        let mut tuple_types: Vec<String> = Vec::new();
+
+        loop {
+            let (line, line_position) = tokenizer.next_line()?;
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (keyword, value) = match line.split_once(|c: char| c.is_ascii_whitespace()) {
+                Some((keyword, value)) => (keyword, value.trim()),
+                None => (line, ""),
+            };
+
+            let number = || match value.parse::<usize>() {
+                Ok(number) => Ok(number),
+                Err(_) => Err(HeaderError::InvalidNumber(value.to_string(), line_position)),
+            };
+
+            match keyword {
+                "ENDHDR" => break,
+                "WIDTH" => width = Some(number()?),
+                "HEIGHT" => height = Some(number()?),
+                "DEPTH" => depth = Some(number()?),
+                "MAXVAL" => match number()? {
+                    maxval_value @ 1..=65535 => maxval = Some(maxval_value as u16),
+                    maxval_value => {
+                        return Err(HeaderError::InvalidMaxval(maxval_value, line_position));
+                    }
+                },
+                // Empty values would otherwise add stray spaces to the joined tuple type, or turn a
+                // header without a tuple type into one with an empty tuple type
+                "TUPLTYPE" if value.is_empty() => continue,
+                "TUPLTYPE" => tuple_types.push(value.to_string()),
+                keyword => {
+                    return Err(HeaderError::InvalidKeyword(
+                        keyword.to_string(),
+                        line_position,
+                    ));
+                }
+            }
+        }
+
+        let end_position = tokenizer.position();
+        let required = |value: Option<usize>, keyword: &str| {
+            value.ok_or_else(|| HeaderError::MissingKeyword(keyword.to_string(), end_position))
+        };
+
+        Ok(PamHeader {
+            width: required(width, "WIDTH")?,
+            height: required(height, "HEIGHT")?,
+            depth: required(depth, "DEPTH")?,
+            maxval: required(maxval.map(|maxval| maxval as usize), "MAXVAL")? as u16,
+            tuple_type: match tuple_types.is_empty() {
+                true => None,
+                false => Some(tuple_types.join(" ")),
+            },
+        })
+    }
+
+    /// Encodes the header, writing each comment on its own line after the magic number.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use libppm::header::PamHeader;
+    ///
+    /// let header = PamHeader::new(3, 2, 1, 255, Some("GRAYSCALE"));
+    /// let bytes = header.encode(&[]);
+    ///
+    /// assert_eq!(
+    ///     bytes,
+    ///     b"P7\nWIDTH 3\nHEIGHT 2\nDEPTH 1\nMAXVAL 255\nTUPLTYPE GRAYSCALE\nENDHDR\n"
+    /// );
+    /// ```
+    pub fn encode(&self, comments: &[String]) -> Vec<u8> {
+        let mut buffer: Vec<u8> = Vec::new();
+
+        buffer.extend_from_slice(b"P7\n");
+
+        for line in comment_lines(comments) {
+            buffer.extend_from_slice(format!("# {}\n", line).as_bytes());
+        }
+
+        buffer.extend_from_slice(
+            format!(
+                "WIDTH {}\nHEIGHT {}\nDEPTH {}\nMAXVAL {}\n",
+                self.width, self.height, self.depth, self.maxval
+            )
+            .as_bytes(),
+        );
+
+        if let Some(tuple_type) = &self.tuple_type {
+            buffer.extend_from_slice(format!("TUPLTYPE {}\n", tuple_type).as_bytes());
+        }
+
+        buffer.extend_from_slice(b"ENDHDR\n");
+
+        buffer
+    }
+}
+
+/// Lines of the given comments. A comment runs until the end of the line, so multi-line comments
+/// become several, split at carriage returns too as the Tokenizer ends comments at either.
+fn comment_lines(comments: &[String]) -> impl Iterator<Item = &str> {
+    comments
+        .iter()
+        .flat_map(|comment| comment.lines())
+        .flat_map(|line| line.split_terminator('\r'))
+}
+
+/// Splits a header into tokens, treating comments as the line ending that terminates them.
+struct Tokenizer<'a, R: Read> {
+    reader: &'a mut R,
+    line: usize,
+    byte: usize,
+}
+
+impl<'a, R: Read> Tokenizer<'a, R> {
+    fn new(reader: &'a mut R) -> Self {
+        Tokenizer {
+            reader,
+            line: 1,
+            byte: 0,
+        }
+    }
+
+    fn position(&self) -> Position {
+        Position {
+            line: self.line,
+            byte: self.byte,
+        }
+    }
+
+    /// Reads a single byte, without any comment handling.
+    fn next_raw(&mut self) -> Result<u8, HeaderError> {
+        let position = self.position();
+        let mut byte = [0u8; 1];
+        loop {
+            match self.reader.read(&mut byte) {
+                Ok(0) => return Err(HeaderError::UnexpectedEndOfHeader(position)),
+                Ok(_) => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(HeaderError::Io(e)),
+            }
+        }
+
+        self.byte += 1;
+        if byte[0] == b'\n' {
+            self.line += 1;
+        }
+
+        Ok(byte[0])
+    }
+
+    /// Reads a single character, replacing a comment with the newline or carriage return
+    /// that ends it.
+    fn next_char(&mut self) -> Result<u8, HeaderError> {
+        let byte = self.next_raw()?;
+        if byte != b'#' {
+            return Ok(byte);
+        }
+
+        loop {
+            match self.next_raw()? {
+                terminator @ (b'\n' | b'\r') => return Ok(terminator),
+                _ => continue,
+            }
+        }
+    }
+
+    /// Reads a line up to, and consuming, the newline that ends it. Returns the line along
+    /// with the position of its first byte.
+    fn next_line(&mut self) -> Result<(String, Position), HeaderError> {
+        let start = self.position();
+        let mut line: Vec<u8> = Vec::new();
+
+        loop {
+            match self.next_raw()? {
+                b'\n' => return Ok((String::from_utf8_lossy(&line).into_owned(), start)),
+                byte => line.push(byte),
+            }
+        }
+    }
+
+    /// Reads a decimal number preceded by whitespace & followed by exactly one whitespace
+    /// character, which is consumed.
+    fn next_number(&mut self) -> Result<(usize, Position), HeaderError> {
+        let mut start = self.position();
+        let mut byte = self.next_char()?;
+        while byte.is_ascii_whitespace() {
+            start = self.position();
+            byte = self.next_char()?;
+        }
+
+        let mut token = String::new();
+        while byte.is_ascii_digit() {
+            token.push(char::from(byte));
+            let position = self.position();
+            byte = self.next_char()?;
+
+            if !byte.is_ascii_digit() && !byte.is_ascii_whitespace() {
+                return Err(HeaderError::UnexpectedCharacter(byte, position));
+            }
+        }
+
+        if token.is_empty() {
+            return Err(HeaderError::UnexpectedCharacter(byte, start));
+        }
+
+        match token.parse::<usize>() {
+            Ok(number) => Ok((number, start)),
+            Err(_) => Err(HeaderError::InvalidNumber(token, start)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_parse_netpbm_grammar() -> Result<(), Box<dyn Error>> {
+        let bytes =
+            b"P6 #comment right after the magic number\n\t 64\r\n#\n  48 # trailing\n65535\rRASTER";
+        let (header, offset) = PnmHeader::parse(bytes)?;
+
+        assert_eq!(header, PnmHeader::new(MagicNumber::P6, 64, 48, 65535));
+        assert_eq!(&bytes[offset..], b"RASTER");
+
+        let (header, offset) = PnmHeader::parse(b"P4\n8 2\n\xFF\x00")?;
+        assert_eq!(header, PnmHeader::new(MagicNumber::P4, 8, 2, 1));
+        assert_eq!(offset, 7);
+
+        // A comment may terminate the maxval, its line ending is the raster delimiter
+        let (header, offset) = PnmHeader::parse(b"P5 1 1 255#comment\n\x7F")?;
+        assert_eq!(header, PnmHeader::new(MagicNumber::P5, 1, 1, 255));
+        assert_eq!(offset, 19);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        match PnmHeader::parse(b"P9\n1 1\n255\n") {
+            Err(HeaderError::InvalidMagicNumber(magic, position)) => {
+                assert_eq!(magic, "P9");
+                assert_eq!(position, Position { line: 1, byte: 0 });
+            }
+            other => panic!("Expected an invalid magic number, got {:?}", other),
+        }
+
+        match PnmHeader::parse(b"P6\n# comment\n12 3x4\n255\n") {
+            Err(HeaderError::UnexpectedCharacter(b'x', position)) => {
+                assert_eq!(position, Position { line: 3, byte: 17 });
+            }
+            other => panic!("Expected an unexpected character, got {:?}", other),
+        }
+
+        match PnmHeader::parse(b"P6\n1 1\n70000\n") {
+            Err(HeaderError::InvalidMaxval(70000, position)) => {
+                assert_eq!(position, Position { line: 3, byte: 7 });
+            }
+            other => panic!("Expected an invalid maxval, got {:?}", other),
+        }
+
+        assert!(matches!(
+            PnmHeader::parse(b"P6\n1 1\n0\n"),
+            Err(HeaderError::InvalidMaxval(0, _))
+        ));
+        assert!(matches!(
+            PnmHeader::parse(b"P6\n1 1\n255"),
+            Err(HeaderError::UnexpectedEndOfHeader(_))
+        ));
+        assert!(matches!(
+            PnmHeader::parse(b"P6\n99999999999999999999999 1\n255\n"),
+            Err(HeaderError::InvalidNumber(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_parse_pam() -> Result<(), Box<dyn Error>> {
+        let bytes = b"P7\n# comment\nWIDTH 4\n  HEIGHT\t2 \r\nDEPTH 2\nMAXVAL 65535\n\nTUPLTYPE GRAYSCALE\nTUPLTYPE _ALPHA\nENDHDR\nRASTER";
+        let (header, offset) = PamHeader::parse(bytes)?;
+
+        assert_eq!(
+            header,
+            PamHeader::new(4, 2, 2, 65535, Some("GRAYSCALE _ALPHA"))
+        );
+        assert_eq!(&bytes[offset..], b"RASTER");
+
+        let header = PamHeader::new(1, 1, 3, 1023, None);
+        let bytes = header.encode(&[String::from("comment")]);
+        assert_eq!(PamHeader::parse(&bytes)?, (header, bytes.len()));
+
+        match PamHeader::parse(b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 1\nCOLORS 3\nENDHDR\n") {
+            Err(HeaderError::InvalidKeyword(keyword, position)) => {
+                assert_eq!(keyword, "COLORS");
+                assert_eq!(position, Position { line: 5, byte: 28 });
+            }
+            other => panic!("Expected an invalid keyword, got {:?}", other),
+        }
+
+        assert!(matches!(
+            PamHeader::parse(b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 1\nENDHDR\n"),
+            Err(HeaderError::MissingKeyword(keyword, _)) if keyword == "MAXVAL"
+        ));
+        assert!(matches!(
+            PamHeader::parse(b"P7\nWIDTH x\n"),
+            Err(HeaderError::InvalidNumber(_, _))
+        ));
+        assert!(matches!(
+            PamHeader::parse(b"P6\n1 1\n255\n"),
+            Err(HeaderError::InvalidMagicNumber(_, _))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_round_trip() -> Result<(), Box<dyn Error>> {
+        let header = PnmHeader::new(MagicNumber::P6, 640, 480, 255);
+        let comments = vec![String::from("first"), String::from("second\nthird")];
+        let bytes = header.encode(&comments);
+
+        assert_eq!(
+            bytes,
+            b"P6\n# first\n# second\n# third\n640 480\n255\n".to_vec()
+        );
+        assert_eq!(PnmHeader::parse(&bytes)?, (header, bytes.len()));
+
+        // Lone carriage returns end a comment as well, so they split it like newlines
+        let comments = vec![String::from("a\rb 7"), String::from("c\r\nd\r")];
+        let bytes = header.encode(&comments);
+        assert_eq!(bytes, b"P6\n# a\n# b 7\n# c\n# d\n640 480\n255\n".to_vec());
+        assert_eq!(PnmHeader::parse(&bytes)?, (header, bytes.len()));
+
+        let header = PamHeader::new(640, 480, 3, 255, Some("RGB"));
+        let bytes = header.encode(&comments);
+        assert_eq!(PamHeader::parse(&bytes)?, (header, bytes.len()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_pam_empty_tuple_type() -> Result<(), Box<dyn Error>> {
+        let bytes = b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 3\nMAXVAL 255\nTUPLTYPE\nTUPLTYPE RGB\nENDHDR\n";
+        assert_eq!(
+            PamHeader::parse(bytes)?.0.tuple_type.as_deref(),
+            Some("RGB")
+        );
+
+        // Empty values are skipped wherever they are, including ones made only of whitespace
+        let bytes = b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 4\nMAXVAL 255\n\
+            TUPLTYPE RGB\nTUPLTYPE \t \nTUPLTYPE _ALPHA\nTUPLTYPE\nENDHDR\n";
+        assert_eq!(
+            PamHeader::parse(bytes)?.0.tuple_type.as_deref(),
+            Some("RGB _ALPHA")
+        );
+
+        // A header with only empty TUPLTYPE lines has no tuple type at all, & encodes without one
+        let bytes = b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 3\nMAXVAL 255\nTUPLTYPE\nENDHDR\n";
+        let header = PamHeader::parse(bytes)?.0;
+        assert_eq!(header.tuple_type, None);
+        assert_eq!(header, PamHeader::new(1, 1, 3, 255, None));
+        assert!(!header
+            .encode(&[])
+            .windows(8)
+            .any(|word| word == b"TUPLTYPE"));
+
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn test_encode_parse_property(
+            width in any::<usize>(),
+            height in any::<usize>(),
+            depth in any::<usize>(),
+            maxval in 1u16..,
+            tuple_type in proptest::option::of("[A-Z_]{1,12}( [A-Z_]{1,12})?"),
+        ) {
+            let header = PnmHeader::new(MagicNumber::P6, width, height, maxval);
+            prop_assert_eq!(PnmHeader::parse(&header.encode(&[]))?.0, header);
+
+            let header = PamHeader::new(width, height, depth, maxval, tuple_type.as_deref());
+            prop_assert_eq!(PamHeader::parse(&header.encode(&[]))?.0, header);
+        }
+
+        #[test]
+        fn test_parse_never_panics(bytes in proptest::collection::vec(any::<u8>(), 0..64)) {
+            let _ = PnmHeader::parse(&bytes);
+            let _ = PamHeader::parse(&bytes);
+        }
+    }
+}
@@ -1,5 +1,18 @@
+// `% 2 == 0` & friends are kept over usize::is_multiple_of, which would need Rust 1.87
+#![allow(clippy::manual_is_multiple_of)]
+
+pub mod color_space;
 pub mod colors;
 pub mod coordinate;
 pub mod graphics;
+pub mod header;
+pub mod limits;
+pub mod netpbm;
+pub mod pam;
+pub mod pbm;
+pub mod pgm;
 pub mod ppm;
+mod raster;
 pub mod validate;
+
+pub use netpbm::{load, save};
@@ -0,0 +1,177 @@
+//! Limits on the size of the images that the decoders accept, checked against the header before
+//! any pixel data is read, so a header of a few bytes can't make a decoder allocate gigabytes.
+
+/// Default limit on the number of pixels in an image, e.g. 8192 by 8192.
+pub const DEFAULT_MAX_PIXELS: usize = 1 << 26;
+
+/// Default limit on the length of an input, & of a binary raster, in bytes.
+pub const DEFAULT_MAX_BYTES: usize = 1 << 30;
+
+/// Limits on the dimensions & length of decoded images
+///
+/// Every decoder applies the default limits unless given others, which cap images at
+/// DEFAULT_MAX_PIXELS pixels & DEFAULT_MAX_BYTES bytes, without limiting width or height on
+/// their own. Limits::unlimited lifts them all, for trusted input.
+///
+/// # Example
+///
+/// ```
+/// use libppm::limits::Limits;
+/// use libppm::pgm::{PGMDecodeError, PGMImage};
+///
+/// // A header declaring a 100000 by 100000 image, without any pixel data
+/// let mut bytes: &[u8] = b"P5\n100000 100000\n255\n";
+/// assert!(matches!(
+///     PGMImage::read_from(&mut bytes),
+///     Err(PGMDecodeError::LimitExceeded(_))
+/// ));
+///
+/// let mut bytes: &[u8] = b"P5\n3 2\n255\n\x00\x00\x00\x00\x00\x00";
+/// let limits = *Limits::new().max_width(2);
+/// assert!(PGMImage::read_from_with_limits(&mut bytes, &limits).is_err());
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Limits {
+    max_width: Option<usize>,
+    max_height: Option<usize>,
+    max_pixels: Option<usize>,
+    max_bytes: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits::new()
+    }
+}
+
+impl Limits {
+    /// The default limits, see Limits
+    pub fn new() -> Self {
+        Limits {
+            max_width: None,
+            max_height: None,
+            max_pixels: Some(DEFAULT_MAX_PIXELS),
+            max_bytes: Some(DEFAULT_MAX_BYTES),
+        }
+    }
+
+    /// No limits at all, so decoders allocate whatever the header declares.
+    pub fn unlimited() -> Self {
+        Limits {
+            max_width: None,
+            max_height: None,
+            max_pixels: None,
+            max_bytes: None,
+        }
+    }
+
+    /// Rejects images wider than `max_width` columns. Unlimited by default.
+    pub fn max_width(&mut self, max_width: usize) -> &mut Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    /// Rejects images taller than `max_height` rows. Unlimited by default.
+    pub fn max_height(&mut self, max_height: usize) -> &mut Self {
+        self.max_height = Some(max_height);
+        self
+    }
+
+    /// Rejects images with more than `max_pixels` pixels in total. DEFAULT_MAX_PIXELS by
+    /// default.
+    pub fn max_pixels(&mut self, max_pixels: usize) -> &mut Self {
+        self.max_pixels = Some(max_pixels);
+        self
+    }
+
+    /// Rejects inputs longer than `max_bytes`, along with binary images whose header declares a
+    /// raster longer than `max_bytes`. DEFAULT_MAX_BYTES by default.
+    pub fn max_bytes(&mut self, max_bytes: usize) -> &mut Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    pub fn get_max_width(&self) -> &Option<usize> {
+        &self.max_width
+    }
+
+    pub fn get_max_height(&self) -> &Option<usize> {
+        &self.max_height
+    }
+
+    pub fn get_max_pixels(&self) -> &Option<usize> {
+        &self.max_pixels
+    }
+
+    pub fn get_max_bytes(&self) -> &Option<usize> {
+        &self.max_bytes
+    }
+
+    /// Checks the dimensions declared by a header, returning the message for a LimitExceeded
+    /// error of the decoder's own error type.
+    pub(crate) fn check_dimensions(&self, width: usize, height: usize) -> Result<(), String> {
+        if exceeds(width, self.max_width) {
+            return Err(format!(
+                "Image width {} exceeds the limit of {}.",
+                width,
+                self.max_width.unwrap_or_default()
+            ));
+        }
+
+        if exceeds(height, self.max_height) {
+            return Err(format!(
+                "Image height {} exceeds the limit of {}.",
+                height,
+                self.max_height.unwrap_or_default()
+            ));
+        }
+
+        if exceeds(width.saturating_mul(height), self.max_pixels) {
+            return Err(format!(
+                "Image of {} by {} pixels exceeds the limit of {} pixels.",
+                width,
+                height,
+                self.max_pixels.unwrap_or_default()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Checks the length of a binary raster declared by a header, which is None when it doesn't
+    /// fit a usize.
+    pub(crate) fn check_raster(
+        &self,
+        width: usize,
+        height: usize,
+        raster_bytes: Option<usize>,
+    ) -> Result<(), String> {
+        if exceeds(raster_bytes.unwrap_or(usize::MAX), self.max_bytes) {
+            return Err(format!(
+                "Raster of {} by {} pixels exceeds the limit of {} bytes.",
+                width,
+                height,
+                self.max_bytes.unwrap_or_default()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Checks the length of a whole input.
+    pub(crate) fn check_input(&self, length: usize) -> Result<(), String> {
+        if exceeds(length, self.max_bytes) {
+            return Err(format!(
+                "Input of {} bytes exceeds the limit of {} bytes.",
+                length,
+                self.max_bytes.unwrap_or_default()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+fn exceeds(value: usize, limit: Option<usize>) -> bool {
+    limit.is_some_and(|limit| value > limit)
+}
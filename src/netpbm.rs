@@ -0,0 +1,505 @@
+//! Format-independent loading & saving of Netpbm images.
+//!
+//! The format of a loaded file is detected from its magic number, & the format of a saved file
+//! is chosen from its extension unless overridden by SaveOptions::format.
+
+use crate::colors::{Color, WHITE};
+use crate::graphics::bitmap::Bitmap;
+use crate::graphics::image::{ColorMode, Image};
+use crate::limits::Limits;
+use crate::pam::{PAMDecodeError, PAMImage, TupleType};
+use crate::pbm::{PBMDecodeError, PBMImage};
+use crate::pgm::{PGMDecodeError, PGMImage};
+use crate::ppm::{PPMDecodeError, PPMImage};
+use crate::raster;
+use std::error::Error;
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+
+pub use crate::header::Encoding;
+
+/// Netpbm formats
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+    Pbm,
+    Pgm,
+    Ppm,
+    Pam,
+}
+
+impl Format {
+    /// Detects the format from the magic number at the start of `bytes`.
+    pub fn from_magic(bytes: &[u8]) -> Option<Format> {
+        match bytes {
+            [b'P', b'1' | b'4', ..] => Some(Format::Pbm),
+            [b'P', b'2' | b'5', ..] => Some(Format::Pgm),
+            [b'P', b'3' | b'6', ..] => Some(Format::Ppm),
+            [b'P', b'7', ..] => Some(Format::Pam),
+            _ => None,
+        }
+    }
+
+    /// Picks the format from the extension of `path`, ignoring case.
+    ///
+    /// The generic .pnm extension is saved as PPM, which holds any Image without loss.
+    pub fn from_extension(path: &str) -> Option<Format> {
+        let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "pbm" => Some(Format::Pbm),
+            "pgm" => Some(Format::Pgm),
+            "ppm" | "pnm" => Some(Format::Ppm),
+            "pam" => Some(Format::Pam),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Pbm => "pbm",
+            Format::Pgm => "pgm",
+            Format::Ppm => "ppm",
+            Format::Pam => "pam",
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.extension().to_ascii_uppercase())
+    }
+}
+
+/// Options for `save`. Options that don't apply to the chosen format are ignored.
+#[derive(Debug, Default, Clone)]
+pub struct SaveOptions {
+    format: Option<Format>,
+    encoding: Encoding,
+    maxval: Option<u16>,
+    tuple_type: Option<TupleType>,
+    background: Color,
+}
+
+impl SaveOptions {
+    pub fn new() -> Self {
+        SaveOptions {
+            format: None,
+            encoding: Encoding::Raw,
+            maxval: None,
+            tuple_type: None,
+            background: WHITE,
+        }
+    }
+
+    /// Overrides the format picked from the extension of the path.
+    pub fn format(&mut self, format: Format) -> &mut Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Selects between binary (the default) and plain encoding. PAM is always binary.
+    pub fn encoding(&mut self, encoding: Encoding) -> &mut Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Sets the maxval written to the header, between 1 and 65535. Ignored for PBM.
+    pub fn maxval(&mut self, maxval: u16) -> &mut Self {
+        self.maxval = Some(maxval);
+        self
+    }
+
+    /// Sets the tuple type of PAM output. Defaults to RGB_ALPHA for images with alpha,
+    /// otherwise RGB.
+    pub fn tuple_type(&mut self, tuple_type: TupleType) -> &mut Self {
+        self.tuple_type = Some(tuple_type);
+        self
+    }
+
+    /// Sets the color that images with alpha are flattened against for every format except PAM,
+    /// which keeps alpha. Defaults to white.
+    pub fn background(&mut self, background: impl Into<Color>) -> &mut Self {
+        self.background = background.into();
+        self
+    }
+}
+
+#[derive(Debug)]
+pub enum NetpbmError {
+    Io(io::Error),
+    UnknownFormat(String),
+    Ppm(PPMDecodeError),
+    Pgm(PGMDecodeError),
+    Pbm(PBMDecodeError),
+    Pam(PAMDecodeError),
+    Encode(String),
+}
+
+impl Display for NetpbmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetpbmError::Io(e) => {
+                write!(f, "{}", e)
+            }
+            NetpbmError::UnknownFormat(msg) => {
+                write!(f, "{}", msg)
+            }
+            NetpbmError::Ppm(e) => {
+                write!(f, "{}", e)
+            }
+            NetpbmError::Pgm(e) => {
+                write!(f, "{}", e)
+            }
+            NetpbmError::Pbm(e) => {
+                write!(f, "{}", e)
+            }
+            NetpbmError::Pam(e) => {
+                write!(f, "{}", e)
+            }
+            NetpbmError::Encode(msg) => {
+                write!(f, "{}", msg)
+            }
+        }
+    }
+}
+
+impl Error for NetpbmError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            NetpbmError::Io(e) => Some(e),
+            NetpbmError::Ppm(e) => Some(e),
+            NetpbmError::Pgm(e) => Some(e),
+            NetpbmError::Pbm(e) => Some(e),
+            NetpbmError::Pam(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for NetpbmError {
+    fn from(e: io::Error) -> Self {
+        NetpbmError::Io(e)
+    }
+}
+
+impl From<PPMDecodeError> for NetpbmError {
+    fn from(e: PPMDecodeError) -> Self {
+        NetpbmError::Ppm(e)
+    }
+}
+
+impl From<PGMDecodeError> for NetpbmError {
+    fn from(e: PGMDecodeError) -> Self {
+        NetpbmError::Pgm(e)
+    }
+}
+
+impl From<PBMDecodeError> for NetpbmError {
+    fn from(e: PBMDecodeError) -> Self {
+        NetpbmError::Pbm(e)
+    }
+}
+
+impl From<PAMDecodeError> for NetpbmError {
+    fn from(e: PAMDecodeError) -> Self {
+        NetpbmError::Pam(e)
+    }
+}
+
+impl From<Box<dyn Error>> for NetpbmError {
+    fn from(e: Box<dyn Error>) -> Self {
+        match e.downcast::<io::Error>() {
+            Ok(e) => NetpbmError::Io(*e),
+            Err(e) => NetpbmError::Encode(e.to_string()),
+        }
+    }
+}
+
+/// Loads a Netpbm image of any format (P1 to P7) into an Image, detecting the format from its
+/// magic number
+///
+/// Grayscale & black and white images are expanded to gray RGB pixels. The default Limits apply.
+///
+/// # Errors
+///
+/// Will return NetpbmError::UnknownFormat if the file doesn't start with a Netpbm magic number,
+/// otherwise any error from the codec of the detected format.
+pub fn load(path: &str) -> Result<Image, NetpbmError> {
+    load_with_limits(path, &Limits::default())
+}
+
+/// Loads a Netpbm image of any format as `load` does, but with the given limits instead of the
+/// defaults.
+pub fn load_with_limits(path: &str, limits: &Limits) -> Result<Image, NetpbmError> {
+    let mut reader = BufReader::new(fs::File::open(path)?);
+    read_from_with_limits(&mut reader, limits)
+}
+
+/// Reads a single Netpbm image of any format from `reader`, as `load` does.
+pub fn read_from<R: BufRead>(reader: &mut R) -> Result<Image, NetpbmError> {
+    read_from_with_limits(reader, &Limits::default())
+}
+
+/// Reads a single Netpbm image of any format from `reader`, as `load_with_limits` does.
+pub fn read_from_with_limits<R: BufRead>(
+    reader: &mut R,
+    limits: &Limits,
+) -> Result<Image, NetpbmError> {
+    // A single fill_buf may hold less than the whole magic number, so it's read instead, &
+    // put back in front of the rest for the codec
+    let mut magic: Vec<u8> = Vec::new();
+    reader.take(2).read_to_end(&mut magic)?;
+    let format = Format::from_magic(&magic).ok_or_else(|| {
+        NetpbmError::UnknownFormat(format!(
+            "Expected a Netpbm magic number from 'P1' to 'P7', but found '{}'.",
+            String::from_utf8_lossy(&magic)
+        ))
+    })?;
+    let reader = &mut magic.as_slice().chain(reader);
+
+    Ok(match format {
+        Format::Pbm => PBMImage::read_from_with_limits(reader, limits)?
+            .into_bitmap()
+            .to_image(),
+        Format::Pgm => PGMImage::read_from_with_limits(reader, limits)?
+            .into_gray_image()
+            .convert(),
+        Format::Ppm => PPMImage::read_from_with_limits(reader, limits)?.into_image(),
+        Format::Pam => PAMImage::read_from_with_limits(reader, limits)?.into_image(),
+    })
+}
+
+/// Saves an Image, in the format picked from the extension of `path` unless overridden
+///
+/// PGM output uses the luma of each pixel, & PBM output sets every pixel whose luma is below
+/// 128, as Bitmap::threshold does.
+///
+/// # Errors
+///
+/// Will return NetpbmError::UnknownFormat if no format was given & the extension isn't one of
+/// .pbm, .pgm, .ppm, .pam or .pnm, otherwise an error if the image can't be encoded or written.
+pub fn save(path: &str, image: &Image, options: &SaveOptions) -> Result<(), NetpbmError> {
+    let format = match options.format.or_else(|| Format::from_extension(path)) {
+        Some(format) => format,
+        None => {
+            return Err(NetpbmError::UnknownFormat(format!(
+                "Can't pick a Netpbm format from the extension of '{}'.",
+                path
+            )));
+        }
+    };
+
+    raster::write_file(path, || {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_to(&mut buffer, format, image, options)?;
+        Ok(buffer)
+    })
+}
+
+/// Writes an Image to `writer` in the given format, as `save` does.
+pub fn write_to<W: Write>(
+    writer: &mut W,
+    format: Format,
+    image: &Image,
+    options: &SaveOptions,
+) -> Result<(), NetpbmError> {
+    // The codec builders require a filename, which isn't used when writing to a writer
+    let filename = format!("image.{}", format.extension());
+
+    let flattened;
+    let flat_image = if *image.get_color_mode() == ColorMode::Rgb {
+        image
+    } else {
+        flattened = image.flatten(options.background);
+        &flattened
+    };
+
+    match format {
+        Format::Pbm => PBMImage::builder()
+            .image(&Bitmap::threshold(flat_image, 128))
+            .filename(&filename)
+            .encoding(options.encoding)
+            .build()
+            .map_err(|e| NetpbmError::Encode(e.to_string()))?
+            .write_to(writer)?,
+        Format::Pgm => {
+            let mut builder = PGMImage::builder();
+            builder
+                .image(&flat_image.convert())
+                .filename(&filename)
+                .encoding(options.encoding);
+            if let Some(maxval) = options.maxval {
+                builder.maxval(maxval);
+            }
+            builder
+                .build()
+                .map_err(|e| NetpbmError::Encode(e.to_string()))?
+                .write_to(writer)?
+        }
+        Format::Ppm => {
+            let mut builder = PPMImage::builder();
+            builder
+                .image(flat_image)
+                .filename(&filename)
+                .encoding(options.encoding);
+            if let Some(maxval) = options.maxval {
+                builder.maxval(maxval);
+            }
+            builder
+                .build()
+                .map_err(|e| NetpbmError::Encode(e.to_string()))?
+                .write_to(writer)?
+        }
+        Format::Pam => {
+            let mut builder = PAMImage::builder();
+            builder.image(image).filename(&filename);
+            if let Some(tuple_type) = options.tuple_type {
+                builder.tuple_type(tuple_type);
+            }
+            if let Some(maxval) = options.maxval {
+                builder.maxval(maxval);
+            }
+            builder
+                .build()
+                .map_err(|e| NetpbmError::Encode(e.to_string()))?
+                .write_to(writer)?
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::{BLACK, RED, WHITE};
+    use crate::coordinate::Coordinate;
+
+    fn test_image() -> Result<Image, Box<dyn Error>> {
+        let mut image = Image::builder().rows(6).cols(9).build()?;
+        image.fill(WHITE).checkerboard(3, BLACK);
+        Ok(image)
+    }
+
+    #[test]
+    fn test_save_and_load() -> Result<(), Box<dyn Error>> {
+        let image = test_image()?;
+
+        for path in [
+            "test_netpbm.pbm",
+            "test_netpbm.pgm",
+            "test_netpbm.ppm",
+            "test_netpbm.pam",
+            "test_netpbm.pnm",
+            "test_netpbm_upper.PPM",
+        ] {
+            for encoding in [Encoding::Raw, Encoding::Plain] {
+                save(path, &image, SaveOptions::new().encoding(encoding))?;
+
+                let mut bytes = [0u8; 2];
+                io::Read::read_exact(&mut fs::File::open(path)?, &mut bytes)?;
+                assert_eq!(Format::from_magic(&bytes), Format::from_extension(path));
+
+                assert_eq!(load(path)?, image);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_override() -> Result<(), Box<dyn Error>> {
+        let mut image = test_image()?;
+        image.set_pixel(Coordinate::new(0, 0), RED)?;
+
+        save(
+            "test_netpbm_override.img",
+            &image,
+            SaveOptions::new().format(Format::Ppm).maxval(1000),
+        )?;
+        assert_eq!(load("test_netpbm_override.img")?, image);
+        fs::remove_file("test_netpbm_override.img")?;
+
+        let mut bytes: Vec<u8> = Vec::new();
+        write_to(&mut bytes, Format::Pam, &image, &SaveOptions::new())?;
+        assert_eq!(Format::from_magic(&bytes), Some(Format::Pam));
+        assert_eq!(read_from(&mut bytes.as_slice())?, image);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_from_short_reads() -> Result<(), Box<dyn Error>> {
+        let image = test_image()?;
+        let mut bytes: Vec<u8> = Vec::new();
+        write_to(&mut bytes, Format::Ppm, &image, &SaveOptions::new())?;
+        bytes.extend_from_slice(b"rest");
+
+        // Buffers of a single byte never hold the whole magic number at once
+        let mut reader = BufReader::with_capacity(1, bytes.as_slice());
+        assert_eq!(read_from(&mut reader)?, image);
+
+        let mut rest = String::new();
+        io::Read::read_to_string(&mut reader, &mut rest)?;
+        assert_eq!(rest, "rest");
+
+        assert!(matches!(
+            read_from(&mut BufReader::with_capacity(1, &b"P"[..])),
+            Err(NetpbmError::UnknownFormat(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() -> Result<(), Box<dyn Error>> {
+        let image = test_image()?;
+
+        assert!(matches!(
+            save("test_netpbm.png", &image, &SaveOptions::new()),
+            Err(NetpbmError::UnknownFormat(_))
+        ));
+        assert!(matches!(
+            read_from(&mut &b"\x89PNG"[..]),
+            Err(NetpbmError::UnknownFormat(_))
+        ));
+        assert!(matches!(
+            read_from(&mut &b"P5 2 2 255 \x00"[..]),
+            Err(NetpbmError::Pgm(PGMDecodeError::TruncatedPixelData(_)))
+        ));
+        assert!(matches!(
+            read_from(&mut &b"P4\n0 1\n"[..]),
+            Err(NetpbmError::Pbm(PBMDecodeError::MalformedHeader(_)))
+        ));
+        assert!(matches!(
+            read_from(&mut &b"P6\n100000 100000\n255\n"[..]),
+            Err(NetpbmError::Ppm(PPMDecodeError::LimitExceeded(_)))
+        ));
+        assert!(matches!(
+            read_from_with_limits(&mut &b"P5 2 2 255 "[..], Limits::new().max_pixels(3)),
+            Err(NetpbmError::Pgm(PGMDecodeError::LimitExceeded(_)))
+        ));
+
+        // A failed save leaves an existing file alone
+        save("test_netpbm_failed.pgm", &image, &SaveOptions::new())?;
+        assert!(matches!(
+            save(
+                "test_netpbm_failed.pgm",
+                &image,
+                SaveOptions::new().maxval(0)
+            ),
+            Err(NetpbmError::Encode(_))
+        ));
+        assert_eq!(load("test_netpbm_failed.pgm")?, image);
+        fs::remove_file("test_netpbm_failed.pgm")?;
+
+        assert!(matches!(
+            load("test_netpbm_missing.ppm"),
+            Err(NetpbmError::Io(_))
+        ));
+
+        Ok(())
+    }
+}
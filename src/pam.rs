@@ -0,0 +1,840 @@
+//! PAM (P7) images.
+//!
+//! Tuple types are mapped onto the library's image types:
+//!
+//! - BLACKANDWHITE onto a Bitmap
+//! - GRAYSCALE onto an Image<Luma8>
+//! - RGB onto an Image
+//! - GRAYSCALE_ALPHA & RGB_ALPHA onto an Image, with the alpha channel carried in the top byte
+//!   of each pixel - 0xAA_RR_GG_BB
+
+use crate::graphics::bitmap::Bitmap;
+use crate::graphics::image::{ColorMode, Image, ImageBuilder};
+use crate::graphics::pixel::{luma, rescale, Luma8};
+use crate::header::{Encoding, HeaderError, PamHeader};
+use crate::limits::Limits;
+use crate::raster::{self, RasterError};
+use std::error::Error;
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::io::{BufReader, Read, Write};
+
+/// Tuple types defined by the PAM spec.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TupleType {
+    BlackAndWhite,
+    Grayscale,
+    Rgb,
+    GrayscaleAlpha,
+    RgbAlpha,
+}
+
+impl TupleType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TupleType::BlackAndWhite => "BLACKANDWHITE",
+            TupleType::Grayscale => "GRAYSCALE",
+            TupleType::Rgb => "RGB",
+            TupleType::GrayscaleAlpha => "GRAYSCALE_ALPHA",
+            TupleType::RgbAlpha => "RGB_ALPHA",
+        }
+    }
+
+    /// Number of samples in each tuple
+    pub fn depth(&self) -> usize {
+        match self {
+            TupleType::BlackAndWhite | TupleType::Grayscale => 1,
+            TupleType::GrayscaleAlpha => 2,
+            TupleType::Rgb => 3,
+            TupleType::RgbAlpha => 4,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TupleType> {
+        [
+            TupleType::BlackAndWhite,
+            TupleType::Grayscale,
+            TupleType::Rgb,
+            TupleType::GrayscaleAlpha,
+            TupleType::RgbAlpha,
+        ]
+        .into_iter()
+        .find(|tuple_type| tuple_type.name() == name)
+    }
+}
+
+impl Display for TupleType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Pixel storage backing a PAMImage.
+#[derive(Debug, PartialEq, Clone)]
+enum Raster {
+    Color(Image),
+    Gray(Image<Luma8>),
+    Bitmap(Bitmap),
+}
+
+impl Raster {
+    fn get_rows(&self) -> usize {
+        match self {
+            Raster::Color(image) => *image.get_rows(),
+            Raster::Gray(image) => *image.get_rows(),
+            Raster::Bitmap(image) => *image.get_rows(),
+        }
+    }
+
+    fn get_cols(&self) -> usize {
+        match self {
+            Raster::Color(image) => *image.get_cols(),
+            Raster::Gray(image) => *image.get_cols(),
+            Raster::Bitmap(image) => *image.get_cols(),
+        }
+    }
+
+    /// Pixels as 0xAA_RR_GG_BB with straight alpha, where pixels of RGB, grayscale & bitmap
+    /// images are fully opaque.
+    fn argb(&self) -> Vec<u32> {
+        match self {
+            Raster::Color(image) => image.to_rgba().get_data().clone(),
+            Raster::Gray(image) => opaque(image.convert()),
+            Raster::Bitmap(image) => opaque(image.to_image()),
+        }
+    }
+
+    /// All samples of the raster as 8 bit values, laid out as tuples of `tuple_type`.
+    fn samples(&self, tuple_type: TupleType) -> Vec<u16> {
+        let argb = self.argb();
+        let channel = |pixel: &u32, shift: u32| ((pixel >> shift) & 0xFF) as u16;
+
+        match tuple_type {
+            TupleType::BlackAndWhite => match self {
+                // PAM uses 1 for white, the opposite of PBM
+                Raster::Bitmap(image) => image.get_data().iter().map(|b| !b as u16).collect(),
+                _ => argb
+                    .iter()
+                    .map(|pixel| (luma(*pixel) >= 128) as u16)
+                    .collect(),
+            },
+            TupleType::Grayscale => argb.iter().map(|pixel| luma(*pixel) as u16).collect(),
+            TupleType::GrayscaleAlpha => argb
+                .iter()
+                .flat_map(|pixel| [luma(*pixel) as u16, channel(pixel, 24)])
+                .collect(),
+            TupleType::Rgb => argb
+                .iter()
+                .flat_map(|pixel| [16, 8, 0].map(|shift| channel(pixel, shift)))
+                .collect(),
+            TupleType::RgbAlpha => argb
+                .iter()
+                .flat_map(|pixel| [16, 8, 0, 24].map(|shift| channel(pixel, shift)))
+                .collect(),
+        }
+    }
+}
+
+/// Sets the alpha of every pixel to fully opaque
+fn opaque(image: Image) -> Vec<u32> {
+    image
+        .get_data()
+        .iter()
+        .map(|pixel| pixel | 0xFF_00_00_00)
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PAMImage {
+    raster: Raster,
+    header: Vec<u8>,
+    tuple_type: TupleType,
+    maxval: u16,
+    pub filename: String,
+}
+
+impl PAMImage {
+    pub fn builder() -> PAMImageBuilder {
+        PAMImageBuilder::new()
+    }
+
+    /// Reads a .pam file with one of the supported tuple types
+    ///
+    /// Samples are rescaled from the maxval of the file to 255.
+    ///
+    /// # Errors
+    ///
+    /// Will return a PAMDecodeError if the file can't be read, the header is malformed, has an
+    /// unsupported tuple type or exceeds the default Limits, or the file ends before all of the
+    /// pixel data declared in the header has been read.
+    pub fn read(filename: &str) -> Result<PAMImage, PAMDecodeError> {
+        let mut reader = BufReader::new(fs::File::open(filename)?);
+        let mut pam_image = PAMImage::read_from(&mut reader)?;
+        pam_image.filename = filename.to_string();
+        Ok(pam_image)
+    }
+
+    /// Reads a single image from `reader`
+    ///
+    /// Only the bytes of the image are consumed, leaving anything after it in the reader. The
+    /// header is read one byte at a time, so unbuffered readers should be wrapped in a
+    /// BufReader. The filename of the returned PAMImage is empty.
+    ///
+    /// # Errors
+    ///
+    /// See PAMImage::read
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<PAMImage, PAMDecodeError> {
+        PAMImage::read_from_with_limits(reader, &Limits::default())
+    }
+
+    /// Reads a single image from `reader` as PAMImage::read_from does, but with the given limits
+    /// instead of the defaults
+    ///
+    /// # Errors
+    ///
+    /// See PAMImage::read
+    pub fn read_from_with_limits<R: Read>(
+        reader: &mut R,
+        limits: &Limits,
+    ) -> Result<PAMImage, PAMDecodeError> {
+        let (raster, header, tuple_type) = read_raster(reader, limits)?;
+
+        Ok(PAMImage {
+            raster,
+            // Keep the header in the same form that PAMImageBuilder::build produces
+            header: header.encode(&[]),
+            tuple_type,
+            maxval: header.maxval,
+            filename: String::new(),
+        })
+    }
+
+    /// Converts into an Image. Tuple types with alpha produce a straight alpha RGBA image.
+    pub fn into_image(self) -> Image {
+        match self.raster {
+            Raster::Color(image) => image,
+            Raster::Gray(image) => image.convert(),
+            Raster::Bitmap(image) => image.to_image(),
+        }
+    }
+
+    /// Converts into an Image<Luma8>, using the luma of color pixels.
+    pub fn into_gray_image(self) -> Image<Luma8> {
+        match self.raster {
+            Raster::Color(image) => image.convert(),
+            Raster::Gray(image) => image,
+            Raster::Bitmap(image) => image.to_image().convert(),
+        }
+    }
+
+    /// Converts into a Bitmap, setting pixels whose luma is below the midpoint.
+    pub fn into_bitmap(self) -> Bitmap {
+        match self.raster {
+            Raster::Color(image) => Bitmap::threshold(&image, 128),
+            Raster::Gray(image) => Bitmap::threshold(&image.convert(), 128),
+            Raster::Bitmap(image) => image,
+        }
+    }
+
+    pub fn get_tuple_type(&self) -> &TupleType {
+        &self.tuple_type
+    }
+
+    pub fn get_maxval(&self) -> &u16 {
+        &self.maxval
+    }
+
+    /// Writes an image to a .pam file
+    ///
+    /// Samples are rescaled to the maxval & written with one byte each when the maxval is less
+    /// than 256, otherwise with two bytes each, most significant byte first.
+    pub fn write(&self) -> Result<(), Box<dyn Error>> {
+        raster::write_file(&self.filename, || Ok(self.to_bytes()))
+    }
+
+    /// Writes the image to `writer`, in the same form as PAMImage::write
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if writing to `writer` fails.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), Box<dyn Error>> {
+        writer.write_all(&self.to_bytes())?;
+        Ok(())
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer: Vec<u8> = Vec::new();
+
+        // Push header data into write buffer
+        self.header.iter().for_each(|byte| buffer.push(*byte));
+
+        // Push pixel data into write buffer
+        let native_maxval = match self.tuple_type {
+            TupleType::BlackAndWhite => 1,
+            _ => 255,
+        };
+        let samples: Vec<u16> = self
+            .raster
+            .samples(self.tuple_type)
+            .iter()
+            .map(|sample| rescale(*sample, native_maxval, self.maxval))
+            .collect();
+        raster::encode(&samples, self.maxval, Encoding::Raw, &mut buffer);
+
+        buffer
+    }
+} /* PAMImage */
+
+#[derive(Default, Clone)]
+pub struct PAMImageBuilder {
+    raster: Option<Raster>,
+    filename: Option<String>,
+    tuple_type: Option<TupleType>,
+    maxval: Option<u16>,
+    comments: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum PAMImageBuilderError {
+    ImageNotProvided(String),
+    FilenameNotProvided(String),
+    InvalidMaxval(String),
+}
+
+impl Display for PAMImageBuilderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PAMImageBuilderError::ImageNotProvided(msg) => {
+                write!(f, "{}", msg)
+            }
+            PAMImageBuilderError::FilenameNotProvided(msg) => {
+                write!(f, "{}", msg)
+            }
+            PAMImageBuilderError::InvalidMaxval(msg) => {
+                write!(f, "{}", msg)
+            }
+        }
+    }
+}
+
+impl Error for PAMImageBuilderError {}
+
+impl PAMImageBuilder {
+    pub fn new() -> Self {
+        PAMImageBuilder {
+            raster: None,
+            filename: None,
+            tuple_type: None,
+            maxval: None,
+            comments: Vec::new(),
+        }
+    }
+
+    /// Uses an Image, written as RGB unless another tuple type is selected.
+    pub fn image(&mut self, image: &Image) -> &mut Self {
+        self.raster = Some(Raster::Color(image.clone()));
+        self
+    }
+
+    /// Uses an Image<Luma8>, written as GRAYSCALE unless another tuple type is selected.
+    pub fn gray_image(&mut self, image: &Image<Luma8>) -> &mut Self {
+        self.raster = Some(Raster::Gray(image.clone()));
+        self
+    }
+
+    /// Uses a Bitmap, written as BLACKANDWHITE unless another tuple type is selected.
+    pub fn bitmap(&mut self, image: &Bitmap) -> &mut Self {
+        self.raster = Some(Raster::Bitmap(image.clone()));
+        self
+    }
+
+    pub fn filename(&mut self, filename: &str) -> &mut Self {
+        self.filename = Some(filename.to_string());
+        self
+    }
+
+    /// Selects the tuple type written, converting the image as needed.
+    pub fn tuple_type(&mut self, tuple_type: TupleType) -> &mut Self {
+        self.tuple_type = Some(tuple_type);
+        self
+    }
+
+    /// Sets the maxval written to the header, between 1 and 65535. Defaults to 255, or 1 for
+    /// BLACKANDWHITE, which only allows a maxval of 1.
+    pub fn maxval(&mut self, maxval: u16) -> &mut Self {
+        self.maxval = Some(maxval);
+        self
+    }
+
+    /// Adds a comment to the header. Comments are written in the order they are added.
+    pub fn comment(&mut self, comment: &str) -> &mut Self {
+        self.comments.push(comment.to_string());
+        self
+    }
+
+    pub fn build(&mut self) -> Result<PAMImage, PAMImageBuilderError> {
+        let raster = match &self.raster {
+            None => {
+                return Err(PAMImageBuilderError::ImageNotProvided(String::from(
+                    "Image must be provided to build a PAMImage.",
+                )));
+            }
+            Some(raster) => raster,
+        };
+
+        let filename = match &self.filename {
+            None => {
+                return Err(PAMImageBuilderError::FilenameNotProvided(String::from(
+                    "Filename must be provided to build a PAMImage.",
+                )));
+            }
+            Some(filename) => filename,
+        };
+
+        let tuple_type = self.tuple_type.unwrap_or(match raster {
+            Raster::Color(image) if *image.get_color_mode() == ColorMode::Rgb => TupleType::Rgb,
+            Raster::Color(_) => TupleType::RgbAlpha,
+            Raster::Gray(_) => TupleType::Grayscale,
+            Raster::Bitmap(_) => TupleType::BlackAndWhite,
+        });
+
+        let maxval = match (self.maxval, tuple_type) {
+            (Some(0), _) => {
+                return Err(PAMImageBuilderError::InvalidMaxval(String::from(
+                    "Maxval must be between 1 and 65535.",
+                )));
+            }
+            (Some(1) | None, TupleType::BlackAndWhite) => 1,
+            (Some(_), TupleType::BlackAndWhite) => {
+                return Err(PAMImageBuilderError::InvalidMaxval(String::from(
+                    "Maxval must be 1 for the BLACKANDWHITE tuple type.",
+                )));
+            }
+            (Some(maxval), _) => maxval,
+            (None, _) => 255,
+        };
+
+        let header = PamHeader::new(
+            raster.get_cols(),
+            raster.get_rows(),
+            tuple_type.depth(),
+            maxval,
+            Some(tuple_type.name()),
+        );
+
+        Ok(PAMImage {
+            raster: raster.clone(),
+            header: header.encode(&self.comments),
+            tuple_type,
+            maxval,
+            filename: filename.clone(),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum PAMDecodeError {
+    Io(io::Error),
+    Header(HeaderError),
+    UnsupportedTupleType(String),
+    MalformedHeader(String),
+    TruncatedPixelData(String),
+    InvalidPixelData(String),
+    LimitExceeded(String),
+}
+
+impl Display for PAMDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PAMDecodeError::Io(e) => {
+                write!(f, "{}", e)
+            }
+            PAMDecodeError::Header(e) => {
+                write!(f, "{}", e)
+            }
+            PAMDecodeError::UnsupportedTupleType(msg) => {
+                write!(f, "{}", msg)
+            }
+            PAMDecodeError::MalformedHeader(msg) => {
+                write!(f, "{}", msg)
+            }
+            PAMDecodeError::TruncatedPixelData(msg) => {
+                write!(f, "{}", msg)
+            }
+            PAMDecodeError::InvalidPixelData(msg) => {
+                write!(f, "{}", msg)
+            }
+            PAMDecodeError::LimitExceeded(msg) => {
+                write!(f, "{}", msg)
+            }
+        }
+    }
+}
+
+impl Error for PAMDecodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PAMDecodeError::Io(e) => Some(e),
+            PAMDecodeError::Header(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PAMDecodeError {
+    fn from(e: io::Error) -> Self {
+        PAMDecodeError::Io(e)
+    }
+}
+
+impl From<RasterError> for PAMDecodeError {
+    fn from(e: RasterError) -> Self {
+        match e {
+            RasterError::Io(e) => PAMDecodeError::Io(e),
+            RasterError::TruncatedPixelData(msg) => PAMDecodeError::TruncatedPixelData(msg),
+            RasterError::InvalidPixelData(msg) => PAMDecodeError::InvalidPixelData(msg),
+        }
+    }
+}
+
+impl From<HeaderError> for PAMDecodeError {
+    fn from(e: HeaderError) -> Self {
+        match e {
+            HeaderError::Io(e) => PAMDecodeError::Io(e),
+            e => PAMDecodeError::Header(e),
+        }
+    }
+}
+
+/// Decodes the bytes of a .pam file into an Image, see PAMImage::into_image. The default Limits
+/// apply.
+pub fn decode(bytes: &[u8]) -> Result<Image, PAMDecodeError> {
+    let mut reader = bytes;
+    let (raster, _, _) = read_raster(&mut reader, &Limits::default())?;
+
+    Ok(match raster {
+        Raster::Color(image) => image,
+        Raster::Gray(image) => image.convert(),
+        Raster::Bitmap(image) => image.to_image(),
+    })
+}
+
+fn read_raster<R: Read>(
+    reader: &mut R,
+    limits: &Limits,
+) -> Result<(Raster, PamHeader, TupleType), PAMDecodeError> {
+    let header = PamHeader::read_from(reader)?;
+
+    let PamHeader {
+        width: cols,
+        height: rows,
+        depth,
+        maxval,
+        ..
+    } = header;
+
+    let tuple_type = header
+        .tuple_type
+        .as_deref()
+        .and_then(TupleType::from_name)
+        .ok_or_else(|| {
+            PAMDecodeError::UnsupportedTupleType(format!(
+                "Unsupported tuple type {:?}.",
+                header.tuple_type
+            ))
+        })?;
+
+    if depth != tuple_type.depth() {
+        return Err(PAMDecodeError::MalformedHeader(format!(
+            "Tuple type {} requires a depth of {}, but found {}.",
+            tuple_type,
+            tuple_type.depth(),
+            depth
+        )));
+    }
+
+    if tuple_type == TupleType::BlackAndWhite && maxval != 1 {
+        return Err(PAMDecodeError::MalformedHeader(format!(
+            "Tuple type BLACKANDWHITE requires a maxval of 1, but found {}.",
+            maxval
+        )));
+    }
+
+    let bytes_per_sample = if maxval < 256 { 1 } else { 2 };
+    limits
+        .check_dimensions(cols, rows)
+        .and_then(|_| {
+            limits.check_raster(
+                cols,
+                rows,
+                raster::sample_count(rows, cols, depth * bytes_per_sample),
+            )
+        })
+        .map_err(PAMDecodeError::LimitExceeded)?;
+
+    let expected = raster::sample_count(rows, cols, depth).ok_or_else(|| {
+        PAMDecodeError::MalformedHeader(format!(
+            "Image dimensions {} by {} by {} are too large.",
+            cols, rows, depth
+        ))
+    })?;
+    let samples: Vec<u8> = raster::read(reader, expected, maxval, Encoding::Raw)?
+        .iter()
+        .map(|sample| rescale(*sample, maxval, 255) as u8)
+        .collect();
+
+    let build_error = |e: crate::graphics::image::ImageBuilderError| {
+        PAMDecodeError::MalformedHeader(e.to_string())
+    };
+
+    let raster = match tuple_type {
+        TupleType::BlackAndWhite => Raster::Bitmap(
+            Bitmap::builder()
+                .rows(rows)
+                .cols(cols)
+                .data(samples.iter().map(|sample| *sample == 0).collect())
+                .build()
+                .map_err(build_error)?,
+        ),
+        TupleType::Grayscale => Raster::Gray(
+            ImageBuilder::<Luma8>::new()
+                .rows(rows)
+                .cols(cols)
+                .data(samples.into_iter().map(Luma8).collect())
+                .build()
+                .map_err(build_error)?,
+        ),
+        _ => {
+            // Expand each tuple to [red, green, blue, alpha]
+            let data: Vec<u32> = samples
+                .chunks_exact(depth)
+                .map(|tuple| {
+                    let [r, g, b, a] = match tuple {
+                        [gray] => [*gray, *gray, *gray, 0],
+                        [gray, alpha] => [*gray, *gray, *gray, *alpha],
+                        [r, g, b] => [*r, *g, *b, 0],
+                        [r, g, b, alpha] => [*r, *g, *b, *alpha],
+                        _ => unreachable!("Depth was validated against the tuple type"),
+                    };
+                    u32::from_be_bytes([a, r, g, b])
+                })
+                .collect();
+
+            let color_mode = match tuple_type {
+                TupleType::GrayscaleAlpha | TupleType::RgbAlpha => ColorMode::Rgba,
+                _ => ColorMode::Rgb,
+            };
+
+            Raster::Color(
+                Image::builder()
+                    .rows(rows)
+                    .cols(cols)
+                    .data(data)
+                    .color_mode(color_mode)
+                    .build()
+                    .map_err(build_error)?,
+            )
+        }
+    };
+
+    Ok((raster, header, tuple_type))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::{BLACK, MAGENTA, RED, TEAL, WHITE};
+    use crate::coordinate::CircleCoordinates;
+
+    #[test]
+    fn test_read_round_trip() -> Result<(), Box<dyn Error>> {
+        let mut image = Image::builder().rows(24).cols(32).build()?;
+        image.fill(MAGENTA).checkerboard(4, TEAL);
+
+        // Alpha gradient in the top byte of each pixel
+        let data: Vec<u32> = (0..24 * 32)
+            .map(|i| image.get_data()[i] | ((i as u32 % 256) << 24))
+            .collect();
+        let alpha_image = Image::builder()
+            .rows(24)
+            .cols(32)
+            .data(data)
+            .color_mode(ColorMode::Rgba)
+            .build()?;
+
+        let gray_data: Vec<u32> = alpha_image
+            .get_data()
+            .iter()
+            .map(|pixel| (pixel & 0xFF_00_00_00) | (luma(*pixel) as u32 * 0x01_01_01))
+            .collect();
+        let gray_alpha_image = Image::builder()
+            .rows(24)
+            .cols(32)
+            .data(gray_data)
+            .color_mode(ColorMode::Rgba)
+            .build()?;
+
+        for (image, tuple_type) in [
+            (&image, TupleType::Rgb),
+            (&alpha_image, TupleType::RgbAlpha),
+            (&gray_alpha_image, TupleType::GrayscaleAlpha),
+        ] {
+            let pam_image = PAMImage::builder()
+                .image(image)
+                .filename("test_pam_read_round_trip.pam")
+                .tuple_type(tuple_type)
+                .build()?;
+            pam_image.write()?;
+
+            let read_pam_image = PAMImage::read("test_pam_read_round_trip.pam")?;
+            assert_eq!(read_pam_image, pam_image);
+            assert_eq!(read_pam_image.into_image(), *image);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_to_and_read_from() -> Result<(), Box<dyn Error>> {
+        let image = Image::builder()
+            .rows(2)
+            .cols(3)
+            .data((0..6).map(|i| 0x33_00_80_FF + i * 0x22_01_00_00).collect())
+            .color_mode(ColorMode::Rgba)
+            .build()?;
+
+        for maxval in [255, 65535] {
+            let mut buffer: Vec<u8> = Vec::new();
+            PAMImage::builder()
+                .image(&image)
+                .filename("unused.pam")
+                .maxval(maxval)
+                .build()?
+                .write_to(&mut buffer)?;
+            buffer.extend_from_slice(b"trailing");
+
+            let mut reader = io::Cursor::new(buffer);
+            let pam_image = PAMImage::read_from(&mut reader)?;
+            assert_eq!(pam_image.filename, "");
+            assert_eq!(pam_image.into_image(), image);
+
+            // Only the image should have been consumed
+            let mut rest = Vec::new();
+            reader.read_to_end(&mut rest)?;
+            assert_eq!(rest, b"trailing");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_alpha_is_preserved() -> Result<(), Box<dyn Error>> {
+        let mut sprite = Image::builder()
+            .rows(1)
+            .cols(3)
+            .data(vec![0xFF_FF_00_00, 0x80_FF_00_00, 0x00_00_00_00])
+            .color_mode(ColorMode::Rgba)
+            .build()?;
+
+        // RGBA images default to RGB_ALPHA, & premultiplied pixels are stored straight
+        sprite.premultiply_alpha();
+        let mut bytes: Vec<u8> = Vec::new();
+        PAMImage::builder()
+            .image(&sprite)
+            .filename("unused.pam")
+            .build()?
+            .write_to(&mut bytes)?;
+
+        let image = decode(&bytes)?;
+        assert_eq!(image.get_color_mode(), &ColorMode::Rgba);
+        assert_eq!(
+            image.get_data(),
+            &vec![0xFF_FF_00_00, 0x80_FF_00_00, 0x00_00_00_00]
+        );
+
+        // RGB images are written as opaque
+        let image = Image::builder()
+            .rows(1)
+            .cols(1)
+            .data(vec![RED.into()])
+            .build()?;
+        bytes.clear();
+        PAMImage::builder()
+            .image(&image)
+            .filename("unused.pam")
+            .tuple_type(TupleType::RgbAlpha)
+            .build()?
+            .write_to(&mut bytes)?;
+        assert_eq!(decode(&bytes)?.get_data(), &vec![0xFF_FF_00_00]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_gray_and_bitmap_round_trip() -> Result<(), Box<dyn Error>> {
+        let mut image = Image::builder().rows(16).cols(16).build()?;
+        image
+            .fill(WHITE)
+            .draw_filled_circle(BLACK, CircleCoordinates::new(8, 8, 5))?;
+
+        let gray_image = image.convert::<Luma8>();
+        let pam_image = PAMImage::builder()
+            .gray_image(&gray_image)
+            .filename("test_pam_gray_round_trip.pam")
+            .maxval(1000)
+            .build()?;
+        pam_image.write()?;
+        assert_eq!(PAMImage::read("test_pam_gray_round_trip.pam")?, pam_image);
+
+        let bitmap = Bitmap::threshold(&image, 128);
+        let pam_image = PAMImage::builder()
+            .bitmap(&bitmap)
+            .filename("test_pam_bitmap_round_trip.pam")
+            .build()?;
+        pam_image.write()?;
+
+        let bytes = fs::read("test_pam_bitmap_round_trip.pam")?;
+        assert!(bytes.starts_with(b"P7\nWIDTH 16\nHEIGHT 16\nDEPTH 1\nMAXVAL 1\n"));
+        // PAM stores white as 1
+        assert_eq!(bytes[bytes.len() - 1], 1);
+        assert_eq!(
+            PAMImage::read("test_pam_bitmap_round_trip.pam")?.into_bitmap(),
+            bitmap
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!(matches!(
+            decode(b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 3\nMAXVAL 255\nTUPLTYPE CMYK\nENDHDR\n\0\0\0"),
+            Err(PAMDecodeError::UnsupportedTupleType(_))
+        ));
+        assert!(matches!(
+            decode(
+                b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 3\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n\0\0\0"
+            ),
+            Err(PAMDecodeError::MalformedHeader(_))
+        ));
+        assert!(matches!(
+            decode(b"P7\nWIDTH 2\nHEIGHT 1\nDEPTH 1\nMAXVAL 255\nTUPLTYPE GRAYSCALE\nENDHDR\n\0"),
+            Err(PAMDecodeError::TruncatedPixelData(_))
+        ));
+
+        // Limits are checked from the header alone, before any pixel data
+        assert!(matches!(
+            decode(b"P7\nWIDTH 100000\nHEIGHT 100000\nDEPTH 3\nMAXVAL 255\nTUPLTYPE RGB\nENDHDR\n"),
+            Err(PAMDecodeError::LimitExceeded(_))
+        ));
+        let limits = *Limits::new().max_bytes(5);
+        assert!(matches!(
+            PAMImage::read_from_with_limits(
+                &mut &b"P7\nWIDTH 2\nHEIGHT 1\nDEPTH 3\nMAXVAL 255\nTUPLTYPE RGB\nENDHDR\n"[..],
+                &limits
+            ),
+            Err(PAMDecodeError::LimitExceeded(_))
+        ));
+    }
+}
@@ -0,0 +1,568 @@
+use crate::graphics::bitmap::Bitmap;
+use crate::header::{HeaderError, MagicNumber, PnmHeader};
+use crate::limits::Limits;
+use crate::raster::{self, ByteReader, RasterError, PLAIN_LINE_LENGTH};
+use std::error::Error;
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::io::{BufReader, Read, Write};
+
+pub use crate::header::Encoding;
+
+/// Magic number of a .pbm file with the given encoding
+fn magic_number(encoding: Encoding) -> MagicNumber {
+    match encoding {
+        Encoding::Raw => MagicNumber::P4,
+        Encoding::Plain => MagicNumber::P1,
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PBMImage {
+    image: Bitmap,
+    header: Vec<u8>,
+    encoding: Encoding,
+    pub filename: String,
+}
+
+impl PBMImage {
+    pub fn builder() -> PBMImageBuilder {
+        PBMImageBuilder::new()
+    }
+
+    /// Reads a binary (P4) or plain (P1) encoded .pbm file
+    ///
+    /// # Errors
+    ///
+    /// Will return a PBMDecodeError if the file can't be read, the header is malformed or
+    /// exceeds the default Limits, or the file ends before all of the pixel data declared in the
+    /// header has been read.
+    pub fn read(filename: &str) -> Result<PBMImage, PBMDecodeError> {
+        let mut reader = BufReader::new(fs::File::open(filename)?);
+        let mut pbm_image = PBMImage::read_from(&mut reader)?;
+        pbm_image.filename = filename.to_string();
+        Ok(pbm_image)
+    }
+
+    /// Reads a single binary (P4) or plain (P1) encoded image from `reader`
+    ///
+    /// Only the bytes of the image are consumed, leaving anything after it in the reader. The
+    /// header is read one byte at a time, so unbuffered readers should be wrapped in a
+    /// BufReader. The filename of the returned PBMImage is empty.
+    ///
+    /// # Errors
+    ///
+    /// See PBMImage::read
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<PBMImage, PBMDecodeError> {
+        PBMImage::read_from_with_limits(reader, &Limits::default())
+    }
+
+    /// Reads a single image from `reader` as PBMImage::read_from does, but with the given limits
+    /// instead of the defaults
+    ///
+    /// # Errors
+    ///
+    /// See PBMImage::read
+    pub fn read_from_with_limits<R: Read>(
+        reader: &mut R,
+        limits: &Limits,
+    ) -> Result<PBMImage, PBMDecodeError> {
+        let (image, header) = read_with_header(reader, limits)?;
+
+        Ok(PBMImage {
+            image,
+            // Keep the header in the same form that PBMImageBuilder::build produces
+            header: header.encode(&[]),
+            encoding: header.magic.encoding(),
+            filename: String::new(),
+        })
+    }
+
+    pub fn into_bitmap(self) -> Bitmap {
+        self.image
+    }
+
+    pub fn get_encoding(&self) -> &Encoding {
+        &self.encoding
+    }
+
+    /// Writes a Bitmap to a .pbm file
+    ///
+    /// Raw rows are packed 8 pixels to a byte, most significant bit first, with the last byte
+    /// of each row padded with zeros.
+    pub fn write(&self) -> Result<(), Box<dyn Error>> {
+        raster::write_file(&self.filename, || Ok(self.to_bytes()))
+    }
+
+    /// Writes the image to `writer`, in the same form as PBMImage::write
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if writing to `writer` fails.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), Box<dyn Error>> {
+        writer.write_all(&self.to_bytes())?;
+        Ok(())
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer: Vec<u8> = Vec::new();
+
+        // Push header data into write buffer
+        self.header.iter().for_each(|byte| buffer.push(*byte));
+
+        // Push pixel data into write buffer
+        for row in self.image.get_data().chunks(*self.image.get_cols()) {
+            match self.encoding {
+                Encoding::Raw => buffer.extend(pack_row(row)),
+                Encoding::Plain => {
+                    for line in row.chunks(PLAIN_LINE_LENGTH) {
+                        buffer.extend(line.iter().map(|bit| if *bit { b'1' } else { b'0' }));
+                        buffer.push(b'\n');
+                    }
+                }
+            }
+        }
+
+        buffer
+    }
+} /* PBMImage */
+
+/// Packs a row of pixels into bytes, most significant bit first.
+fn pack_row(row: &[bool]) -> Vec<u8> {
+    row.chunks(8)
+        .map(|bits| {
+            bits.iter()
+                .enumerate()
+                .fold(0u8, |byte, (i, bit)| byte | ((*bit as u8) << (7 - i)))
+        })
+        .collect()
+}
+
+#[derive(Default, Clone)]
+pub struct PBMImageBuilder {
+    image: Option<Bitmap>,
+    filename: Option<String>,
+    encoding: Encoding,
+    comments: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum PBMImageBuilderError {
+    ImageNotProvided(String),
+    FilenameNotProvided(String),
+}
+
+impl Display for PBMImageBuilderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PBMImageBuilderError::ImageNotProvided(msg) => {
+                write!(f, "{}", msg)
+            }
+            PBMImageBuilderError::FilenameNotProvided(msg) => {
+                write!(f, "{}", msg)
+            }
+        }
+    }
+}
+
+impl Error for PBMImageBuilderError {}
+
+impl PBMImageBuilder {
+    pub fn new() -> Self {
+        PBMImageBuilder {
+            image: None,
+            filename: None,
+            encoding: Encoding::Raw,
+            comments: Vec::new(),
+        }
+    }
+
+    pub fn image(&mut self, image: &Bitmap) -> &mut Self {
+        self.image = Some(image.clone());
+        self
+    }
+
+    pub fn filename(&mut self, filename: &str) -> &mut Self {
+        self.filename = Some(filename.to_string());
+        self
+    }
+
+    /// Selects between binary (P4, the default) and plain (P1) encoding.
+    pub fn encoding(&mut self, encoding: Encoding) -> &mut Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Adds a comment to the header. Comments are written in the order they are added.
+    pub fn comment(&mut self, comment: &str) -> &mut Self {
+        self.comments.push(comment.to_string());
+        self
+    }
+
+    pub fn build(&mut self) -> Result<PBMImage, PBMImageBuilderError> {
+        let image = match &self.image {
+            None => {
+                return Err(PBMImageBuilderError::ImageNotProvided(String::from(
+                    "Image must be provided to build a PBMImage.",
+                )));
+            }
+            Some(image) => image,
+        };
+
+        let filename = match &self.filename {
+            None => {
+                return Err(PBMImageBuilderError::FilenameNotProvided(String::from(
+                    "Filename must be provided to build a PBMImage.",
+                )));
+            }
+            Some(filename) => filename,
+        };
+
+        let header = PnmHeader::new(
+            magic_number(self.encoding),
+            *image.get_cols(),
+            *image.get_rows(),
+            1,
+        );
+
+        Ok(PBMImage {
+            image: image.clone(),
+            header: header.encode(&self.comments),
+            encoding: self.encoding,
+            filename: filename.clone(),
+        })
+    }
+}
+
+impl Bitmap {
+    /// Reads a binary (P4) or plain (P1) encoded .pbm file into a Bitmap
+    ///
+    /// # Errors
+    ///
+    /// See PBMImage::read
+    pub fn from_pbm(filename: &str) -> Result<Bitmap, PBMDecodeError> {
+        Ok(PBMImage::read(filename)?.into_bitmap())
+    }
+}
+
+#[derive(Debug)]
+pub enum PBMDecodeError {
+    Io(io::Error),
+    Header(HeaderError),
+    InvalidMagicNumber(String),
+    MalformedHeader(String),
+    TruncatedPixelData(String),
+    InvalidPixelData(String),
+    LimitExceeded(String),
+}
+
+impl Display for PBMDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PBMDecodeError::Io(e) => {
+                write!(f, "{}", e)
+            }
+            PBMDecodeError::Header(e) => {
+                write!(f, "{}", e)
+            }
+            PBMDecodeError::InvalidMagicNumber(msg) => {
+                write!(f, "{}", msg)
+            }
+            PBMDecodeError::MalformedHeader(msg) => {
+                write!(f, "{}", msg)
+            }
+            PBMDecodeError::TruncatedPixelData(msg) => {
+                write!(f, "{}", msg)
+            }
+            PBMDecodeError::InvalidPixelData(msg) => {
+                write!(f, "{}", msg)
+            }
+            PBMDecodeError::LimitExceeded(msg) => {
+                write!(f, "{}", msg)
+            }
+        }
+    }
+}
+
+impl Error for PBMDecodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PBMDecodeError::Io(e) => Some(e),
+            PBMDecodeError::Header(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PBMDecodeError {
+    fn from(e: io::Error) -> Self {
+        PBMDecodeError::Io(e)
+    }
+}
+
+impl From<RasterError> for PBMDecodeError {
+    fn from(e: RasterError) -> Self {
+        match e {
+            RasterError::Io(e) => PBMDecodeError::Io(e),
+            RasterError::TruncatedPixelData(msg) => PBMDecodeError::TruncatedPixelData(msg),
+            RasterError::InvalidPixelData(msg) => PBMDecodeError::InvalidPixelData(msg),
+        }
+    }
+}
+
+impl From<HeaderError> for PBMDecodeError {
+    fn from(e: HeaderError) -> Self {
+        match e {
+            HeaderError::Io(e) => PBMDecodeError::Io(e),
+            e => PBMDecodeError::Header(e),
+        }
+    }
+}
+
+/// Decodes the bytes of a binary (P4) or plain (P1) encoded .pbm file into a Bitmap. The default
+/// Limits apply.
+pub fn decode(bytes: &[u8]) -> Result<Bitmap, PBMDecodeError> {
+    let mut reader = bytes;
+    Ok(read_with_header(&mut reader, &Limits::default())?.0)
+}
+
+fn read_with_header<R: Read>(
+    reader: &mut R,
+    limits: &Limits,
+) -> Result<(Bitmap, PnmHeader), PBMDecodeError> {
+    let header = PnmHeader::read_from(reader)?;
+
+    let PnmHeader {
+        magic,
+        width: cols,
+        height: rows,
+        ..
+    } = header;
+
+    // Checked before the raster is read, as raw rows of zero columns take up zero bytes
+    if rows == 0 || cols == 0 {
+        return Err(PBMDecodeError::MalformedHeader(String::from(
+            if rows == 0 {
+                "Rows can't be zero."
+            } else {
+                "Columns can't be zero."
+            },
+        )));
+    }
+
+    limits
+        .check_dimensions(cols, rows)
+        .map_err(PBMDecodeError::LimitExceeded)?;
+    if magic == MagicNumber::P4 {
+        limits
+            .check_raster(cols, rows, rows.checked_mul(cols.div_ceil(8)))
+            .map_err(PBMDecodeError::LimitExceeded)?;
+    }
+
+    let data = match magic {
+        MagicNumber::P4 => {
+            expected_bits(rows, cols)?;
+            raw_bits(reader, rows, cols)?
+        }
+        MagicNumber::P1 => plain_bits(reader, expected_bits(rows, cols)?)?,
+        magic => {
+            return Err(PBMDecodeError::InvalidMagicNumber(format!(
+                "Expected magic number 'P4' or 'P1', but found '{}'.",
+                magic
+            )));
+        }
+    };
+
+    let image = Bitmap::builder()
+        .rows(rows)
+        .cols(cols)
+        .data(data)
+        .build()
+        .map_err(|e| PBMDecodeError::MalformedHeader(e.to_string()))?;
+
+    Ok((image, header))
+}
+
+fn expected_bits(rows: usize, cols: usize) -> Result<usize, PBMDecodeError> {
+    raster::sample_count(rows, cols, 1).ok_or_else(|| {
+        PBMDecodeError::MalformedHeader(format!(
+            "Image dimensions {} by {} are too large.",
+            cols, rows
+        ))
+    })
+}
+
+/// Unpacks rows of bits, ignoring the padding at the end of each row.
+fn raw_bits<R: Read>(reader: &mut R, rows: usize, cols: usize) -> Result<Vec<bool>, RasterError> {
+    let bytes_per_row = cols.div_ceil(8);
+    // Can't overflow, as there are fewer bytes per row than columns
+    let bytes = raster::read_bytes(reader, rows * bytes_per_row)?;
+
+    Ok(bytes
+        .chunks_exact(bytes_per_row)
+        .flat_map(|row| (0..cols).map(move |col| (row[col / 8] >> (7 - col % 8)) & 1 == 1))
+        .collect())
+}
+
+/// Parses '0' & '1' characters, which may or may not be separated by whitespace or comments.
+fn plain_bits<R: Read>(reader: &mut R, expected: usize) -> Result<Vec<bool>, RasterError> {
+    let mut bytes = ByteReader::new(reader);
+    let mut bits: Vec<bool> = Vec::new();
+
+    while bits.len() < expected {
+        let position = bytes.position();
+        match bytes.next_char()? {
+            None => {
+                return Err(RasterError::TruncatedPixelData(format!(
+                    "Not enough pixel data. Expected {} pixels, but found {}.",
+                    expected,
+                    bits.len()
+                )));
+            }
+            Some(b'0') => bits.push(false),
+            Some(b'1') => bits.push(true),
+            Some(byte) if byte.is_ascii_whitespace() => continue,
+            Some(byte) => return Err(raster::unexpected_character(byte, position)),
+        }
+    }
+
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::{BLACK, WHITE};
+    use crate::coordinate::CircleCoordinates;
+    use crate::graphics::image::Image;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_read_round_trip() -> Result<(), Box<dyn Error>> {
+        // 75 columns means rows need padding & plain lines need wrapping
+        let mut image = Image::builder().rows(40).cols(75).build()?;
+        image
+            .fill(WHITE)
+            .draw_filled_circle(BLACK, CircleCoordinates::new(20, 37, 12))?;
+        let bitmap = Bitmap::threshold(&image, 128);
+
+        for encoding in [Encoding::Raw, Encoding::Plain] {
+            let pbm_image = PBMImage::builder()
+                .image(&bitmap)
+                .filename("test_pbm_read_round_trip.pbm")
+                .encoding(encoding)
+                .build()?;
+            pbm_image.write()?;
+
+            let read_pbm_image = PBMImage::read("test_pbm_read_round_trip.pbm")?;
+            assert_eq!(read_pbm_image, pbm_image);
+            assert_eq!(read_pbm_image.into_bitmap().to_image(), image);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode() -> Result<(), Box<dyn Error>> {
+        let bits = vec![
+            true, false, true, false, false, false, false, false, false, true,
+        ];
+
+        let bitmap = decode(b"P4\n10 1\n\xA0\x40")?;
+        assert_eq!(bitmap.get_data(), &bits);
+
+        let bitmap = decode(b"P1\n10 1\n1010000 # comment\n0 0 1")?;
+        assert_eq!(bitmap.get_data(), &bits);
+
+        // Consecutive images can be read from the same reader
+        let mut reader: &[u8] = b"P4\n10 1\n\xA0\x40P1 10 1 1010000001";
+        let first = PBMImage::read_from(&mut reader)?;
+        let second = PBMImage::read_from(&mut reader)?;
+        assert_eq!(first.image.get_data(), &bits);
+        assert_eq!(second.image.get_data(), &bits);
+        assert!(reader.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!(matches!(
+            decode(b"P5\n1 1\n255\n\x00"),
+            Err(PBMDecodeError::InvalidMagicNumber(_))
+        ));
+        assert!(matches!(
+            decode(b"P1\n2 1\n0 2\n"),
+            Err(PBMDecodeError::InvalidPixelData(_))
+        ));
+        assert!(matches!(
+            decode(b"P4\n9 2\n\x00\x00\x00"),
+            Err(PBMDecodeError::TruncatedPixelData(_))
+        ));
+
+        // Zero sized images are rejected before the raster is read
+        for bytes in [&b"P4\n0 1\n"[..], b"P4\n1 0\n", b"P1\n0 1\n"] {
+            assert!(matches!(
+                decode(bytes),
+                Err(PBMDecodeError::MalformedHeader(_))
+            ));
+        }
+
+        // Limits are checked from the header alone, before any pixel data
+        assert!(matches!(
+            decode(b"P4\n100000 100000\n"),
+            Err(PBMDecodeError::LimitExceeded(_))
+        ));
+        let limits = *Limits::new().max_width(8);
+        assert!(matches!(
+            PBMImage::read_from_with_limits(&mut &b"P1\n9 1\n"[..], &limits),
+            Err(PBMDecodeError::LimitExceeded(_))
+        ));
+    }
+
+    fn arbitrary_bitmap() -> impl Strategy<Value = Bitmap> {
+        (1usize..24, 1usize..24).prop_flat_map(|(rows, cols)| {
+            proptest::collection::vec(any::<bool>(), rows * cols).prop_map(move |data| {
+                Bitmap::builder()
+                    .rows(rows)
+                    .cols(cols)
+                    .data(data)
+                    .build()
+                    .unwrap()
+            })
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip_property(
+            bitmap in arbitrary_bitmap(),
+            encoding in prop_oneof![Just(Encoding::Raw), Just(Encoding::Plain)],
+        ) {
+            let mut bytes: Vec<u8> = Vec::new();
+            PBMImage::builder()
+                .image(&bitmap)
+                .filename("unused.pbm")
+                .encoding(encoding)
+                .build()
+                .unwrap()
+                .write_to(&mut bytes)
+                .unwrap();
+
+            prop_assert_eq!(decode(&bytes).unwrap(), bitmap);
+        }
+
+        #[test]
+        fn test_decode_never_panics(
+            magic in prop_oneof![Just("P4"), Just("P1")],
+            cols in 0usize..4,
+            rows in 0usize..4,
+            rest in proptest::collection::vec(any::<u8>(), 0..64),
+        ) {
+            let header = format!("{}\n{} {}\n", magic, cols, rows);
+            let _ = decode(&[header.as_bytes(), &rest].concat());
+            let _ = decode(&[magic.as_bytes(), &rest].concat());
+        }
+    }
+}
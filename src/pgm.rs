@@ -0,0 +1,640 @@
+use crate::graphics::image::{Image, ImageBuilder};
+use crate::graphics::pixel::{rescale, Luma16, Luma8};
+use crate::header::{HeaderError, MagicNumber, PnmHeader};
+use crate::limits::Limits;
+use crate::raster::{self, RasterError};
+use std::error::Error;
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::io::{BufReader, Read, Write};
+
+pub use crate::header::Encoding;
+
+/// Magic number of a .pgm file with the given encoding
+fn magic_number(encoding: Encoding) -> MagicNumber {
+    match encoding {
+        Encoding::Raw => MagicNumber::P5,
+        Encoding::Plain => MagicNumber::P2,
+    }
+}
+
+/// Pixel storage backing a PGMImage, at either 8 or 16 bits per sample.
+#[derive(Debug, PartialEq, Clone)]
+enum Raster {
+    Gray8(Image<Luma8>),
+    Gray16(Image<Luma16>),
+}
+
+impl Raster {
+    fn get_rows(&self) -> usize {
+        match self {
+            Raster::Gray8(image) => *image.get_rows(),
+            Raster::Gray16(image) => *image.get_rows(),
+        }
+    }
+
+    fn get_cols(&self) -> usize {
+        match self {
+            Raster::Gray8(image) => *image.get_cols(),
+            Raster::Gray16(image) => *image.get_cols(),
+        }
+    }
+
+    /// The largest value a sample can take before it is scaled to a maxval.
+    fn native_maxval(&self) -> u16 {
+        match self {
+            Raster::Gray8(_) => 255,
+            Raster::Gray16(_) => 65535,
+        }
+    }
+
+    /// All samples of the raster, scaled from the native maxval to `maxval`.
+    fn samples(&self, maxval: u16) -> Vec<u16> {
+        let native_maxval = self.native_maxval();
+        match self {
+            Raster::Gray8(image) => image
+                .get_data()
+                .iter()
+                .map(|gray| rescale(gray.0 as u16, native_maxval, maxval))
+                .collect(),
+            Raster::Gray16(image) => image
+                .get_data()
+                .iter()
+                .map(|gray| rescale(gray.0, native_maxval, maxval))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PGMImage {
+    raster: Raster,
+    header: Vec<u8>,
+    encoding: Encoding,
+    maxval: u16,
+    pub filename: String,
+}
+
+impl PGMImage {
+    pub fn builder() -> PGMImageBuilder {
+        PGMImageBuilder::new()
+    }
+
+    /// Reads a binary (P5) or plain (P2) encoded .pgm file with any maxval
+    ///
+    /// Files with a maxval of up to 255 are stored with 8 bits per sample, larger maxvals, such as
+    /// those of depth maps, with 16 bits per sample. Samples are rescaled to the full range of
+    /// the storage.
+    ///
+    /// # Errors
+    ///
+    /// Will return a PGMDecodeError if the file can't be read, the header is malformed or
+    /// exceeds the default Limits, or the file ends before all of the pixel data declared in the
+    /// header has been read.
+    pub fn read(filename: &str) -> Result<PGMImage, PGMDecodeError> {
+        let mut reader = BufReader::new(fs::File::open(filename)?);
+        let mut pgm_image = PGMImage::read_from(&mut reader)?;
+        pgm_image.filename = filename.to_string();
+        Ok(pgm_image)
+    }
+
+    /// Reads a single binary (P5) or plain (P2) encoded image from `reader`
+    ///
+    /// Only the bytes of the image are consumed, leaving anything after it in the reader. The
+    /// header is read one byte at a time, so unbuffered readers should be wrapped in a
+    /// BufReader. The filename of the returned PGMImage is empty.
+    ///
+    /// # Errors
+    ///
+    /// See PGMImage::read
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<PGMImage, PGMDecodeError> {
+        PGMImage::read_from_with_limits(reader, &Limits::default())
+    }
+
+    /// Reads a single image from `reader` as PGMImage::read_from does, but with the given limits
+    /// instead of the defaults
+    ///
+    /// # Errors
+    ///
+    /// See PGMImage::read
+    pub fn read_from_with_limits<R: Read>(
+        reader: &mut R,
+        limits: &Limits,
+    ) -> Result<PGMImage, PGMDecodeError> {
+        let (raster, header) = read_with_header(reader, limits)?;
+
+        Ok(PGMImage {
+            raster,
+            // Keep the header in the same form that PGMImageBuilder::build produces
+            header: header.encode(&[]),
+            encoding: header.magic.encoding(),
+            maxval: header.maxval,
+            filename: String::new(),
+        })
+    }
+
+    /// Converts into an Image<Luma8>, rounding samples to 8 bits if they were stored with 16
+    /// bits.
+    pub fn into_gray_image(self) -> Image<Luma8> {
+        match self.raster {
+            Raster::Gray8(image) => image,
+            Raster::Gray16(image) => image.convert(),
+        }
+    }
+
+    /// Converts into an Image<Luma16>, widening samples to 16 bits if they were stored with 8
+    /// bits.
+    pub fn into_gray_image16(self) -> Image<Luma16> {
+        match self.raster {
+            Raster::Gray8(image) => image.convert(),
+            Raster::Gray16(image) => image,
+        }
+    }
+
+    pub fn get_encoding(&self) -> &Encoding {
+        &self.encoding
+    }
+
+    pub fn get_maxval(&self) -> &u16 {
+        &self.maxval
+    }
+
+    /// Writes an image to a .pgm file
+    ///
+    /// Samples are rescaled to the maxval & written with one byte each when the maxval is less
+    /// than 256, otherwise with two bytes each, most significant byte first.
+    pub fn write(&self) -> Result<(), Box<dyn Error>> {
+        raster::write_file(&self.filename, || Ok(self.to_bytes()))
+    }
+
+    /// Writes the image to `writer`, in the same form as PGMImage::write
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if writing to `writer` fails.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), Box<dyn Error>> {
+        writer.write_all(&self.to_bytes())?;
+        Ok(())
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer: Vec<u8> = Vec::new();
+
+        // Push header data into write buffer
+        self.header.iter().for_each(|byte| buffer.push(*byte));
+
+        // Push pixel data into write buffer
+        let samples = self.raster.samples(self.maxval);
+        raster::encode(&samples, self.maxval, self.encoding, &mut buffer);
+
+        buffer
+    }
+} /* PGMImage */
+
+#[derive(Default, Clone)]
+pub struct PGMImageBuilder {
+    raster: Option<Raster>,
+    filename: Option<String>,
+    encoding: Encoding,
+    maxval: Option<u16>,
+    comments: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum PGMImageBuilderError {
+    ImageNotProvided(String),
+    FilenameNotProvided(String),
+    InvalidMaxval(String),
+}
+
+impl Display for PGMImageBuilderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PGMImageBuilderError::ImageNotProvided(msg) => {
+                write!(f, "{}", msg)
+            }
+            PGMImageBuilderError::FilenameNotProvided(msg) => {
+                write!(f, "{}", msg)
+            }
+            PGMImageBuilderError::InvalidMaxval(msg) => {
+                write!(f, "{}", msg)
+            }
+        }
+    }
+}
+
+impl Error for PGMImageBuilderError {}
+
+impl PGMImageBuilder {
+    pub fn new() -> Self {
+        PGMImageBuilder {
+            raster: None,
+            filename: None,
+            encoding: Encoding::Raw,
+            maxval: None,
+            comments: Vec::new(),
+        }
+    }
+
+    pub fn image(&mut self, image: &Image<Luma8>) -> &mut Self {
+        self.raster = Some(Raster::Gray8(image.clone()));
+        self
+    }
+
+    pub fn image16(&mut self, image: &Image<Luma16>) -> &mut Self {
+        self.raster = Some(Raster::Gray16(image.clone()));
+        self
+    }
+
+    pub fn filename(&mut self, filename: &str) -> &mut Self {
+        self.filename = Some(filename.to_string());
+        self
+    }
+
+    /// Selects between binary (P5, the default) and plain (P2) encoding.
+    pub fn encoding(&mut self, encoding: Encoding) -> &mut Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Sets the maxval written to the header, between 1 and 65535. Defaults to 255 for an
+    /// Image<Luma8> & 65535 for an Image<Luma16>.
+    pub fn maxval(&mut self, maxval: u16) -> &mut Self {
+        self.maxval = Some(maxval);
+        self
+    }
+
+    /// Adds a comment to the header. Comments are written in the order they are added.
+    pub fn comment(&mut self, comment: &str) -> &mut Self {
+        self.comments.push(comment.to_string());
+        self
+    }
+
+    pub fn build(&mut self) -> Result<PGMImage, PGMImageBuilderError> {
+        let raster = match &self.raster {
+            None => {
+                return Err(PGMImageBuilderError::ImageNotProvided(String::from(
+                    "Image must be provided to build a PGMImage.",
+                )));
+            }
+            Some(raster) => raster,
+        };
+
+        let filename = match &self.filename {
+            None => {
+                return Err(PGMImageBuilderError::FilenameNotProvided(String::from(
+                    "Filename must be provided to build a PGMImage.",
+                )));
+            }
+            Some(filename) => filename,
+        };
+
+        let maxval = match self.maxval {
+            Some(0) => {
+                return Err(PGMImageBuilderError::InvalidMaxval(String::from(
+                    "Maxval must be between 1 and 65535.",
+                )));
+            }
+            Some(maxval) => maxval,
+            None => raster.native_maxval(),
+        };
+
+        let header = PnmHeader::new(
+            magic_number(self.encoding),
+            raster.get_cols(),
+            raster.get_rows(),
+            maxval,
+        );
+
+        Ok(PGMImage {
+            raster: raster.clone(),
+            header: header.encode(&self.comments),
+            encoding: self.encoding,
+            maxval,
+            filename: filename.clone(),
+        })
+    }
+}
+
+impl Image<Luma8> {
+    /// Reads a binary (P5) or plain (P2) encoded .pgm file into an Image<Luma8>
+    ///
+    /// # Errors
+    ///
+    /// See PGMImage::read
+    pub fn from_pgm(filename: &str) -> Result<Image<Luma8>, PGMDecodeError> {
+        Ok(PGMImage::read(filename)?.into_gray_image())
+    }
+}
+
+#[derive(Debug)]
+pub enum PGMDecodeError {
+    Io(io::Error),
+    Header(HeaderError),
+    InvalidMagicNumber(String),
+    MalformedHeader(String),
+    TruncatedPixelData(String),
+    InvalidPixelData(String),
+    LimitExceeded(String),
+}
+
+impl Display for PGMDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PGMDecodeError::Io(e) => {
+                write!(f, "{}", e)
+            }
+            PGMDecodeError::Header(e) => {
+                write!(f, "{}", e)
+            }
+            PGMDecodeError::InvalidMagicNumber(msg) => {
+                write!(f, "{}", msg)
+            }
+            PGMDecodeError::MalformedHeader(msg) => {
+                write!(f, "{}", msg)
+            }
+            PGMDecodeError::TruncatedPixelData(msg) => {
+                write!(f, "{}", msg)
+            }
+            PGMDecodeError::InvalidPixelData(msg) => {
+                write!(f, "{}", msg)
+            }
+            PGMDecodeError::LimitExceeded(msg) => {
+                write!(f, "{}", msg)
+            }
+        }
+    }
+}
+
+impl Error for PGMDecodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PGMDecodeError::Io(e) => Some(e),
+            PGMDecodeError::Header(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PGMDecodeError {
+    fn from(e: io::Error) -> Self {
+        PGMDecodeError::Io(e)
+    }
+}
+
+impl From<RasterError> for PGMDecodeError {
+    fn from(e: RasterError) -> Self {
+        match e {
+            RasterError::Io(e) => PGMDecodeError::Io(e),
+            RasterError::TruncatedPixelData(msg) => PGMDecodeError::TruncatedPixelData(msg),
+            RasterError::InvalidPixelData(msg) => PGMDecodeError::InvalidPixelData(msg),
+        }
+    }
+}
+
+impl From<HeaderError> for PGMDecodeError {
+    fn from(e: HeaderError) -> Self {
+        match e {
+            HeaderError::Io(e) => PGMDecodeError::Io(e),
+            e => PGMDecodeError::Header(e),
+        }
+    }
+}
+
+/// Decodes the bytes of a binary (P5) or plain (P2) encoded .pgm file into an Image<Luma8>, with
+/// samples rescaled from the maxval of the file to 255. The default Limits apply.
+pub fn decode(bytes: &[u8]) -> Result<Image<Luma8>, PGMDecodeError> {
+    let mut reader = bytes;
+    Ok(match read_with_header(&mut reader, &Limits::default())?.0 {
+        Raster::Gray8(image) => image,
+        Raster::Gray16(image) => image.convert(),
+    })
+}
+
+fn read_with_header<R: Read>(
+    reader: &mut R,
+    limits: &Limits,
+) -> Result<(Raster, PnmHeader), PGMDecodeError> {
+    let header = PnmHeader::read_from(reader)?;
+
+    let PnmHeader {
+        magic,
+        width: cols,
+        height: rows,
+        maxval,
+    } = header;
+
+    if !matches!(magic, MagicNumber::P5 | MagicNumber::P2) {
+        return Err(PGMDecodeError::InvalidMagicNumber(format!(
+            "Expected magic number 'P5' or 'P2', but found '{}'.",
+            magic
+        )));
+    }
+
+    limits
+        .check_dimensions(cols, rows)
+        .map_err(PGMDecodeError::LimitExceeded)?;
+    if magic == MagicNumber::P5 {
+        let bytes_per_sample = if maxval < 256 { 1 } else { 2 };
+        limits
+            .check_raster(
+                cols,
+                rows,
+                raster::sample_count(rows, cols, bytes_per_sample),
+            )
+            .map_err(PGMDecodeError::LimitExceeded)?;
+    }
+
+    let expected = raster::sample_count(rows, cols, 1).ok_or_else(|| {
+        PGMDecodeError::MalformedHeader(format!(
+            "Image dimensions {} by {} are too large.",
+            cols, rows
+        ))
+    })?;
+    let samples = raster::read(reader, expected, maxval, magic.encoding())?;
+
+    let build_error = |e: crate::graphics::image::ImageBuilderError| {
+        PGMDecodeError::MalformedHeader(e.to_string())
+    };
+
+    // Samples are kept at 16 bits when the maxval needs them, so depth maps aren't truncated
+    let raster = if maxval < 256 {
+        Raster::Gray8(
+            ImageBuilder::<Luma8>::new()
+                .rows(rows)
+                .cols(cols)
+                .data(
+                    samples
+                        .iter()
+                        .map(|sample| Luma8(rescale(*sample, maxval, 255) as u8))
+                        .collect(),
+                )
+                .build()
+                .map_err(build_error)?,
+        )
+    } else {
+        Raster::Gray16(
+            ImageBuilder::<Luma16>::new()
+                .rows(rows)
+                .cols(cols)
+                .data(
+                    samples
+                        .iter()
+                        .map(|sample| Luma16(rescale(*sample, maxval, 65535)))
+                        .collect(),
+                )
+                .build()
+                .map_err(build_error)?,
+        )
+    };
+
+    Ok((raster, header))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_round_trip() -> Result<(), Box<dyn Error>> {
+        let data: Vec<Luma8> = (0..=255).map(Luma8).collect();
+        let image = ImageBuilder::<Luma8>::new()
+            .rows(16)
+            .cols(16)
+            .data(data)
+            .build()?;
+
+        for encoding in [Encoding::Raw, Encoding::Plain] {
+            let pgm_image = PGMImage::builder()
+                .image(&image)
+                .filename("test_pgm_read_round_trip.pgm")
+                .encoding(encoding)
+                .build()?;
+            pgm_image.write()?;
+
+            let read_pgm_image = PGMImage::read("test_pgm_read_round_trip.pgm")?;
+            assert_eq!(read_pgm_image, pgm_image);
+        }
+
+        // 8 bit images written with a 16-bit maxval are rescaled back to 255 on read
+        PGMImage::builder()
+            .image(&image)
+            .filename("test_pgm_read_round_trip.pgm")
+            .maxval(65535)
+            .build()?
+            .write()?;
+
+        let bytes = fs::read("test_pgm_read_round_trip.pgm")?;
+        assert_eq!(bytes.len(), b"P5\n16 16\n65535\n".len() + 16 * 16 * 2);
+        assert_eq!(
+            Image::<Luma8>::from_pgm("test_pgm_read_round_trip.pgm")?,
+            image
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_to_and_read_from() -> Result<(), Box<dyn Error>> {
+        let data: Vec<Luma8> = (0..15).map(|i| Luma8(i * 17)).collect();
+        let image = ImageBuilder::<Luma8>::new()
+            .rows(3)
+            .cols(5)
+            .data(data)
+            .build()?;
+
+        for encoding in [Encoding::Raw, Encoding::Plain] {
+            let mut buffer: Vec<u8> = Vec::new();
+            PGMImage::builder()
+                .image(&image)
+                .filename("unused.pgm")
+                .encoding(encoding)
+                .build()?
+                .write_to(&mut buffer)?;
+            buffer.extend_from_slice(b"trailing");
+
+            let mut reader = io::Cursor::new(buffer);
+            let pgm_image = PGMImage::read_from(&mut reader)?;
+            assert_eq!(pgm_image.filename, "");
+            assert_eq!(pgm_image.into_gray_image(), image);
+
+            // Only the image should have been consumed
+            let mut rest = Vec::new();
+            reader.read_to_end(&mut rest)?;
+            assert_eq!(rest, b"trailing");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_16_bit_round_trip() -> Result<(), Box<dyn Error>> {
+        // A depth map using every bit of its samples
+        let data: Vec<Luma16> = (0..12u16).map(|i| Luma16(i * 5000 + i)).collect();
+        let depth_map = ImageBuilder::<Luma16>::new()
+            .rows(3)
+            .cols(4)
+            .data(data)
+            .build()?;
+
+        for encoding in [Encoding::Raw, Encoding::Plain] {
+            let pgm_image = PGMImage::builder()
+                .image16(&depth_map)
+                .filename("test_pgm_16_bit_round_trip.pgm")
+                .encoding(encoding)
+                .build()?;
+            pgm_image.write()?;
+
+            let read_pgm_image = PGMImage::read("test_pgm_16_bit_round_trip.pgm")?;
+            assert_eq!(read_pgm_image, pgm_image);
+            assert_eq!(read_pgm_image.into_gray_image16(), depth_map);
+        }
+
+        let bytes = fs::read("test_pgm_16_bit_round_trip.pgm")?;
+        assert!(bytes.starts_with(b"P2\n4 3\n65535\n0 5001 10002"));
+
+        // Other maxvals above 255 are rescaled to the full 16 bits
+        let mut bytes: &[u8] = b"P5\n2 1\n1000\n\x01\xF4\x03\xE8";
+        assert_eq!(
+            PGMImage::read_from(&mut bytes)?
+                .into_gray_image16()
+                .get_data(),
+            &vec![Luma16(32768), Luma16(65535)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!(matches!(
+            decode(b"P6\n1 1\n255\n\x00\x00\x00"),
+            Err(PGMDecodeError::InvalidMagicNumber(_))
+        ));
+        assert!(matches!(
+            decode(b"P2\n2 1\n15\n0 16\n"),
+            Err(PGMDecodeError::InvalidPixelData(_))
+        ));
+        assert!(matches!(
+            decode(b"P5\n2 2\n255\n\x00\x00\x00"),
+            Err(PGMDecodeError::TruncatedPixelData(_))
+        ));
+
+        // Limits are checked from the header alone, before any pixel data
+        assert!(matches!(
+            decode(b"P5\n100000 100000\n255\n"),
+            Err(PGMDecodeError::LimitExceeded(_))
+        ));
+        let limits = *Limits::new().max_height(1);
+        assert!(matches!(
+            PGMImage::read_from_with_limits(&mut &b"P2\n1 2\n255\n"[..], &limits),
+            Err(PGMDecodeError::LimitExceeded(_))
+        ));
+        let limits = *Limits::new().max_bytes(3);
+        assert!(matches!(
+            PGMImage::read_from_with_limits(&mut &b"P5\n2 1\n65535\n"[..], &limits),
+            Err(PGMDecodeError::LimitExceeded(_))
+        ));
+    }
+}
@@ -1,14 +1,84 @@
-use crate::graphics::image::Image;
+use crate::colors::{Color, WHITE};
+use crate::coordinate::Coordinate;
+use crate::graphics::image::{ColorMode, Image, ImageBuilder};
+use crate::graphics::pixel::{rescale, Rgb16};
+use crate::header::{HeaderError, MagicNumber, PnmHeader};
+use crate::limits::Limits;
+use crate::raster::{self, RasterError};
 use crate::validate;
+use std::borrow::Cow;
 use std::error::Error;
 use std::fmt::Display;
 use std::fs;
-use std::io::Write;
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+
+pub use crate::header::Encoding;
+
+/// Magic number of a .ppm file with the given encoding
+fn magic_number(encoding: Encoding) -> MagicNumber {
+    match encoding {
+        Encoding::Raw => MagicNumber::P6,
+        Encoding::Plain => MagicNumber::P3,
+    }
+}
+
+/// Pixel storage backing a PPMImage, at either 8 or 16 bits per channel.
+#[derive(Debug, PartialEq, Clone)]
+enum Raster {
+    Rgb8(Image),
+    Rgb16(Image<Rgb16>),
+}
+
+impl Raster {
+    fn get_rows(&self) -> usize {
+        match self {
+            Raster::Rgb8(image) => *image.get_rows(),
+            Raster::Rgb16(image) => *image.get_rows(),
+        }
+    }
+
+    fn get_cols(&self) -> usize {
+        match self {
+            Raster::Rgb8(image) => *image.get_cols(),
+            Raster::Rgb16(image) => *image.get_cols(),
+        }
+    }
+
+    /// The largest value a sample can take before it is scaled to a maxval.
+    fn native_maxval(&self) -> u16 {
+        match self {
+            Raster::Rgb8(_) => 255,
+            Raster::Rgb16(_) => 65535,
+        }
+    }
+
+    /// All samples of the raster in RGB order, scaled from the native maxval to `maxval`.
+    fn samples(&self, maxval: u16) -> Vec<u16> {
+        let native_maxval = self.native_maxval();
+        match self {
+            Raster::Rgb8(image) => image
+                .get_data()
+                .iter()
+                .flat_map(|pixel| samples(*pixel))
+                .map(|sample| rescale(sample as u16, native_maxval, maxval))
+                .collect(),
+            Raster::Rgb16(image) => image
+                .get_data()
+                .iter()
+                .flat_map(|pixel| pixel.0)
+                .map(|sample| rescale(sample, native_maxval, maxval))
+                .collect(),
+        }
+    }
+}
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct PPMImage {
-    image: Image,
+    raster: Raster,
     header: Vec<u8>,
+    encoding: Encoding,
+    maxval: u16,
     pub filename: String,
 }
 
@@ -17,41 +87,154 @@ impl PPMImage {
         PPMImageBuilder::new()
     }
 
+    /// Reads a binary (P6) or plain (P3) encoded .ppm file with any maxval
+    ///
+    /// Files with a maxval of up to 255 are stored with 8 bits per channel, larger maxvals with
+    /// 16 bits per channel. Samples are rescaled to the full range of the storage.
+    ///
+    /// # Errors
+    ///
+    /// Will return a PPMDecodeError if the file can't be read, the header is malformed or
+    /// exceeds the default Limits, or the file ends before all of the pixel data declared in the
+    /// header has been read.
+    pub fn read(filename: &str) -> Result<PPMImage, PPMDecodeError> {
+        let mut reader = BufReader::new(fs::File::open(filename)?);
+        let mut ppm_image = PPMImage::read_from(&mut reader)?;
+        ppm_image.filename = filename.to_string();
+        Ok(ppm_image)
+    }
+
+    /// Reads a single binary (P6) or plain (P3) encoded image from `reader`
+    ///
+    /// Only the bytes of the image are consumed, leaving anything after it in the reader. The
+    /// header is read one byte at a time, so unbuffered readers should be wrapped in a
+    /// BufReader. The filename of the returned PPMImage is empty.
+    ///
+    /// # Errors
+    ///
+    /// See PPMImage::read
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<PPMImage, PPMDecodeError> {
+        PPMImage::read_from_with_limits(reader, &Limits::default())
+    }
+
+    /// Reads a single image from `reader` as PPMImage::read_from does, but with the given limits
+    /// instead of the defaults
+    ///
+    /// # Errors
+    ///
+    /// See PPMImage::read
+    pub fn read_from_with_limits<R: Read>(
+        reader: &mut R,
+        limits: &Limits,
+    ) -> Result<PPMImage, PPMDecodeError> {
+        let (raster, header) = read_raster(reader, limits)?;
+
+        Ok(PPMImage {
+            raster,
+            // Keep the header in the same form that PPMImageBuilder::build produces
+            header: header.encode(&[]),
+            encoding: header.magic.encoding(),
+            maxval: header.maxval,
+            filename: String::new(),
+        })
+    }
+
+    /// Borrows the image, or converts a copy of it as PPMImage::into_image does if samples were
+    /// stored with 16 bits.
+    #[deprecated(note = "use PPMImage::into_image, or PPMImage::into_image16 for 16-bit samples")]
+    pub fn get_image(&self) -> Cow<'_, Image> {
+        match &self.raster {
+            Raster::Rgb8(image) => Cow::Borrowed(image),
+            Raster::Rgb16(image) => Cow::Owned(image.convert()),
+        }
+    }
+
+    /// Converts into an Image, rounding samples to 8 bits if they were stored with 16 bits.
+    pub fn into_image(self) -> Image {
+        match self.raster {
+            Raster::Rgb8(image) => image,
+            Raster::Rgb16(image) => image.convert(),
+        }
+    }
+
+    /// Converts into an Image<Rgb16>, widening samples to 16 bits if they were stored with 8
+    /// bits.
+    pub fn into_image16(self) -> Image<Rgb16> {
+        match self.raster {
+            Raster::Rgb8(image) => image.convert(),
+            Raster::Rgb16(image) => image,
+        }
+    }
+
+    pub fn get_encoding(&self) -> &Encoding {
+        &self.encoding
+    }
+
+    pub fn get_maxval(&self) -> &u16 {
+        &self.maxval
+    }
+
     /// Writes an Image to a .ppm file
+    ///
+    /// Samples are rescaled to the maxval & written with one byte each when the maxval is less
+    /// than 256, otherwise with two bytes each, most significant byte first.
     pub fn write(&self) -> Result<(), Box<dyn Error>> {
-        if let Err(e) = validate::pixel_data_length(&self.image) {
-            return Err(Box::new(e));
+        raster::write_file(&self.filename, || self.to_bytes())
+    }
+
+    /// Writes the image to `writer`, in the same form as PPMImage::write
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the image doesn't have the amount of pixel data its dimensions
+    /// require, or if writing to `writer` fails.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), Box<dyn Error>> {
+        writer.write_all(&self.to_bytes()?)?;
+        Ok(())
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        if let Raster::Rgb8(image) = &self.raster {
+            validate::pixel_data_length(image)?;
         }
 
-        let mut fh = fs::File::create(&self.filename)?;
         let mut buffer: Vec<u8> = Vec::new();
 
         // Push header data into write buffer
         self.header.iter().for_each(|byte| buffer.push(*byte));
 
         // Push pixel data into write buffer
-        for pixel in self.image.get_data().iter() {
-            // RGB - 0x00_RR_GG_BB
-            buffer.push(((pixel >> 8 * 2) & 0xFF) as u8);
-            buffer.push(((pixel >> 8 * 1) & 0xFF) as u8);
-            buffer.push(((pixel >> 8 * 0) & 0xFF) as u8);
-        }
+        let samples = self.raster.samples(self.maxval);
+        raster::encode(&samples, self.maxval, self.encoding, &mut buffer);
 
-        let _ = fh.write(&buffer);
-        Ok(())
+        Ok(buffer)
     }
 } /* PPMImage */
 
+/// Splits a pixel into its RGB samples - 0x00_RR_GG_BB
+fn samples(pixel: u32) -> [u8; 3] {
+    [
+        ((pixel >> 16) & 0xFF) as u8,
+        ((pixel >> 8) & 0xFF) as u8,
+        (pixel & 0xFF) as u8,
+    ]
+}
+
 #[derive(Default, Clone)]
 pub struct PPMImageBuilder {
-    image: Option<Image>,
+    raster: Option<Raster>,
     filename: Option<String>,
+    encoding: Encoding,
+    maxval: Option<u16>,
+    background: Color,
+    comments: Vec<String>,
 }
 
 #[derive(Debug)]
 pub enum PPMImageBuilderError {
     ImageNotProvided(String),
     FilenameNotProvided(String),
+    InvalidMaxval(String),
 }
 
 impl Display for PPMImageBuilderError {
@@ -63,6 +246,9 @@ impl Display for PPMImageBuilderError {
             PPMImageBuilderError::FilenameNotProvided(msg) => {
                 write!(f, "{}", msg)
             }
+            PPMImageBuilderError::InvalidMaxval(msg) => {
+                write!(f, "{}", msg)
+            }
         }
     }
 }
@@ -72,13 +258,22 @@ impl Error for PPMImageBuilderError {}
 impl PPMImageBuilder {
     pub fn new() -> Self {
         PPMImageBuilder {
-            image: None,
+            raster: None,
             filename: None,
+            encoding: Encoding::Raw,
+            maxval: None,
+            background: WHITE,
+            comments: Vec::new(),
         }
     }
 
     pub fn image(&mut self, image: &Image) -> &mut Self {
-        self.image = Some(image.clone());
+        self.raster = Some(Raster::Rgb8(image.clone()));
+        self
+    }
+
+    pub fn image16(&mut self, image: &Image<Rgb16>) -> &mut Self {
+        self.raster = Some(Raster::Rgb16(image.clone()));
         self
     }
 
@@ -87,14 +282,40 @@ impl PPMImageBuilder {
         self
     }
 
+    /// Selects between binary (P6, the default) and plain (P3) encoding.
+    pub fn encoding(&mut self, encoding: Encoding) -> &mut Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Sets the maxval written to the header, between 1 and 65535. Defaults to 255 for an Image
+    /// & 65535 for an Image<Rgb16>.
+    pub fn maxval(&mut self, maxval: u16) -> &mut Self {
+        self.maxval = Some(maxval);
+        self
+    }
+
+    /// Sets the color that an Image with alpha is flattened against, as PPM has no alpha.
+    /// Defaults to white.
+    pub fn background(&mut self, background: impl Into<Color>) -> &mut Self {
+        self.background = background.into();
+        self
+    }
+
+    /// Adds a comment to the header. Comments are written in the order they are added.
+    pub fn comment(&mut self, comment: &str) -> &mut Self {
+        self.comments.push(comment.to_string());
+        self
+    }
+
     pub fn build(&mut self) -> Result<PPMImage, PPMImageBuilderError> {
-        let image = match &self.image {
+        let raster = match &self.raster {
             None => {
                 return Err(PPMImageBuilderError::ImageNotProvided(String::from(
                     "Image must be provided to build a PPMImage.",
                 )));
             }
-            Some(image) => image,
+            Some(raster) => raster,
         };
 
         let filename = match &self.filename {
@@ -106,13 +327,1892 @@ impl PPMImageBuilder {
             Some(filename) => filename,
         };
 
-        let header = format!("P6\n{} {}\n255\n", *image.get_cols(), *image.get_rows());
+        let maxval = match self.maxval {
+            Some(0) => {
+                return Err(PPMImageBuilderError::InvalidMaxval(String::from(
+                    "Maxval must be between 1 and 65535.",
+                )));
+            }
+            Some(maxval) => maxval,
+            None => raster.native_maxval(),
+        };
+
+        let header = PnmHeader::new(
+            magic_number(self.encoding),
+            raster.get_cols(),
+            raster.get_rows(),
+            maxval,
+        );
+
+        let raster = match raster {
+            Raster::Rgb8(image) if *image.get_color_mode() != ColorMode::Rgb => {
+                Raster::Rgb8(image.flatten(self.background))
+            }
+            // TODO : Do not clone here
+            raster => raster.clone(),
+        };
 
-        // TODO : Do not clone here
         Ok(PPMImage {
-            image: image.clone(),
-            header: header.into(),
+            raster,
+            header: header.encode(&self.comments),
+            encoding: self.encoding,
+            maxval,
             filename: filename.clone(),
         })
     }
 }
+
+impl Image {
+    /// Reads a binary (P6) or plain (P3) encoded .ppm file into an Image
+    ///
+    /// # Errors
+    ///
+    /// See PPMImage::read
+    pub fn from_ppm(filename: &str) -> Result<Image, PPMDecodeError> {
+        Ok(PPMImage::read(filename)?.into_image())
+    }
+}
+
+#[derive(Debug)]
+pub enum PPMDecodeError {
+    Io(io::Error),
+    Header(HeaderError),
+    InvalidMagicNumber(String),
+    MalformedHeader(String),
+    TruncatedPixelData(String),
+    TooMuchPixelData(String),
+    InvalidPixelData(String),
+    LimitExceeded(String),
+}
+
+impl Display for PPMDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PPMDecodeError::Io(e) => {
+                write!(f, "{}", e)
+            }
+            PPMDecodeError::Header(e) => {
+                write!(f, "{}", e)
+            }
+            PPMDecodeError::InvalidMagicNumber(msg) => {
+                write!(f, "{}", msg)
+            }
+            PPMDecodeError::MalformedHeader(msg) => {
+                write!(f, "{}", msg)
+            }
+            PPMDecodeError::TruncatedPixelData(msg) => {
+                write!(f, "{}", msg)
+            }
+            PPMDecodeError::TooMuchPixelData(msg) => {
+                write!(f, "{}", msg)
+            }
+            PPMDecodeError::InvalidPixelData(msg) => {
+                write!(f, "{}", msg)
+            }
+            PPMDecodeError::LimitExceeded(msg) => {
+                write!(f, "{}", msg)
+            }
+        }
+    }
+}
+
+impl Error for PPMDecodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PPMDecodeError::Io(e) => Some(e),
+            PPMDecodeError::Header(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PPMDecodeError {
+    fn from(e: io::Error) -> Self {
+        PPMDecodeError::Io(e)
+    }
+}
+
+impl From<RasterError> for PPMDecodeError {
+    fn from(e: RasterError) -> Self {
+        match e {
+            RasterError::Io(e) => PPMDecodeError::Io(e),
+            RasterError::TruncatedPixelData(msg) => PPMDecodeError::TruncatedPixelData(msg),
+            RasterError::InvalidPixelData(msg) => PPMDecodeError::InvalidPixelData(msg),
+        }
+    }
+}
+
+impl From<HeaderError> for PPMDecodeError {
+    fn from(e: HeaderError) -> Self {
+        match e {
+            HeaderError::Io(e) => PPMDecodeError::Io(e),
+            e => PPMDecodeError::Header(e),
+        }
+    }
+}
+
+/// Decodes the bytes of a binary (P6) or plain (P3) encoded .ppm file into an Image.
+///
+/// Pixels are packed back into the 0x00_RR_GG_BB layout used by PPMImage::write, with samples
+/// rescaled from the maxval of the file to 255. The default Limits apply.
+pub fn decode(bytes: &[u8]) -> Result<Image, PPMDecodeError> {
+    let mut reader = bytes;
+    match read_raster(&mut reader, &Limits::default())?.0 {
+        Raster::Rgb8(image) => Ok(image),
+        Raster::Rgb16(image) => Ok(image.convert()),
+    }
+}
+
+/// Read-only view of a binary (P6) encoded image that borrows its raster, e.g. from a
+/// memory-mapped file, instead of converting it into an Image.
+///
+/// Samples are only checked against the maxval when they are accessed, where larger samples
+/// are clamped to the maxval.
+///
+/// # Example
+///
+/// ```
+/// use libppm::coordinate::Coordinate;
+/// use libppm::ppm::PPMView;
+///
+/// let bytes = b"P6\n2 1\n255\n\xFF\x00\x00\x00\x00\xFF";
+/// let view = PPMView::new(bytes).unwrap();
+/// assert_eq!(view.get_pixel(Coordinate::new(0, 1)), Some(0x00_00_00_FF));
+/// assert_eq!(view.get_row(0), Some(&bytes[11..]));
+/// ```
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct PPMView<'a> {
+    rows: usize,
+    cols: usize,
+    maxval: u16,
+    raster: &'a [u8],
+}
+
+impl<'a> PPMView<'a> {
+    /// Parses the header of a P6 image at the start of `bytes` & borrows its raster
+    ///
+    /// Any bytes following the raster are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Will return a PPMDecodeError if the header is malformed, the image isn't binary encoded,
+    /// as plain rasters can't be accessed without conversion, or `bytes` ends before the whole
+    /// raster.
+    pub fn new(bytes: &'a [u8]) -> Result<PPMView<'a>, PPMDecodeError> {
+        let (header, position) = PnmHeader::parse(bytes)?;
+
+        if header.magic != MagicNumber::P6 {
+            return Err(PPMDecodeError::InvalidMagicNumber(format!(
+                "Expected magic number 'P6', but found '{}'.",
+                header.magic
+            )));
+        }
+
+        let bytes_per_pixel = if header.maxval < 256 { 3 } else { 6 };
+        let length = header
+            .height
+            .checked_mul(header.width)
+            .and_then(|pixels| pixels.checked_mul(bytes_per_pixel))
+            .filter(|length| *length > 0)
+            .ok_or_else(|| {
+                PPMDecodeError::MalformedHeader(format!(
+                    "Invalid image dimensions {} by {}.",
+                    header.width, header.height
+                ))
+            })?;
+
+        let raster = &bytes[position..];
+        if raster.len() < length {
+            return Err(PPMDecodeError::TruncatedPixelData(format!(
+                "Not enough pixel data. Expected {} bytes, but found {}.",
+                length,
+                raster.len()
+            )));
+        }
+
+        Ok(PPMView {
+            rows: header.height,
+            cols: header.width,
+            maxval: header.maxval,
+            raster: &raster[..length],
+        })
+    }
+
+    pub fn get_rows(&self) -> &usize {
+        &self.rows
+    }
+
+    pub fn get_cols(&self) -> &usize {
+        &self.cols
+    }
+
+    pub fn get_maxval(&self) -> &u16 {
+        &self.maxval
+    }
+
+    /// The bytes of the whole raster, without the header.
+    pub fn get_raster(&self) -> &'a [u8] {
+        self.raster
+    }
+
+    fn bytes_per_pixel(&self) -> usize {
+        if self.maxval < 256 {
+            3
+        } else {
+            6
+        }
+    }
+
+    /// The bytes of a single row of the raster, or None if `row` is out of bounds.
+    pub fn get_row(&self, row: usize) -> Option<&'a [u8]> {
+        let row_length = self.cols * self.bytes_per_pixel();
+        row.checked_mul(row_length)
+            .and_then(|start| self.raster.get(start..))
+            .and_then(|raster| raster.get(..row_length))
+    }
+
+    /// Iterates over the bytes of each row of the raster, from top to bottom.
+    pub fn row_slices(&self) -> impl Iterator<Item = &'a [u8]> {
+        self.raster.chunks_exact(self.cols * self.bytes_per_pixel())
+    }
+
+    /// The RGB samples of a pixel as stored in the raster, between 0 & the maxval, or None if
+    /// `coord` is out of bounds.
+    pub fn get_samples(&self, coord: Coordinate) -> Option<[u16; 3]> {
+        if coord.x < 0 || coord.y < 0 || coord.y as usize >= self.cols {
+            return None;
+        }
+
+        let bytes_per_pixel = self.bytes_per_pixel();
+        let pixel = self
+            .get_row(coord.x as usize)?
+            .get(coord.y as usize * bytes_per_pixel..)?;
+
+        let samples = if bytes_per_pixel == 3 {
+            [pixel[0], pixel[1], pixel[2]].map(u16::from)
+        } else {
+            [0, 2, 4].map(|index| u16::from_be_bytes([pixel[index], pixel[index + 1]]))
+        };
+
+        Some(samples.map(|sample| sample.min(self.maxval)))
+    }
+
+    /// A pixel packed as 0x00_RR_GG_BB, rescaled to 255 in the same way as `decode`, or None
+    /// if `coord` is out of bounds.
+    pub fn get_pixel(&self, coord: Coordinate) -> Option<u32> {
+        let [r, g, b] = self
+            .get_samples(coord)?
+            .map(|sample| rescale(sample, self.maxval, 255) as u32);
+        Some((r << 16) | (g << 8) | b)
+    }
+
+    /// Copies the view into an Image, identical to the one `decode` would produce.
+    pub fn to_image(&self) -> Image {
+        let mut data: Vec<u32> = Vec::with_capacity(self.rows * self.cols);
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                let coord = Coordinate::new(row as i32, col as i32);
+                data.push(self.get_pixel(coord).expect("pixel is within the view"));
+            }
+        }
+
+        Image::builder()
+            .rows(self.rows)
+            .cols(self.cols)
+            .data(data)
+            .build()
+            .expect("view dimensions are valid for an Image")
+    }
+}
+
+/// A binary (P6) encoded .ppm file mapped into memory, for cheap access to large files through
+/// a PPMView. Requires the `mmap` feature.
+///
+/// The file must not be modified by this or any other process while it is mapped, otherwise
+/// the contents of the view may change underneath it.
+#[cfg(feature = "mmap")]
+pub struct MappedPPM {
+    mmap: memmap2::Mmap,
+}
+
+#[cfg(feature = "mmap")]
+impl MappedPPM {
+    /// Maps a .ppm file into memory, checking its header as PPMView::new does
+    ///
+    /// # Errors
+    ///
+    /// Will return a PPMDecodeError if the file can't be mapped, or on any error from
+    /// PPMView::new.
+    pub fn open(filename: &str) -> Result<MappedPPM, PPMDecodeError> {
+        let file = fs::File::open(filename)?;
+
+        // SAFETY: the mapping is read-only, & the documented requirement that the file isn't
+        // modified while mapped is left to the caller, as with any memory-mapped file.
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        PPMView::new(&mmap)?;
+
+        Ok(MappedPPM { mmap })
+    }
+
+    pub fn view(&self) -> PPMView<'_> {
+        PPMView::new(&self.mmap).expect("header was checked in MappedPPM::open")
+    }
+}
+
+/// Iterator over the images of a stream of concatenated .ppm images, e.g. a file holding an
+/// animation or the output of `ffmpeg -f image2pipe -c:v ppm`.
+///
+/// Each item is decoded as by `decode`. Whitespace between images is skipped. Iteration ends at
+/// the end of the reader, or after the first error, since the start of the next image can't be
+/// found once an image fails to decode.
+///
+/// # Example
+///
+/// ```
+/// use libppm::ppm::PPMFrames;
+///
+/// let stream: &[u8] = b"P6 1 1 255 \xFF\x00\x00P3 1 1 255 0 0 255\n";
+/// let frames: Vec<_> = PPMFrames::new(stream).collect::<Result<_, _>>().unwrap();
+/// assert_eq!(frames.len(), 2);
+/// ```
+pub struct PPMFrames<R: BufRead> {
+    reader: R,
+    limits: Limits,
+    failed: bool,
+}
+
+impl<R: BufRead> PPMFrames<R> {
+    pub fn new(reader: R) -> Self {
+        PPMFrames {
+            reader,
+            limits: Limits::default(),
+            failed: false,
+        }
+    }
+
+    /// Sets the limits that each frame is checked against. Limits::default() unless set.
+    pub fn limits(&mut self, limits: Limits) -> &mut Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Skips whitespace before the next image, returning false at the end of the reader.
+    fn skip_whitespace(&mut self) -> io::Result<bool> {
+        loop {
+            let buffer = match self.reader.fill_buf() {
+                Ok(buffer) => buffer,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            if buffer.is_empty() {
+                return Ok(false);
+            }
+
+            let whitespace = buffer
+                .iter()
+                .take_while(|byte| byte.is_ascii_whitespace())
+                .count();
+            if whitespace == 0 {
+                return Ok(true);
+            }
+            self.reader.consume(whitespace);
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PPMFrames<R> {
+    type Item = Result<Image, PPMDecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let result = match self.skip_whitespace() {
+            Ok(false) => return None,
+            Ok(true) => {
+                read_raster(&mut self.reader, &self.limits).map(|(raster, _)| match raster {
+                    Raster::Rgb8(image) => image,
+                    Raster::Rgb16(image) => image.convert(),
+                })
+            }
+            Err(e) => Err(PPMDecodeError::Io(e)),
+        };
+
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
+
+/// Writes images one after another to a single stream, e.g. to store an animation in one file
+/// or pipe frames to `ffmpeg -f image2pipe -c:v ppm -i -`.
+///
+/// Frames are written with a maxval of 255 & may differ in size. Frames with alpha are
+/// flattened against the background color, white unless set otherwise.
+pub struct PPMFrameWriter<W: Write> {
+    writer: W,
+    encoding: Encoding,
+    background: Color,
+    frame_count: usize,
+}
+
+impl<W: Write> PPMFrameWriter<W> {
+    pub fn new(writer: W, encoding: Encoding) -> Self {
+        PPMFrameWriter {
+            writer,
+            encoding,
+            background: WHITE,
+            frame_count: 0,
+        }
+    }
+
+    /// Sets the color that frames with alpha are flattened against.
+    pub fn background(&mut self, background: impl Into<Color>) -> &mut Self {
+        self.background = background.into();
+        self
+    }
+
+    /// Appends an image to the stream
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the image doesn't have the amount of pixel data its dimensions
+    /// require, or if writing to the stream fails.
+    pub fn write_frame(&mut self, image: &Image) -> Result<(), Box<dyn Error>> {
+        validate::pixel_data_length(image)?;
+
+        let flattened;
+        let image = if *image.get_color_mode() == ColorMode::Rgb {
+            image
+        } else {
+            flattened = image.flatten(self.background);
+            &flattened
+        };
+
+        let header = PnmHeader::new(
+            magic_number(self.encoding),
+            *image.get_cols(),
+            *image.get_rows(),
+            255,
+        );
+        let mut buffer = header.encode(&[]);
+
+        let samples: Vec<u16> = image
+            .get_data()
+            .iter()
+            .flat_map(|pixel| samples(*pixel))
+            .map(u16::from)
+            .collect();
+        raster::encode(&samples, 255, self.encoding, &mut buffer);
+
+        self.writer.write_all(&buffer)?;
+        self.frame_count += 1;
+        Ok(())
+    }
+
+    pub fn get_frame_count(&self) -> &usize {
+        &self.frame_count
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Writes a single .ppm image a row at a time, so images larger than memory never need to be
+/// materialized as a whole.
+///
+/// The header is written when the writer is built, from the dimensions given up front. Rows are
+/// then pushed one at a time or in bands, & PPMRowWriter::finish checks that every declared
+/// row was written.
+///
+/// Rows are 0x00_RR_GG_BB pixels, so rows with alpha must be flattened first, e.g. with
+/// Image::flatten, or their alpha is dropped.
+///
+/// # Example
+///
+/// ```
+/// use libppm::colors::{BLUE, RED};
+/// use libppm::ppm::PPMRowWriterBuilder;
+///
+/// let mut writer = PPMRowWriterBuilder::new().rows(2).cols(3).build(Vec::new()).unwrap();
+/// writer.write_row(&[RED.to_u32(); 3]).unwrap();
+/// writer.write_row(&[BLUE.to_u32(); 3]).unwrap();
+/// let bytes = writer.finish().unwrap();
+/// assert_eq!(&bytes[..11], b"P6\n3 2\n255\n");
+/// ```
+pub struct PPMRowWriter<W: Write> {
+    writer: W,
+    rows: usize,
+    cols: usize,
+    encoding: Encoding,
+    maxval: u16,
+    rows_written: usize,
+}
+
+#[derive(Default, Clone)]
+pub struct PPMRowWriterBuilder {
+    rows: Option<usize>,
+    cols: Option<usize>,
+    encoding: Encoding,
+    maxval: Option<u16>,
+    comments: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum PPMRowWriterError {
+    Io(io::Error),
+    InvalidDimensions(String),
+    InvalidMaxval(String),
+    RowLengthMismatch(String),
+    TooManyRows(String),
+    NotEnoughRows(String),
+}
+
+impl Display for PPMRowWriterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PPMRowWriterError::Io(e) => {
+                write!(f, "{}", e)
+            }
+            PPMRowWriterError::InvalidDimensions(msg) => {
+                write!(f, "{}", msg)
+            }
+            PPMRowWriterError::InvalidMaxval(msg) => {
+                write!(f, "{}", msg)
+            }
+            PPMRowWriterError::RowLengthMismatch(msg) => {
+                write!(f, "{}", msg)
+            }
+            PPMRowWriterError::TooManyRows(msg) => {
+                write!(f, "{}", msg)
+            }
+            PPMRowWriterError::NotEnoughRows(msg) => {
+                write!(f, "{}", msg)
+            }
+        }
+    }
+}
+
+impl Error for PPMRowWriterError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PPMRowWriterError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PPMRowWriterError {
+    fn from(e: io::Error) -> Self {
+        PPMRowWriterError::Io(e)
+    }
+}
+
+impl PPMRowWriterBuilder {
+    pub fn new() -> Self {
+        PPMRowWriterBuilder {
+            rows: None,
+            cols: None,
+            encoding: Encoding::Raw,
+            maxval: None,
+            comments: Vec::new(),
+        }
+    }
+
+    pub fn rows(&mut self, rows: usize) -> &mut Self {
+        self.rows = Some(rows);
+        self
+    }
+
+    pub fn cols(&mut self, cols: usize) -> &mut Self {
+        self.cols = Some(cols);
+        self
+    }
+
+    /// Selects between binary (P6, the default) and plain (P3) encoding.
+    pub fn encoding(&mut self, encoding: Encoding) -> &mut Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Sets the maxval written to the header, between 1 and 65535. Defaults to 255.
+    pub fn maxval(&mut self, maxval: u16) -> &mut Self {
+        self.maxval = Some(maxval);
+        self
+    }
+
+    /// Adds a comment to the header. Comments are written in the order they are added.
+    pub fn comment(&mut self, comment: &str) -> &mut Self {
+        self.comments.push(comment.to_string());
+        self
+    }
+
+    /// Writes the header to `writer`, returning a PPMRowWriter ready for the first row.
+    pub fn build<W: Write>(&self, mut writer: W) -> Result<PPMRowWriter<W>, PPMRowWriterError> {
+        let (rows, cols) = match (self.rows, self.cols) {
+            (Some(rows), Some(cols))
+                if rows > 0 && cols > 0 && raster::sample_count(rows, cols, 3).is_some() =>
+            {
+                (rows, cols)
+            }
+            _ => {
+                return Err(PPMRowWriterError::InvalidDimensions(String::from(
+                    "Non-zero rows & columns, small enough to address, must be provided to build a PPMRowWriter.",
+                )));
+            }
+        };
+
+        let maxval = match self.maxval {
+            Some(0) => {
+                return Err(PPMRowWriterError::InvalidMaxval(String::from(
+                    "Maxval must be between 1 and 65535.",
+                )));
+            }
+            Some(maxval) => maxval,
+            None => 255,
+        };
+
+        let header = PnmHeader::new(magic_number(self.encoding), cols, rows, maxval);
+        writer.write_all(&header.encode(&self.comments))?;
+
+        Ok(PPMRowWriter {
+            writer,
+            rows,
+            cols,
+            encoding: self.encoding,
+            maxval,
+            rows_written: 0,
+        })
+    }
+}
+
+impl<W: Write> PPMRowWriter<W> {
+    /// Writes a single row of 0x00_RR_GG_BB pixels, ignoring the top byte
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if `row` doesn't have exactly one pixel per column, if every
+    /// declared row has already been written, or if writing fails.
+    pub fn write_row(&mut self, row: &[u32]) -> Result<(), PPMRowWriterError> {
+        if row.len() != self.cols {
+            return Err(PPMRowWriterError::RowLengthMismatch(format!(
+                "Expected a row of {} pixels, but found {}.",
+                self.cols,
+                row.len()
+            )));
+        }
+        self.write_rows(row)
+    }
+
+    /// Writes a band of whole rows, given as consecutive rows of 0x00_RR_GG_BB pixels
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if `band` isn't a whole number of rows, if it holds more rows than
+    /// remain to be written, or if writing fails.
+    pub fn write_rows(&mut self, band: &[u32]) -> Result<(), PPMRowWriterError> {
+        if band.len() % self.cols != 0 {
+            return Err(PPMRowWriterError::RowLengthMismatch(format!(
+                "Expected a multiple of {} pixels, but found {}.",
+                self.cols,
+                band.len()
+            )));
+        }
+
+        let band_rows = band.len() / self.cols;
+        if band_rows > self.rows - self.rows_written {
+            return Err(PPMRowWriterError::TooManyRows(format!(
+                "Too many rows. Expected {}, but found {}.",
+                self.rows,
+                self.rows_written + band_rows
+            )));
+        }
+
+        let samples: Vec<u16> = band
+            .iter()
+            .flat_map(|pixel| samples(*pixel))
+            .map(|sample| rescale(sample as u16, 255, self.maxval))
+            .collect();
+
+        // Encode a row at a time, so plain rows each start on a new line
+        let mut buffer: Vec<u8> = Vec::new();
+        for row in samples.chunks(self.cols * 3) {
+            raster::encode(row, self.maxval, self.encoding, &mut buffer);
+        }
+        self.writer.write_all(&buffer)?;
+
+        self.rows_written += band_rows;
+        Ok(())
+    }
+
+    pub fn get_rows_written(&self) -> &usize {
+        &self.rows_written
+    }
+
+    /// Checks that every declared row was written & flushes the writer, returning it.
+    ///
+    /// # Errors
+    ///
+    /// Will return PPMRowWriterError::NotEnoughRows if fewer rows were written than declared in
+    /// the header, or an error if flushing fails.
+    pub fn finish(mut self) -> Result<W, PPMRowWriterError> {
+        if self.rows_written != self.rows {
+            return Err(PPMRowWriterError::NotEnoughRows(format!(
+                "Not enough rows. Expected {}, but found {}.",
+                self.rows, self.rows_written
+            )));
+        }
+
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+fn read_raster<R: Read>(
+    reader: &mut R,
+    limits: &Limits,
+) -> Result<(Raster, PnmHeader), PPMDecodeError> {
+    let header = PnmHeader::read_from(reader)?;
+    check_magic_number(header.magic)?;
+    check_limits(&header, limits)?;
+
+    let PnmHeader {
+        magic,
+        width: cols,
+        height: rows,
+        maxval,
+    } = header;
+
+    let samples = raster::read(
+        reader,
+        expected_samples(rows, cols)?,
+        maxval,
+        magic.encoding(),
+    )?;
+
+    Ok((build_raster(samples, rows, cols, maxval)?, header))
+}
+
+fn expected_samples(rows: usize, cols: usize) -> Result<usize, PPMDecodeError> {
+    raster::sample_count(rows, cols, 3).ok_or_else(|| {
+        PPMDecodeError::MalformedHeader(format!(
+            "Image dimensions {} by {} are too large.",
+            cols, rows
+        ))
+    })
+}
+
+fn check_magic_number(magic: MagicNumber) -> Result<(), PPMDecodeError> {
+    if !matches!(magic, MagicNumber::P6 | MagicNumber::P3) {
+        return Err(PPMDecodeError::InvalidMagicNumber(format!(
+            "Expected magic number 'P6' or 'P3', but found '{}'.",
+            magic
+        )));
+    }
+    Ok(())
+}
+
+/// Checks the dimensions declared by a header against the limits, before any pixel data is read.
+fn check_limits(header: &PnmHeader, limits: &Limits) -> Result<(), PPMDecodeError> {
+    let PnmHeader {
+        magic,
+        width,
+        height,
+        maxval,
+    } = *header;
+
+    limits
+        .check_dimensions(width, height)
+        .map_err(PPMDecodeError::LimitExceeded)?;
+
+    if magic == MagicNumber::P6 {
+        let bytes_per_sample = if maxval < 256 { 1 } else { 2 };
+        limits
+            .check_raster(
+                width,
+                height,
+                raster::sample_count(height, width, 3 * bytes_per_sample),
+            )
+            .map_err(PPMDecodeError::LimitExceeded)?;
+    }
+
+    Ok(())
+}
+
+/// Packs samples into an 8 bit raster when the maxval is up to 255, otherwise a 16 bit raster.
+fn build_raster(
+    samples: Vec<u16>,
+    rows: usize,
+    cols: usize,
+    maxval: u16,
+) -> Result<Raster, PPMDecodeError> {
+    let raster = if maxval < 256 {
+        // RGB - 0x00_RR_GG_BB
+        let data: Vec<u32> = samples
+            .chunks_exact(3)
+            .map(|rgb| {
+                let [r, g, b] = [rgb[0], rgb[1], rgb[2]].map(|s| rescale(s, maxval, 255) as u32);
+                (r << 16) | (g << 8) | b
+            })
+            .collect();
+
+        Raster::Rgb8(
+            Image::builder()
+                .rows(rows)
+                .cols(cols)
+                .data(data)
+                .build()
+                .map_err(|e| PPMDecodeError::MalformedHeader(e.to_string()))?,
+        )
+    } else {
+        let data: Vec<Rgb16> = samples
+            .chunks_exact(3)
+            .map(|rgb| Rgb16([rgb[0], rgb[1], rgb[2]].map(|s| rescale(s, maxval, 65535))))
+            .collect();
+
+        Raster::Rgb16(
+            ImageBuilder::<Rgb16>::new()
+                .rows(rows)
+                .cols(cols)
+                .data(data)
+                .build()
+                .map_err(|e| PPMDecodeError::MalformedHeader(e.to_string()))?,
+        )
+    };
+
+    Ok(raster)
+}
+
+/// How strictly a PPMDecoder treats images that deviate from the spec.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum DecodeMode {
+    /// Rejects any deviation with a PPMDecodeError.
+    #[default]
+    Strict,
+    /// Recovers from deviations where possible, reporting each one as a PPMDecodeWarning.
+    Lenient,
+}
+
+/// Deviations from the spec that a lenient PPMDecoder recovered from.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PPMDecodeWarning {
+    /// A maxval of 0, which was replaced by 255.
+    InvalidMaxval(String),
+    /// Extra line breaks between the header & a binary raster, such as the line feed of a CRLF
+    /// after the maxval, which were skipped.
+    NonStandardWhitespace(String),
+    /// A raster shorter than declared in the header, which was padded with black.
+    NotEnoughPixelData(String),
+    /// Pixel data following the raster declared in the header, which was ignored.
+    TooMuchPixelData(String),
+    /// Samples larger than the maxval, which were clamped to it.
+    SampleOutOfRange(String),
+}
+
+impl Display for PPMDecodeWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PPMDecodeWarning::InvalidMaxval(msg) => {
+                write!(f, "{}", msg)
+            }
+            PPMDecodeWarning::NonStandardWhitespace(msg) => {
+                write!(f, "{}", msg)
+            }
+            PPMDecodeWarning::NotEnoughPixelData(msg) => {
+                write!(f, "{}", msg)
+            }
+            PPMDecodeWarning::TooMuchPixelData(msg) => {
+                write!(f, "{}", msg)
+            }
+            PPMDecodeWarning::SampleOutOfRange(msg) => {
+                write!(f, "{}", msg)
+            }
+        }
+    }
+}
+
+/// Decodes whole .ppm files, either strictly or leniently
+///
+/// Unlike PPMImage::read & `decode`, which stop at the end of the raster, a PPMDecoder treats
+/// anything but whitespace & comments after the raster as too much pixel data. The default
+/// Limits apply unless others are set, including to lenient mode, which pads short rasters up
+/// to the size declared in the header.
+///
+/// # Example
+///
+/// ```
+/// use libppm::ppm::{DecodeMode, PPMDecodeWarning, PPMDecoder};
+///
+/// // Declares 2 pixels, but only holds one
+/// let bytes = b"P6\n2 1\n255\n\xFF\x00\x00";
+/// assert!(PPMDecoder::new().decode(bytes).is_err());
+///
+/// let (ppm_image, warnings) = PPMDecoder::new()
+///     .mode(DecodeMode::Lenient)
+///     .decode(bytes)
+///     .unwrap();
+/// assert!(matches!(warnings[..], [PPMDecodeWarning::NotEnoughPixelData(_)]));
+/// assert_eq!(ppm_image.into_image().get_data(), &vec![0xFF_00_00, 0x00_00_00]);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct PPMDecoder {
+    mode: DecodeMode,
+    limits: Limits,
+}
+
+impl PPMDecoder {
+    pub fn new() -> Self {
+        PPMDecoder {
+            mode: DecodeMode::Strict,
+            limits: Limits::default(),
+        }
+    }
+
+    pub fn mode(&mut self, mode: DecodeMode) -> &mut Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Replaces all of the limits at once, e.g. with Limits::unlimited() for trusted input.
+    pub fn limits(&mut self, limits: Limits) -> &mut Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Rejects images wider than `max_width` columns. Unlimited by default.
+    pub fn max_width(&mut self, max_width: usize) -> &mut Self {
+        self.limits.max_width(max_width);
+        self
+    }
+
+    /// Rejects images taller than `max_height` rows. Unlimited by default.
+    pub fn max_height(&mut self, max_height: usize) -> &mut Self {
+        self.limits.max_height(max_height);
+        self
+    }
+
+    /// Rejects images with more than `max_pixels` pixels in total. DEFAULT_MAX_PIXELS by
+    /// default.
+    pub fn max_pixels(&mut self, max_pixels: usize) -> &mut Self {
+        self.limits.max_pixels(max_pixels);
+        self
+    }
+
+    /// Rejects files longer than `max_bytes`, along with binary images whose header declares a
+    /// raster longer than `max_bytes`. DEFAULT_MAX_BYTES by default.
+    pub fn max_bytes(&mut self, max_bytes: usize) -> &mut Self {
+        self.limits.max_bytes(max_bytes);
+        self
+    }
+
+    /// Reads a .ppm file, returning any warnings along with the image
+    ///
+    /// # Errors
+    ///
+    /// See PPMDecoder::decode
+    pub fn read(
+        &self,
+        filename: &str,
+    ) -> Result<(PPMImage, Vec<PPMDecodeWarning>), PPMDecodeError> {
+        let file = fs::File::open(filename)?;
+
+        // Never read more than one byte past the limit, whatever the size of the file
+        let mut bytes: Vec<u8> = Vec::new();
+        match *self.limits.get_max_bytes() {
+            Some(max_bytes) => file
+                .take((max_bytes as u64).saturating_add(1))
+                .read_to_end(&mut bytes)?,
+            None => BufReader::new(file).read_to_end(&mut bytes)?,
+        };
+
+        let (mut ppm_image, warnings) = self.decode(&bytes)?;
+        ppm_image.filename = filename.to_string();
+        Ok((ppm_image, warnings))
+    }
+
+    /// Decodes the bytes of a whole .ppm file, returning any warnings along with the image
+    ///
+    /// # Errors
+    ///
+    /// Will return PPMDecodeError::LimitExceeded if the input or the dimensions declared in the
+    /// header exceed a limit, or another PPMDecodeError if the header is malformed or the raster
+    /// has invalid characters. In strict mode, an error is also returned for every deviation
+    /// that lenient mode would warn about, with extra line breaks before a binary raster
+    /// reported as a malformed header. Any other extra whitespace after the header of a binary
+    /// raster can't be told apart from pixel data, so it's reported as too much pixel data.
+    pub fn decode(
+        &self,
+        bytes: &[u8],
+    ) -> Result<(PPMImage, Vec<PPMDecodeWarning>), PPMDecodeError> {
+        let lenient = self.mode == DecodeMode::Lenient;
+        let mut warnings: Vec<PPMDecodeWarning> = Vec::new();
+
+        self.limits
+            .check_input(bytes.len())
+            .map_err(PPMDecodeError::LimitExceeded)?;
+
+        let mut reader = bytes;
+        let (mut header, maxval_position) = PnmHeader::read_any_maxval(&mut reader)?;
+        check_magic_number(header.magic)?;
+        check_limits(&header, &self.limits)?;
+
+        if let (0, Some(position)) = (header.maxval, maxval_position) {
+            if !lenient {
+                return Err(PPMDecodeError::Header(HeaderError::InvalidMaxval(
+                    0, position,
+                )));
+            }
+            warnings.push(PPMDecodeWarning::InvalidMaxval(format!(
+                "Maxval 0 at {} is invalid, using 255 instead.",
+                position
+            )));
+            header.maxval = 255;
+        }
+
+        let PnmHeader {
+            magic,
+            width: cols,
+            height: rows,
+            maxval,
+        } = header;
+        let encoding = magic.encoding();
+        let expected = expected_samples(rows, cols)?;
+
+        // Extra line breaks before a binary raster are only recognized when skipping them leaves
+        // exactly the bytes the raster needs. Otherwise, & for any other whitespace, which is
+        // just as valid a sample, the bytes are read as the raster followed by too much pixel data.
+        if encoding == Encoding::Raw {
+            let bytes_per_sample = if maxval < 256 { 1 } else { 2 };
+            let extra = reader
+                .len()
+                .saturating_sub(expected.saturating_mul(bytes_per_sample));
+
+            if extra > 0
+                && reader[..extra]
+                    .iter()
+                    .all(|byte| matches!(byte, b'\r' | b'\n'))
+            {
+                let msg = format!(
+                    "Found {} extra line breaks, \"{}\", between the header & the raster, which \
+                    must be separated by a single whitespace character.",
+                    extra,
+                    reader[..extra].escape_ascii()
+                );
+                if !lenient {
+                    return Err(PPMDecodeError::MalformedHeader(msg));
+                }
+                warnings.push(PPMDecodeWarning::NonStandardWhitespace(msg));
+                reader = &reader[extra..];
+            }
+        }
+
+        let mut samples = raster::read_available(&mut reader, expected, maxval, encoding)?;
+
+        if samples.len() < expected {
+            let msg = format!(
+                "Not enough pixel data. Expected {} samples, but found {}.",
+                expected,
+                samples.len()
+            );
+            if !lenient {
+                return Err(PPMDecodeError::TruncatedPixelData(msg));
+            }
+            warnings.push(PPMDecodeWarning::NotEnoughPixelData(msg));
+            samples.resize(expected, 0);
+        }
+
+        let out_of_range = samples.iter().filter(|sample| **sample > maxval).count();
+        if out_of_range > 0 {
+            let msg = format!(
+                "Found {} samples larger than the maxval {}.",
+                out_of_range, maxval
+            );
+            if !lenient {
+                return Err(PPMDecodeError::InvalidPixelData(msg));
+            }
+            warnings.push(PPMDecodeWarning::SampleOutOfRange(msg));
+            samples
+                .iter_mut()
+                .for_each(|sample| *sample = (*sample).min(maxval));
+        }
+
+        let surplus = raster::surplus(reader, encoding)?;
+        if surplus > 0 {
+            let msg = format!(
+                "Too much pixel data. Found {} bytes after the raster.",
+                surplus
+            );
+            if !lenient {
+                return Err(PPMDecodeError::TooMuchPixelData(msg));
+            }
+            warnings.push(PPMDecodeWarning::TooMuchPixelData(msg));
+        }
+
+        let ppm_image = PPMImage {
+            raster: build_raster(samples, rows, cols, maxval)?,
+            // Keep the header in the same form that PPMImageBuilder::build produces
+            header: header.encode(&[]),
+            encoding,
+            maxval,
+            filename: String::new(),
+        };
+
+        Ok((ppm_image, warnings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::{BLUE, MAGENTA, RED, TEAL, WHITE};
+    use crate::coordinate::Coordinate;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_read_round_trip() -> Result<(), Box<dyn Error>> {
+        let mut image = Image::builder().rows(48).cols(64).build()?;
+        image.fill(MAGENTA).checkerboard(8, TEAL);
+        image.set_pixel(Coordinate::new(47, 63), BLUE)?;
+
+        PPMImage::builder()
+            .image(&image)
+            .filename("test_read_round_trip.ppm")
+            .comment("written by test_read_round_trip")
+            .build()?
+            .write()?;
+
+        let read_image = Image::from_ppm("test_read_round_trip.ppm")?;
+        assert_eq!(read_image, image);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_plain_round_trip() -> Result<(), Box<dyn Error>> {
+        let mut image = Image::builder().rows(5).cols(7).build()?;
+        image.fill(MAGENTA).checkerboard(2, TEAL);
+
+        let ppm_image = PPMImage::builder()
+            .image(&image)
+            .filename("test_plain_round_trip.ppm")
+            .encoding(Encoding::Plain)
+            .build()?;
+        ppm_image.write()?;
+
+        let bytes = fs::read("test_plain_round_trip.ppm")?;
+        assert!(bytes.starts_with(b"P3\n7 5\n255\n"));
+        assert!(bytes
+            .split(|byte| *byte == b'\n')
+            .all(|line| line.len() <= raster::PLAIN_LINE_LENGTH));
+
+        let read_ppm_image = PPMImage::read("test_plain_round_trip.ppm")?;
+        assert_eq!(read_ppm_image, ppm_image);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_plain() -> Result<(), Box<dyn Error>> {
+        let image = decode(b"P3\n# comment\n2 1\n255\n255 0 0 # red\n\t0 0 255")?;
+        assert_eq!(image.get_data(), &vec![RED.to_u32(), BLUE.to_u32()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_16_bit_round_trip() -> Result<(), Box<dyn Error>> {
+        let data: Vec<Rgb16> = (0..12u16)
+            .map(|i| Rgb16([i * 5000, 65535 - i, i]))
+            .collect();
+        let image16 = ImageBuilder::<Rgb16>::new()
+            .rows(3)
+            .cols(4)
+            .data(data)
+            .build()?;
+
+        for encoding in [Encoding::Raw, Encoding::Plain] {
+            let ppm_image = PPMImage::builder()
+                .image16(&image16)
+                .filename("test_16_bit_round_trip.ppm")
+                .encoding(encoding)
+                .build()?;
+            ppm_image.write()?;
+
+            let read_ppm_image = PPMImage::read("test_16_bit_round_trip.ppm")?;
+            assert_eq!(read_ppm_image, ppm_image);
+            assert_eq!(read_ppm_image.into_image16(), image16);
+        }
+
+        let bytes = fs::read("test_16_bit_round_trip.ppm")?;
+        assert!(bytes.starts_with(b"P3\n4 3\n65535\n0 65535 0 5000 65534 1 10000"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rescaled_maxval() -> Result<(), Box<dyn Error>> {
+        // Samples are rescaled from the maxval of the file to 255
+        let image = decode(b"P6\n2 1\n3\n\x00\x01\x02\x03\x03\x03")?;
+        assert_eq!(image.get_data(), &vec![0x00_00_55_AA, WHITE.to_u32()]);
+
+        // Big-endian samples are rescaled from the maxval of the file to 255
+        let image = decode(b"P6\n1 1\n1000\n\x03\xE8\x01\xF4\x00\x00")?;
+        assert_eq!(image.get_data(), &vec![0x00_FF_80_00]);
+
+        let mut image = Image::builder().rows(2).cols(2).build()?;
+        image.fill(MAGENTA).checkerboard(1, TEAL);
+
+        let ppm_image = PPMImage::builder()
+            .image(&image)
+            .filename("test_rescaled_maxval.ppm")
+            .maxval(1023)
+            .build()?;
+        ppm_image.write()?;
+
+        let bytes = fs::read("test_rescaled_maxval.ppm")?;
+        assert_eq!(
+            &bytes[..20],
+            b"P6\n2 2\n1023\n\x00\x00\x02\x02\x02\x02\x03\xFF"
+        );
+        assert_eq!(Image::from_ppm("test_rescaled_maxval.ppm")?, image);
+
+        Ok(())
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_get_image() -> Result<(), Box<dyn Error>> {
+        let mut bytes: &[u8] = b"P6\n2 1\n255\n\xFF\x00\x00\x00\x00\xFF";
+        let ppm_image = PPMImage::read_from(&mut bytes)?;
+        assert!(matches!(ppm_image.get_image(), Cow::Borrowed(_)));
+        assert_eq!(*ppm_image.get_image(), ppm_image.clone().into_image());
+
+        // 16-bit samples are rounded, as by into_image
+        let mut bytes: &[u8] = b"P6\n1 1\n65535\n\xFF\xFF\x80\x00\x00\x00";
+        let ppm_image = PPMImage::read_from(&mut bytes)?;
+        assert_eq!(ppm_image.get_image().get_data(), &vec![0x00_FF_80_00]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_to_and_read_from() -> Result<(), Box<dyn Error>> {
+        let mut image = Image::builder().rows(3).cols(5).build()?;
+        image.fill(RED).set_pixel(Coordinate::new(2, 4), WHITE)?;
+
+        for encoding in [Encoding::Raw, Encoding::Plain] {
+            let mut buffer: Vec<u8> = Vec::new();
+            PPMImage::builder()
+                .image(&image)
+                .filename("unused.ppm")
+                .encoding(encoding)
+                .build()?
+                .write_to(&mut buffer)?;
+            buffer.extend_from_slice(b"trailing");
+
+            let mut reader = io::Cursor::new(buffer);
+            let ppm_image = PPMImage::read_from(&mut reader)?;
+            assert_eq!(ppm_image.filename, "");
+            assert_eq!(ppm_image.into_image(), image);
+
+            // Only the image should have been consumed
+            let mut rest = Vec::new();
+            reader.read_to_end(&mut rest)?;
+            assert_eq!(rest, b"trailing");
+        }
+
+        Ok(())
+    }
+
+    struct FailingWriter;
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk full"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_to_propagates_errors() -> Result<(), Box<dyn Error>> {
+        let image = Image::builder().rows(2).cols(2).build()?;
+        let result = PPMImage::builder()
+            .image(&image)
+            .filename("unused.ppm")
+            .build()?
+            .write_to(&mut FailingWriter);
+
+        assert_eq!(result.unwrap_err().to_string(), "disk full");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_alpha_is_flattened() -> Result<(), Box<dyn Error>> {
+        let sprite = Image::builder()
+            .rows(1)
+            .cols(3)
+            .data(vec![0xFF_FF_00_00, 0x80_FF_00_00, 0x00_FF_00_00])
+            .color_mode(ColorMode::Rgba)
+            .build()?;
+
+        let mut bytes: Vec<u8> = Vec::new();
+        PPMImage::builder()
+            .image(&sprite)
+            .filename("unused.ppm")
+            .build()?
+            .write_to(&mut bytes)?;
+        assert_eq!(
+            decode(&bytes)?.get_data(),
+            &vec![RED.to_u32(), 0xFF_7F_7F, WHITE.to_u32()]
+        );
+
+        bytes.clear();
+        PPMImage::builder()
+            .image(&sprite)
+            .filename("unused.ppm")
+            .background(BLUE)
+            .build()?
+            .write_to(&mut bytes)?;
+        assert_eq!(
+            decode(&bytes)?.get_data(),
+            &vec![RED.to_u32(), 0x80_00_7F, BLUE.to_u32()]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_frames_round_trip() -> Result<(), Box<dyn Error>> {
+        let mut frames: Vec<Image> = Vec::new();
+        for (index, color) in [RED, TEAL, BLUE].iter().enumerate() {
+            let mut image = Image::builder().rows(4 + index).cols(6).build()?;
+            image
+                .fill(*color)
+                .set_pixel(Coordinate::new(index as i32, index as i32), WHITE)?;
+            frames.push(image);
+        }
+
+        for encoding in [Encoding::Raw, Encoding::Plain] {
+            let mut writer = PPMFrameWriter::new(Vec::new(), encoding);
+            for frame in frames.iter() {
+                writer.write_frame(frame)?;
+            }
+            assert_eq!(writer.get_frame_count(), &3);
+
+            let stream = writer.into_inner();
+            let read_frames = PPMFrames::new(stream.as_slice()).collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(read_frames, frames);
+        }
+
+        // Frames can also be read back from a file
+        let mut writer = PPMFrameWriter::new(fs::File::create("test_frames.ppm")?, Encoding::Raw);
+        for frame in frames.iter() {
+            writer.write_frame(frame)?;
+        }
+        writer.flush()?;
+
+        let reader = BufReader::new(fs::File::open("test_frames.ppm")?);
+        let read_frames = PPMFrames::new(reader).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(read_frames, frames);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_frames_end_of_stream() {
+        assert_eq!(PPMFrames::new(&b""[..]).count(), 0);
+        assert_eq!(PPMFrames::new(&b" \n"[..]).count(), 0);
+
+        // Iteration stops after the first error
+        let mut frames = PPMFrames::new(&b"P6 1 1 255 \x00\x00\x00P6 1 1 255 \x00"[..]);
+        assert!(matches!(frames.next(), Some(Ok(_))));
+        assert!(matches!(
+            frames.next(),
+            Some(Err(PPMDecodeError::TruncatedPixelData(_)))
+        ));
+        assert!(frames.next().is_none());
+    }
+
+    #[test]
+    fn test_row_writer() -> Result<(), Box<dyn Error>> {
+        let mut image = Image::builder().rows(5).cols(4).build()?;
+        image.fill(MAGENTA).checkerboard(1, TEAL);
+        let data = image.get_data();
+
+        for encoding in [Encoding::Raw, Encoding::Plain] {
+            for maxval in [255, 1000] {
+                let mut writer = PPMRowWriterBuilder::new()
+                    .rows(5)
+                    .cols(4)
+                    .encoding(encoding)
+                    .maxval(maxval)
+                    .build(Vec::new())?;
+                writer.write_row(&data[..4])?;
+                writer.write_rows(&data[4..12])?;
+                writer.write_rows(&data[12..])?;
+                assert_eq!(writer.get_rows_written(), &5);
+
+                assert_eq!(decode(&writer.finish()?)?, image);
+            }
+        }
+
+        // The output is identical to writing the whole image at once
+        let mut expected: Vec<u8> = Vec::new();
+        PPMImage::builder()
+            .image(&image)
+            .filename("unused.ppm")
+            .build()?
+            .write_to(&mut expected)?;
+
+        let mut writer = PPMRowWriterBuilder::new()
+            .rows(5)
+            .cols(4)
+            .build(Vec::new())?;
+        writer.write_rows(data)?;
+        assert_eq!(writer.finish()?, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_row_writer_errors() -> Result<(), Box<dyn Error>> {
+        assert!(matches!(
+            PPMRowWriterBuilder::new().rows(0).cols(4).build(Vec::new()),
+            Err(PPMRowWriterError::InvalidDimensions(_))
+        ));
+        assert!(matches!(
+            PPMRowWriterBuilder::new().cols(4).build(Vec::new()),
+            Err(PPMRowWriterError::InvalidDimensions(_))
+        ));
+
+        let mut writer = PPMRowWriterBuilder::new()
+            .rows(2)
+            .cols(4)
+            .build(Vec::new())?;
+        assert!(matches!(
+            writer.write_row(&[RED.to_u32(); 3]),
+            Err(PPMRowWriterError::RowLengthMismatch(_))
+        ));
+        assert!(matches!(
+            writer.write_rows(&[RED.to_u32(); 6]),
+            Err(PPMRowWriterError::RowLengthMismatch(_))
+        ));
+        assert!(matches!(
+            writer.write_rows(&[RED.to_u32(); 12]),
+            Err(PPMRowWriterError::TooManyRows(_))
+        ));
+
+        // Failed writes don't count towards the declared rows
+        writer.write_row(&[RED.to_u32(); 4])?;
+        assert!(matches!(
+            writer.finish(),
+            Err(PPMRowWriterError::NotEnoughRows(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_view() -> Result<(), Box<dyn Error>> {
+        let mut image = Image::builder().rows(3).cols(4).build()?;
+        image.fill(MAGENTA).set_pixel(Coordinate::new(1, 2), TEAL)?;
+
+        for maxval in [255, 1000] {
+            let mut bytes: Vec<u8> = Vec::new();
+            PPMImage::builder()
+                .image(&image)
+                .filename("unused.ppm")
+                .maxval(maxval)
+                .build()?
+                .write_to(&mut bytes)?;
+            bytes.extend_from_slice(b"trailing");
+
+            let view = PPMView::new(&bytes)?;
+            assert_eq!((view.get_rows(), view.get_cols()), (&3, &4));
+            assert_eq!(view.get_pixel(Coordinate::new(1, 2)), Some(TEAL.to_u32()));
+            assert_eq!(
+                view.get_pixel(Coordinate::new(2, 3)),
+                Some(MAGENTA.to_u32())
+            );
+            assert_eq!(view.get_pixel(Coordinate::new(3, 0)), None);
+            assert_eq!(view.get_pixel(Coordinate::new(0, 4)), None);
+            assert_eq!(view.get_pixel(Coordinate::new(-1, 0)), None);
+            assert_eq!(view.to_image(), image);
+
+            // Row slices borrow the raster directly
+            let row_length = 4 * if maxval < 256 { 3 } else { 6 };
+            assert_eq!(view.row_slices().count(), 3);
+            assert_eq!(view.get_row(2).map(<[u8]>::len), Some(row_length));
+            assert_eq!(view.get_row(3), None);
+            assert_eq!(view.get_row(usize::MAX), None);
+            assert!(std::ptr::eq(
+                view.get_row(0).unwrap(),
+                &bytes[bytes.len() - 8 - 3 * row_length..][..row_length]
+            ));
+        }
+
+        assert_eq!(
+            PPMView::new(b"P6 1 1 15 \x10\x00\x00")?.get_samples(Coordinate::new(0, 0)),
+            Some([15, 0, 0])
+        );
+        assert!(matches!(
+            PPMView::new(b"P3 1 1 255 0 0 0"),
+            Err(PPMDecodeError::InvalidMagicNumber(_))
+        ));
+        assert!(matches!(
+            PPMView::new(b"P6 2 1 255 \x00\x00\x00"),
+            Err(PPMDecodeError::TruncatedPixelData(_))
+        ));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_mapped_ppm() -> Result<(), Box<dyn Error>> {
+        let mut image = Image::builder().rows(8).cols(8).build()?;
+        image.fill(WHITE).checkerboard(2, BLUE);
+
+        PPMImage::builder()
+            .image(&image)
+            .filename("test_mapped_ppm.ppm")
+            .build()?
+            .write()?;
+
+        let mapped = MappedPPM::open("test_mapped_ppm.ppm")?;
+        assert_eq!(mapped.view().to_image(), image);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decoder_modes() -> Result<(), Box<dyn Error>> {
+        let mut lenient = PPMDecoder::new();
+        lenient.mode(DecodeMode::Lenient);
+        let strict = PPMDecoder::new();
+
+        // Well formed images decode identically & without warnings in both modes
+        let bytes = b"P3\n2 1\n255\n255 0 0 0 0 255\n# trailing comment\n";
+        let (ppm_image, warnings) = strict.decode(bytes)?;
+        assert!(warnings.is_empty());
+        assert_eq!(lenient.decode(bytes)?, (ppm_image.clone(), vec![]));
+        assert_eq!(
+            ppm_image.into_image().get_data(),
+            &vec![RED.to_u32(), BLUE.to_u32()]
+        );
+
+        let decode = |bytes: &[u8]| -> Result<(Vec<u32>, Vec<PPMDecodeWarning>), PPMDecodeError> {
+            let (ppm_image, warnings) = lenient.decode(bytes)?;
+            Ok((ppm_image.into_image().get_data().clone(), warnings))
+        };
+
+        let bytes = b"P6\n2 1\n0\n\xFF\x00\x00\x00\x00\xFF";
+        assert!(matches!(
+            strict.decode(bytes),
+            Err(PPMDecodeError::Header(_))
+        ));
+        let (data, warnings) = decode(bytes)?;
+        assert_eq!(data, vec![RED.to_u32(), BLUE.to_u32()]);
+        assert!(matches!(warnings[..], [PPMDecodeWarning::InvalidMaxval(_)]));
+
+        // The extra line feed of a CRLF after the maxval is named, rather than read as pixel data
+        let bytes = b"P6\n2 1\n255\r\n\xFF\x00\x00\x00\x00\xFF";
+        match strict.decode(bytes) {
+            Err(PPMDecodeError::MalformedHeader(msg)) => {
+                assert!(msg.contains("\"\\n\""), "{}", msg)
+            }
+            result => panic!("Expected a malformed header, but found {:?}", result),
+        }
+        let (data, warnings) = decode(bytes)?;
+        assert_eq!(data, vec![RED.to_u32(), BLUE.to_u32()]);
+        assert!(matches!(
+            warnings[..],
+            [PPMDecodeWarning::NonStandardWhitespace(_)]
+        ));
+
+        // Line breaks are only skipped when the rest is exactly the raster, & spaces never are, so
+        // an over-long raster that starts with a whitespace sample keeps it
+        let bytes = b"P6\n1 1\n255\n\x20\x00\x00\xFF";
+        let (data, warnings) = decode(bytes)?;
+        assert_eq!(data, vec![0x20_00_00]);
+        assert!(matches!(
+            warnings[..],
+            [PPMDecodeWarning::TooMuchPixelData(_)]
+        ));
+        let bytes = b"P6\n1 1\n255\n\n\n\xFF\x00\x00\x00";
+        let (data, warnings) = decode(bytes)?;
+        assert_eq!(data, vec![0x0A_0A_FF]);
+        assert!(matches!(
+            warnings[..],
+            [PPMDecodeWarning::TooMuchPixelData(_)]
+        ));
+
+        let bytes = b"P6\n2 1\n255\n\xFF\x00\x00\x00";
+        assert!(matches!(
+            strict.decode(bytes),
+            Err(PPMDecodeError::TruncatedPixelData(_))
+        ));
+        let (data, warnings) = decode(bytes)?;
+        assert_eq!(data, vec![RED.to_u32(), 0]);
+        assert!(matches!(
+            warnings[..],
+            [PPMDecodeWarning::NotEnoughPixelData(_)]
+        ));
+
+        let bytes = b"P3\n1 1\n255\n255 0 0 0 0 255\n";
+        assert!(matches!(
+            strict.decode(bytes),
+            Err(PPMDecodeError::TooMuchPixelData(_))
+        ));
+        let (data, warnings) = decode(bytes)?;
+        assert_eq!(data, vec![RED.to_u32()]);
+        assert!(matches!(
+            warnings[..],
+            [PPMDecodeWarning::TooMuchPixelData(_)]
+        ));
+
+        let bytes = b"P3\n2 1\n100\n300 0 0\n";
+        assert!(matches!(
+            strict.decode(bytes),
+            Err(PPMDecodeError::TruncatedPixelData(_))
+        ));
+        let (data, warnings) = decode(bytes)?;
+        assert_eq!(data, vec![RED.to_u32(), 0]);
+        assert!(matches!(
+            warnings[..],
+            [
+                PPMDecodeWarning::NotEnoughPixelData(_),
+                PPMDecodeWarning::SampleOutOfRange(_)
+            ]
+        ));
+
+        // Malformed headers & invalid characters can't be recovered from
+        assert!(matches!(
+            lenient.decode(b"P6\n2\n255\n"),
+            Err(PPMDecodeError::Header(_))
+        ));
+        assert!(matches!(
+            lenient.decode(b"P3\n1 1\n255\n0 x 0\n"),
+            Err(PPMDecodeError::InvalidPixelData(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decoder_limits() -> Result<(), Box<dyn Error>> {
+        let mut decoder = PPMDecoder::new();
+        decoder
+            .max_width(4)
+            .max_height(3)
+            .max_pixels(10)
+            .max_bytes(40);
+
+        let image = Image::builder().rows(3).cols(3).build()?;
+        let mut bytes: Vec<u8> = Vec::new();
+        PPMImage::builder()
+            .image(&image)
+            .filename("unused.ppm")
+            .build()?
+            .write_to(&mut bytes)?;
+        assert_eq!(decoder.decode(&bytes)?.0.into_image(), image);
+
+        // Limits are checked from the header alone, before any pixel data
+        for header in [
+            &b"P6\n5 1\n255\n"[..],
+            b"P6\n1 4\n255\n",
+            b"P6\n4 3\n255\n",
+            b"P6\n3 3\n65535\n",
+            b"P6\n100000 100000\n255\n",
+            b"P3\n18446744073709551615 18446744073709551615\n255\n",
+        ] {
+            assert!(matches!(
+                decoder.decode(header),
+                Err(PPMDecodeError::LimitExceeded(_))
+            ));
+        }
+
+        bytes.extend_from_slice(&[b' '; 64]);
+        assert!(matches!(
+            decoder.decode(&bytes),
+            Err(PPMDecodeError::LimitExceeded(_))
+        ));
+
+        // Dimensions that overflow are rejected without limits as well
+        let mut bytes: &[u8] = b"P6\n18446744073709551615 2\n255\n";
+        assert!(matches!(
+            PPMDecoder::new().limits(Limits::unlimited()).decode(bytes),
+            Err(PPMDecodeError::MalformedHeader(_))
+        ));
+        assert!(matches!(
+            PPMImage::read_from_with_limits(&mut bytes, &Limits::unlimited()),
+            Err(PPMDecodeError::MalformedHeader(_))
+        ));
+
+        // Every entry point applies the default limits, including lenient mode, which would
+        // otherwise pad the missing raster with gigabytes of black
+        let header = b"P6\n100000 100000\n255\n";
+        for decoder in [
+            PPMDecoder::new(),
+            PPMDecoder::new().mode(DecodeMode::Lenient).clone(),
+        ] {
+            assert!(matches!(
+                decoder.decode(header),
+                Err(PPMDecodeError::LimitExceeded(_))
+            ));
+        }
+        assert!(matches!(
+            decode(header),
+            Err(PPMDecodeError::LimitExceeded(_))
+        ));
+        assert!(matches!(
+            PPMFrames::new(&header[..]).next(),
+            Some(Err(PPMDecodeError::LimitExceeded(_)))
+        ));
+        assert!(matches!(
+            PPMFrames::new(&b"P6 2 1 255 \x00\x00\x00\x00\x00\x00"[..])
+                .limits(*Limits::new().max_width(1))
+                .next(),
+            Some(Err(PPMDecodeError::LimitExceeded(_)))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!(matches!(
+            decode(b"P5\n1 1\n255\n\x00"),
+            Err(PPMDecodeError::InvalidMagicNumber(_))
+        ));
+        assert!(matches!(
+            decode(b"P3\n1 1\n255\n0 256 0\n"),
+            Err(PPMDecodeError::InvalidPixelData(_))
+        ));
+        assert!(matches!(
+            decode(b"P3\n1 1\n255\n0 x 0\n"),
+            Err(PPMDecodeError::InvalidPixelData(_))
+        ));
+        assert!(matches!(
+            decode(b"P3\n2 1\n255\n0 0 0 0\n"),
+            Err(PPMDecodeError::TruncatedPixelData(_))
+        ));
+        assert!(matches!(
+            decode(b"P6\n1\n255\n\x00\x00\x00"),
+            Err(PPMDecodeError::Header(_))
+        ));
+        assert!(matches!(
+            decode(b"P6\n0 1\n255\n"),
+            Err(PPMDecodeError::MalformedHeader(_))
+        ));
+        assert!(matches!(
+            decode(b"P6\n1 1\n15\n\x00\x10\x00"),
+            Err(PPMDecodeError::InvalidPixelData(_))
+        ));
+        assert!(matches!(
+            decode(b"P6\n1 1\n1000\n\x00\x00\x00\x00\x00"),
+            Err(PPMDecodeError::TruncatedPixelData(_))
+        ));
+        assert!(matches!(
+            decode(b"P6\n2 1\n255\n\x00\x00\x00\x00"),
+            Err(PPMDecodeError::TruncatedPixelData(_))
+        ));
+    }
+
+    fn arbitrary_image() -> impl Strategy<Value = Image> {
+        (1usize..24, 1usize..24).prop_flat_map(|(rows, cols)| {
+            proptest::collection::vec(0u32..=0xFF_FF_FF, rows * cols).prop_map(move |data| {
+                Image::builder()
+                    .rows(rows)
+                    .cols(cols)
+                    .data(data)
+                    .build()
+                    .unwrap()
+            })
+        })
+    }
+
+    fn arbitrary_image16() -> impl Strategy<Value = Image<Rgb16>> {
+        (1usize..16, 1usize..16).prop_flat_map(|(rows, cols)| {
+            proptest::collection::vec(any::<[u16; 3]>(), rows * cols).prop_map(move |data| {
+                ImageBuilder::<Rgb16>::new()
+                    .rows(rows)
+                    .cols(cols)
+                    .data(data.into_iter().map(Rgb16).collect())
+                    .build()
+                    .unwrap()
+            })
+        })
+    }
+
+    fn arbitrary_encoding() -> impl Strategy<Value = Encoding> {
+        prop_oneof![Just(Encoding::Raw), Just(Encoding::Plain)]
+    }
+
+    proptest! {
+        #[test]
+        fn test_round_trip_property(image in arbitrary_image(), encoding in arbitrary_encoding()) {
+            let mut bytes: Vec<u8> = Vec::new();
+            PPMImage::builder()
+                .image(&image)
+                .filename("unused.ppm")
+                .encoding(encoding)
+                .build()
+                .unwrap()
+                .write_to(&mut bytes)
+                .unwrap();
+
+            prop_assert_eq!(decode(&bytes).unwrap(), image.clone());
+            prop_assert_eq!(PPMDecoder::new().decode(&bytes).unwrap().0.into_image(), image);
+        }
+
+        #[test]
+        fn test_16_bit_round_trip_property(
+            image in arbitrary_image16(),
+            encoding in arbitrary_encoding(),
+        ) {
+            let mut bytes: Vec<u8> = Vec::new();
+            PPMImage::builder()
+                .image16(&image)
+                .filename("unused.ppm")
+                .encoding(encoding)
+                .maxval(65535)
+                .build()
+                .unwrap()
+                .write_to(&mut bytes)
+                .unwrap();
+
+            let read_image = PPMImage::read_from(&mut bytes.as_slice()).unwrap().into_image16();
+            prop_assert_eq!(read_image, image);
+        }
+
+        #[test]
+        fn test_decoders_never_panic(
+            magic in prop_oneof![Just(&b"P6"[..]), Just(&b"P3"[..]), Just(&b""[..])],
+            rest in proptest::collection::vec(any::<u8>(), 0..64),
+        ) {
+            let bytes = [magic, &rest].concat();
+
+            let _ = decode(&bytes);
+            let _ = PPMView::new(&bytes);
+            let _ = PPMFrames::new(bytes.as_slice()).count();
+            for mode in [DecodeMode::Strict, DecodeMode::Lenient] {
+                let _ = PPMDecoder::new().mode(mode).max_pixels(1 << 16).decode(&bytes);
+            }
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! Reading & writing of the samples that follow a Netpbm header, shared by the Netpbm codecs.
+
+use crate::header::Encoding;
+use std::fs;
+use std::io;
+use std::io::Read;
+
+/// Maximum length of a line in a plain raster, as recommended by the spec.
+pub(crate) const PLAIN_LINE_LENGTH: usize = 70;
+
+#[derive(Debug)]
+pub(crate) enum RasterError {
+    Io(io::Error),
+    TruncatedPixelData(String),
+    InvalidPixelData(String),
+}
+
+impl From<io::Error> for RasterError {
+    fn from(e: io::Error) -> Self {
+        RasterError::Io(e)
+    }
+}
+
+/// Number of samples in a raster of the given dimensions, or None if it doesn't fit a usize.
+pub(crate) fn sample_count(rows: usize, cols: usize, depth: usize) -> Option<usize> {
+    rows.checked_mul(cols)?.checked_mul(depth)
+}
+
+/// Encodes samples into `buffer`.
+///
+/// Raw samples are written with one byte each when the maxval is less than 256, otherwise with
+/// two bytes each, most significant byte first. Plain samples are written as decimal numbers,
+/// wrapping lines so none are longer than PLAIN_LINE_LENGTH.
+pub(crate) fn encode(samples: &[u16], maxval: u16, encoding: Encoding, buffer: &mut Vec<u8>) {
+    match encoding {
+        Encoding::Raw if maxval < 256 => {
+            buffer.extend(samples.iter().map(|sample| *sample as u8));
+        }
+        Encoding::Raw => {
+            buffer.extend(samples.iter().flat_map(|sample| sample.to_be_bytes()));
+        }
+        Encoding::Plain => {
+            let mut line_length = 0;
+            for sample in samples.iter() {
+                let sample = sample.to_string();
+
+                // Wrap before a sample (and its separating space) would exceed the line
+                if line_length > 0 && line_length + 1 + sample.len() > PLAIN_LINE_LENGTH {
+                    buffer.push(b'\n');
+                    line_length = 0;
+                } else if line_length > 0 {
+                    buffer.push(b' ');
+                    line_length += 1;
+                }
+
+                buffer.extend_from_slice(sample.as_bytes());
+                line_length += sample.len();
+            }
+            buffer.push(b'\n');
+        }
+    }
+}
+
+/// Writes the bytes of an encoded image to a file.
+///
+/// The image is encoded before the file is created, so an image that fails to encode can't leave
+/// an existing file truncated.
+pub(crate) fn write_file<E: From<io::Error>>(
+    filename: &str,
+    encode: impl FnOnce() -> Result<Vec<u8>, E>,
+) -> Result<(), E> {
+    let bytes = encode()?;
+    fs::write(filename, bytes)?;
+    Ok(())
+}
+
+/// Reads `expected` samples from `reader`, checking none exceed the maxval.
+///
+/// Only the bytes of the raster are consumed, apart from a plain raster, where the single
+/// whitespace character following the last sample is consumed as well.
+pub(crate) fn read<R: Read>(
+    reader: &mut R,
+    expected: usize,
+    maxval: u16,
+    encoding: Encoding,
+) -> Result<Vec<u16>, RasterError> {
+    let samples = read_available(reader, expected, maxval, encoding)?;
+
+    if samples.len() < expected {
+        return Err(RasterError::TruncatedPixelData(format!(
+            "Not enough pixel data. Expected {} samples, but found {}.",
+            expected,
+            samples.len()
+        )));
+    }
+
+    if let Some(index) = samples.iter().position(|sample| *sample > maxval) {
+        return Err(RasterError::InvalidPixelData(format!(
+            "Sample {} at index {} is larger than the maxval {}.",
+            samples[index], index, maxval
+        )));
+    }
+
+    Ok(samples)
+}
+
+/// Reads up to `expected` samples from `reader`, stopping early without error at the end of
+/// the reader, & without checking samples against the maxval.
+///
+/// The maxval only selects the width of raw samples. A trailing partial raw sample is dropped.
+pub(crate) fn read_available<R: Read>(
+    reader: &mut R,
+    expected: usize,
+    maxval: u16,
+    encoding: Encoding,
+) -> Result<Vec<u16>, RasterError> {
+    match encoding {
+        Encoding::Raw => raw_samples(reader, expected, maxval),
+        Encoding::Plain => plain_samples(reader, expected),
+    }
+}
+
+/// Reads exactly `expected` bytes from `reader`.
+///
+/// # Errors
+///
+/// Will return RasterError::TruncatedPixelData if the reader ends before `expected` bytes.
+pub(crate) fn read_bytes<R: Read>(reader: &mut R, expected: usize) -> Result<Vec<u8>, RasterError> {
+    let mut bytes: Vec<u8> = Vec::new();
+    reader.take(expected as u64).read_to_end(&mut bytes)?;
+
+    if bytes.len() < expected {
+        return Err(RasterError::TruncatedPixelData(format!(
+            "Not enough pixel data. Expected {} bytes, but found {}.",
+            expected,
+            bytes.len()
+        )));
+    }
+
+    Ok(bytes)
+}
+
+fn raw_samples<R: Read>(
+    reader: &mut R,
+    expected: usize,
+    maxval: u16,
+) -> Result<Vec<u16>, RasterError> {
+    let bytes_per_sample = if maxval < 256 { 1 } else { 2 };
+    let mut bytes: Vec<u8> = Vec::new();
+    reader
+        .take(expected.saturating_mul(bytes_per_sample) as u64)
+        .read_to_end(&mut bytes)?;
+
+    Ok(if bytes_per_sample == 1 {
+        bytes.iter().map(|byte| *byte as u16).collect()
+    } else {
+        bytes
+            .chunks_exact(2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+            .collect()
+    })
+}
+
+/// Parses whitespace separated decimal samples, skipping any comments between them.
+fn plain_samples<R: Read>(reader: &mut R, expected: usize) -> Result<Vec<u16>, RasterError> {
+    let mut bytes = ByteReader::new(reader);
+    let mut samples: Vec<u16> = Vec::new();
+
+    while samples.len() < expected {
+        let start = bytes.position();
+        match bytes.next_char()? {
+            None => break,
+            Some(byte) if byte.is_ascii_whitespace() => continue,
+            Some(byte) if byte.is_ascii_digit() => {
+                let mut token = String::from(char::from(byte));
+                loop {
+                    let position = bytes.position();
+                    match bytes.next_char()? {
+                        Some(byte) if byte.is_ascii_digit() => token.push(char::from(byte)),
+                        Some(byte) if byte.is_ascii_whitespace() => break,
+                        None => break,
+                        Some(byte) => return Err(unexpected_character(byte, position)),
+                    }
+                }
+
+                match token.parse::<u16>() {
+                    Ok(sample) => samples.push(sample),
+                    Err(_) => {
+                        return Err(RasterError::InvalidPixelData(format!(
+                            "Sample {} at raster byte {} is larger than the largest maxval 65535.",
+                            token, start
+                        )));
+                    }
+                }
+            }
+            Some(byte) => return Err(unexpected_character(byte, start)),
+        }
+    }
+
+    Ok(samples)
+}
+
+/// Counts the pixel data left in `rest` after a raster, ignoring whitespace & comments after a
+/// plain raster.
+pub(crate) fn surplus(rest: &[u8], encoding: Encoding) -> Result<usize, RasterError> {
+    match encoding {
+        Encoding::Raw => Ok(rest.len()),
+        Encoding::Plain => {
+            let mut reader = rest;
+            let mut bytes = ByteReader::new(&mut reader);
+            let mut surplus = 0;
+            while let Some(byte) = bytes.next_char()? {
+                if !byte.is_ascii_whitespace() {
+                    surplus += 1;
+                }
+            }
+            Ok(surplus)
+        }
+    }
+}
+
+pub(crate) fn unexpected_character(byte: u8, position: usize) -> RasterError {
+    RasterError::InvalidPixelData(format!(
+        "Unexpected character {:?} at raster byte {}.",
+        char::from(byte),
+        position
+    ))
+}
+
+/// Reads a plain raster one byte at a time, keeping track of the position within it.
+pub(crate) struct ByteReader<'a, R: Read> {
+    reader: &'a mut R,
+    position: usize,
+}
+
+impl<'a, R: Read> ByteReader<'a, R> {
+    pub(crate) fn new(reader: &'a mut R) -> Self {
+        ByteReader {
+            reader,
+            position: 0,
+        }
+    }
+
+    pub(crate) fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Reads a single byte, or None at the end of the reader.
+    pub(crate) fn next(&mut self) -> Result<Option<u8>, RasterError> {
+        let mut byte = [0u8; 1];
+        loop {
+            match self.reader.read(&mut byte) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(RasterError::Io(e)),
+            }
+        }
+
+        self.position += 1;
+        Ok(Some(byte[0]))
+    }
+
+    /// Reads a single character, replacing a comment with the newline or carriage return
+    /// that ends it.
+    pub(crate) fn next_char(&mut self) -> Result<Option<u8>, RasterError> {
+        match self.next()? {
+            Some(b'#') => loop {
+                match self.next()? {
+                    Some(terminator @ (b'\n' | b'\r')) => return Ok(Some(terminator)),
+                    None => return Ok(None),
+                    _ => continue,
+                }
+            },
+            byte => Ok(byte),
+        }
+    }
+}
@@ -1,18 +1,20 @@
 use crate::coordinate::CircleCoordinates;
 use crate::coordinate::Coordinate;
+use crate::coordinate::FloatLineCoordinates;
 use crate::coordinate::LineCoordinates;
 use crate::graphics::image::Image;
+use crate::graphics::pixel::Pixel;
 use std::fmt::Display;
 
 #[derive(Debug)]
-pub enum ValidationError {
-    OutOfBoundsInImageError(Coordinate, Image),
-    OutOfBoundsInMemoryError(Coordinate, Image),
-    NotEnoughPixelData(Image),
-    TooMuchPixelData(Image),
+pub enum ValidationError<P: Pixel = u32> {
+    OutOfBoundsInImageError(Coordinate, Image<P>),
+    OutOfBoundsInMemoryError(Coordinate, Image<P>),
+    NotEnoughPixelData(Image<P>),
+    TooMuchPixelData(Image<P>),
 }
 
-impl Display for ValidationError {
+impl<P: Pixel> Display for ValidationError<P> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ValidationError::OutOfBoundsInImageError(coord, image) => {
@@ -28,7 +30,7 @@ impl Display for ValidationError {
                 write!(
                     f,
                     "Not enough pixel data. Expected {}, but found {}.",
-                    image.get_cols() * image.get_rows(),
+                    expected_length(image),
                     image.get_data_length()
                 )
             }
@@ -36,7 +38,7 @@ impl Display for ValidationError {
                 write!(
                     f,
                     "Too much pixel data. Expected {}, but found {}.",
-                    image.get_cols() * image.get_rows(),
+                    expected_length(image),
                     image.get_data_length()
                 )
             }
@@ -55,10 +57,13 @@ impl Display for ValidationError {
     }
 }
 
-impl std::error::Error for ValidationError {}
+impl<P: Pixel> std::error::Error for ValidationError<P> {}
 
 /// Validates that given coordinates are within a given image
-pub fn line_coordinates(image: &Image, coords: &LineCoordinates) -> Result<(), ValidationError> {
+pub fn line_coordinates<P: Pixel>(
+    image: &Image<P>,
+    coords: &LineCoordinates,
+) -> Result<(), ValidationError<P>> {
     let LineCoordinates {
         first: coord_a,
         second: coord_b,
@@ -68,21 +73,34 @@ pub fn line_coordinates(image: &Image, coords: &LineCoordinates) -> Result<(), V
     Ok(())
 }
 
-pub fn coordinate(image: &Image, coord: &Coordinate) -> Result<(), ValidationError> {
+/// Validates that the pixels nearest to given sub-pixel coordinates are within a given image
+pub fn float_line_coordinates<P: Pixel>(
+    image: &Image<P>,
+    coords: &FloatLineCoordinates,
+) -> Result<(), ValidationError<P>> {
+    coordinate(image, &coords.first.nearest())?;
+    coordinate(image, &coords.second.nearest())?;
+    Ok(())
+}
+
+pub fn coordinate<P: Pixel>(
+    image: &Image<P>,
+    coord: &Coordinate,
+) -> Result<(), ValidationError<P>> {
     if coord.x >= (*image.get_rows() as i32)
         || coord.y >= (*image.get_cols() as i32)
         || coord.x < 0
         || coord.y < 0
     {
         return Err(ValidationError::OutOfBoundsInImageError(
-            coord.clone(),
+            *coord,
             image.clone(),
         ));
     }
 
     if ((coord.x as usize * image.get_cols()) + coord.y as usize) > image.get_data_length() {
         return Err(ValidationError::OutOfBoundsInMemoryError(
-            coord.clone(),
+            *coord,
             image.clone(),
         ));
     }
@@ -90,19 +108,44 @@ pub fn coordinate(image: &Image, coord: &Coordinate) -> Result<(), ValidationErr
     Ok(())
 }
 
-pub fn circle_coordinates(image: &Image, coord: &CircleCoordinates) -> Result<(), ValidationError> {
-    // TODO : figure out validation needed for the radius
+/// Validates that a whole circle, from its center out to the radius in every direction, is
+/// within a given image
+pub fn circle_coordinates<P: Pixel>(
+    image: &Image<P>,
+    coord: &CircleCoordinates,
+) -> Result<(), ValidationError<P>> {
     coordinate(image, &coord.center)?;
 
+    // Radii beyond i32::MAX saturate, which is still out of bounds of any image
+    let radius = coord.radius.min(i32::MAX as u32) as i32;
+    let Coordinate { x, y } = coord.center;
+    for extreme in [
+        Coordinate::new(x.saturating_sub(radius), y),
+        Coordinate::new(x.saturating_add(radius), y),
+        Coordinate::new(x, y.saturating_sub(radius)),
+        Coordinate::new(x, y.saturating_add(radius)),
+    ] {
+        coordinate(image, &extreme)?;
+    }
+
     Ok(())
 }
 
-pub fn pixel_data_length(image: &Image) -> Result<(), ValidationError> {
-    if image.get_data_length() > image.get_cols() * image.get_rows() {
-        Err(ValidationError::TooMuchPixelData(image.clone()))
-    } else if image.get_data_length() < image.get_cols() * image.get_rows() {
-        Err(ValidationError::NotEnoughPixelData(image.clone()))
-    } else {
-        Ok(())
+pub fn pixel_data_length<P: Pixel>(image: &Image<P>) -> Result<(), ValidationError<P>> {
+    // Dimensions too large for a usize can never be matched by the pixel data
+    match image.get_cols().checked_mul(*image.get_rows()) {
+        Some(expected) if image.get_data_length() > expected => {
+            Err(ValidationError::TooMuchPixelData(image.clone()))
+        }
+        Some(expected) if image.get_data_length() == expected => Ok(()),
+        _ => Err(ValidationError::NotEnoughPixelData(image.clone())),
+    }
+}
+
+/// Number of pixels expected from the dimensions of an image, for error messages.
+fn expected_length<P: Pixel>(image: &Image<P>) -> String {
+    match image.get_cols().checked_mul(*image.get_rows()) {
+        Some(expected) => expected.to_string(),
+        None => format!("{} by {}", image.get_rows(), image.get_cols()),
     }
 }